@@ -0,0 +1,414 @@
+//! Errors the API sends back to clients.
+//!
+//! Every error response has the same shape: `{"error": "...", "code": "...", "details": {...}}`.
+//! `error` is a message for people and can change; `code` is stable, so clients can react to or
+//! translate specific failures. `details` is only there for errors that have more to say.
+
+use serde_json::{Value, json};
+use utoipa::ToSchema;
+
+use crate::types::FieldError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError {
+    TeamNotFound,
+    /// The team exists, but the admin secret in the URL isn't its secret.
+    WrongSecret,
+    GameNotFound,
+    PlayerNotFound,
+    GuestNotFound,
+    SquadNotFound,
+    JoinRequestNotFound,
+    DocumentNotFound,
+    PushNotConfigured,
+    /// The request body isn't valid JSON, doesn't fit the endpoint, or fails validation.
+    InvalidBody(Vec<FieldError>),
+    /// A change `service` refused, with the reason's own code.
+    Refused(ServiceError),
+    /// A roster CSV that can't be imported, with the reason's own code.
+    Csv(CsvError),
+    /// An archive to import that's bigger than imports allow.
+    ArchiveTooLarge,
+    /// An archive to import that isn't JSON, or isn't an archive. The detail says what's wrong.
+    InvalidArchive(String),
+    /// A document sent to repair a stored one that still can't be read.
+    InvalidDocument { kind: String, detail: String },
+    InvalidSeason,
+    /// The game has no date to put in a calendar.
+    NoDate,
+    /// A squad board request that isn't a WebSocket upgrade.
+    ExpectedWebSocket,
+    NotFound,
+    RsvpClosed,
+    AdminOnly(&'static str),
+    /// A stored document can't be read. `too_new` if a newer server wrote it.
+    UnreadableDocument {
+        kind: String,
+        key: String,
+        detail: String,
+        too_new: bool,
+    },
+    /// Writing to storage failed. The message says what was being saved.
+    Storage(&'static str),
+}
+
+impl ApiError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::TeamNotFound => "team_not_found",
+            ApiError::WrongSecret => "wrong_secret",
+            ApiError::GameNotFound => "game_not_found",
+            ApiError::PlayerNotFound => "player_not_found",
+            ApiError::GuestNotFound => "guest_not_found",
+            ApiError::SquadNotFound => "squad_not_found",
+            ApiError::JoinRequestNotFound => "join_request_not_found",
+            ApiError::DocumentNotFound => "document_not_found",
+            ApiError::PushNotConfigured => "push_not_configured",
+            ApiError::InvalidBody(_) => "invalid_body",
+            ApiError::Refused(err) => err.code(),
+            ApiError::Csv(err) => err.code(),
+            ApiError::ArchiveTooLarge => "archive_too_large",
+            ApiError::InvalidArchive(_) => "invalid_archive",
+            ApiError::InvalidDocument { .. } => "invalid_document",
+            ApiError::InvalidSeason => "invalid_season",
+            ApiError::NoDate => "game_has_no_date",
+            ApiError::ExpectedWebSocket => "expected_websocket",
+            ApiError::NotFound => "not_found",
+            ApiError::RsvpClosed => "rsvp_closed",
+            ApiError::AdminOnly(_) => "admin_only",
+            ApiError::UnreadableDocument { too_new: true, .. } => "document_too_new",
+            ApiError::UnreadableDocument { too_new: false, .. } => "document_unreadable",
+            ApiError::Storage(_) => "storage_failed",
+        }
+    }
+
+    pub fn status(&self) -> u16 {
+        match self {
+            ApiError::TeamNotFound
+            | ApiError::GameNotFound
+            | ApiError::PlayerNotFound
+            | ApiError::GuestNotFound
+            | ApiError::SquadNotFound
+            | ApiError::JoinRequestNotFound
+            | ApiError::DocumentNotFound
+            | ApiError::PushNotConfigured
+            | ApiError::NoDate
+            | ApiError::NotFound => 404,
+            ApiError::ArchiveTooLarge | ApiError::Csv(CsvError::TooLarge) => 413,
+            ApiError::InvalidBody(_)
+            | ApiError::Refused(_)
+            | ApiError::Csv(_)
+            | ApiError::InvalidArchive(_)
+            | ApiError::InvalidDocument { .. }
+            | ApiError::InvalidSeason => 400,
+            ApiError::ExpectedWebSocket => 426,
+            ApiError::WrongSecret | ApiError::RsvpClosed | ApiError::AdminOnly(_) => 403,
+            ApiError::UnreadableDocument { too_new: true, .. } => 422,
+            ApiError::UnreadableDocument { too_new: false, .. } | ApiError::Storage(_) => 500,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            ApiError::TeamNotFound => "team not found".to_string(),
+            ApiError::WrongSecret => "wrong admin secret".to_string(),
+            ApiError::GameNotFound => "game not found".to_string(),
+            ApiError::PlayerNotFound => "player not found".to_string(),
+            ApiError::GuestNotFound => "guest not found".to_string(),
+            ApiError::SquadNotFound => "squad not found".to_string(),
+            ApiError::JoinRequestNotFound => "join request not found".to_string(),
+            ApiError::DocumentNotFound => "document not found".to_string(),
+            ApiError::PushNotConfigured => "push notifications are not configured".to_string(),
+            ApiError::InvalidBody(_) => "invalid request body".to_string(),
+            ApiError::Refused(err) => err.message().to_string(),
+            ApiError::Csv(err) => err.message(),
+            ApiError::ArchiveTooLarge => "archive is too large".to_string(),
+            ApiError::InvalidArchive(_) => "invalid archive".to_string(),
+            ApiError::InvalidDocument { kind, .. } => format!("{} still can't be read", kind),
+            ApiError::InvalidSeason => "season must be a year".to_string(),
+            ApiError::NoDate => "the game has no date".to_string(),
+            ApiError::ExpectedWebSocket => "expected a WebSocket upgrade".to_string(),
+            ApiError::NotFound => "not found".to_string(),
+            ApiError::RsvpClosed => "RSVPs are closed, ask an admin to make changes".to_string(),
+            ApiError::AdminOnly(msg) | ApiError::Storage(msg) => msg.to_string(),
+            ApiError::UnreadableDocument { kind, .. } => format!("stored {} can't be read", kind),
+        }
+    }
+
+    fn details(&self) -> Option<Value> {
+        match self {
+            ApiError::InvalidBody(fields) => Some(json!({ "fields": fields })),
+            ApiError::UnreadableDocument { kind, key, detail, .. } => {
+                Some(json!({ "kind": kind, "key": key, "detail": detail }))
+            }
+            ApiError::InvalidArchive(detail) => Some(json!({ "detail": detail })),
+            ApiError::InvalidDocument { kind, detail } => Some(json!({ "kind": kind, "detail": detail })),
+            ApiError::Csv(CsvError::Unreadable(detail)) => Some(json!({ "detail": detail })),
+            _ => None,
+        }
+    }
+
+    /// The response body.
+    pub fn to_json(&self) -> Value {
+        let mut body = json!({ "error": self.message(), "code": self.code() });
+        if let Some(details) = self.details() {
+            body["details"] = details;
+        }
+        body
+    }
+}
+
+/// The shape [`ApiError::to_json`] builds, for the OpenAPI spec.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct ErrorBody {
+    /// For people. Can change.
+    error: String,
+    /// Stable, like `team_not_found` or `invalid_body`.
+    code: String,
+    /// `{"fields": [...]}` for `invalid_body`, `{"kind", "key", "detail"}` for unreadable
+    /// documents, `{"kind", "detail"}` for `invalid_document`, and `{"detail"}` for archives and
+    /// CSV files that can't be read.
+    #[schema(value_type = Option<Object>)]
+    details: Option<Value>,
+}
+
+/// Why a `service` function refused a change. Each one has its own `code`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceError {
+    PlayerNotFound,
+    GuestNotFound,
+    SquadNotFound,
+    JoinRequestNotFound,
+    RsvpClosed,
+    NoteTooLong,
+    EmptyComment,
+    EmptyGuestName,
+    GuestLimitReached,
+    EmptySquad,
+    SquadExists,
+    EmptySquadName,
+    NoSquads,
+    NobodyPlaying,
+    EmptyPlayerNames,
+    EmptyPlayerName,
+    NameTaken,
+    MergeIntoSelf,
+    EmptyName,
+    NameTooLong,
+    RequestWaiting,
+    TooManyRequests,
+    NotInSquad,
+    TooManyGoals,
+    TooManyAssists,
+    VotingClosed,
+    VotingNotOpen,
+    NoKickoff,
+    NotAVoter,
+    SelfVote,
+    CandidateDidntPlay,
+    AlreadyVoted,
+    UnsupportedArchive,
+    EmptyTeamName,
+    TooManyGames,
+}
+
+impl ServiceError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ServiceError::PlayerNotFound => "player_not_found",
+            ServiceError::GuestNotFound => "guest_not_found",
+            ServiceError::SquadNotFound => "squad_not_found",
+            ServiceError::JoinRequestNotFound => "join_request_not_found",
+            ServiceError::RsvpClosed => "rsvp_closed",
+            ServiceError::NoteTooLong => "note_too_long",
+            ServiceError::EmptyComment => "empty_comment",
+            ServiceError::EmptyGuestName => "empty_guest_name",
+            ServiceError::GuestLimitReached => "guest_limit_reached",
+            ServiceError::EmptySquad => "empty_squad",
+            ServiceError::SquadExists => "squad_exists",
+            ServiceError::EmptySquadName => "empty_squad_name",
+            ServiceError::NoSquads => "no_squads",
+            ServiceError::NobodyPlaying => "nobody_playing",
+            ServiceError::EmptyPlayerNames => "empty_player_names",
+            ServiceError::EmptyPlayerName => "empty_player_name",
+            ServiceError::NameTaken => "name_taken",
+            ServiceError::MergeIntoSelf => "merge_into_self",
+            ServiceError::EmptyName => "empty_name",
+            ServiceError::NameTooLong => "name_too_long",
+            ServiceError::RequestWaiting => "request_waiting",
+            ServiceError::TooManyRequests => "too_many_requests",
+            ServiceError::NotInSquad => "not_in_squad",
+            ServiceError::TooManyGoals => "too_many_goals",
+            ServiceError::TooManyAssists => "too_many_assists",
+            ServiceError::VotingClosed => "voting_closed",
+            ServiceError::VotingNotOpen => "voting_not_open",
+            ServiceError::NoKickoff => "no_kickoff",
+            ServiceError::NotAVoter => "not_a_voter",
+            ServiceError::SelfVote => "self_vote",
+            ServiceError::CandidateDidntPlay => "candidate_didnt_play",
+            ServiceError::AlreadyVoted => "already_voted",
+            ServiceError::UnsupportedArchive => "unsupported_archive",
+            ServiceError::EmptyTeamName => "empty_team_name",
+            ServiceError::TooManyGames => "too_many_games",
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            ServiceError::PlayerNotFound => "player not found",
+            ServiceError::GuestNotFound => "guest not found",
+            ServiceError::SquadNotFound => "squad not found",
+            ServiceError::JoinRequestNotFound => "join request not found",
+            ServiceError::RsvpClosed => "RSVPs are closed, ask an admin to make changes",
+            ServiceError::NoteTooLong => "note is too long",
+            ServiceError::EmptyComment => "comment can't be empty",
+            ServiceError::EmptyGuestName => "guest name can't be empty",
+            ServiceError::GuestLimitReached => "guest limit reached",
+            ServiceError::EmptySquad => "squad id and name can't be empty",
+            ServiceError::SquadExists => "squad already exists",
+            ServiceError::EmptySquadName => "squad name can't be empty",
+            ServiceError::NoSquads => "no squads to fill",
+            ServiceError::NobodyPlaying => "nobody is playing yet",
+            ServiceError::EmptyPlayerNames => "player names can't be empty",
+            ServiceError::EmptyPlayerName => "player name can't be empty",
+            ServiceError::NameTaken => "a player with that name is already on the roster",
+            ServiceError::MergeIntoSelf => "can't merge a player into themselves",
+            ServiceError::EmptyName => "name can't be empty",
+            ServiceError::NameTooLong => "name is too long",
+            ServiceError::RequestWaiting => "a request with that name is already waiting",
+            ServiceError::TooManyRequests => "too many requests are waiting, try again later",
+            ServiceError::NotInSquad => "player is not in a squad",
+            ServiceError::TooManyGoals => "more goals than the squad scored",
+            ServiceError::TooManyAssists => "more assists than the squad scored",
+            ServiceError::VotingClosed => "voting is closed",
+            ServiceError::VotingNotOpen => "voting opens at kick-off",
+            ServiceError::NoKickoff => "the game has no kick-off time",
+            ServiceError::NotAVoter => "only players who played can vote",
+            ServiceError::SelfVote => "you can't vote for yourself",
+            ServiceError::CandidateDidntPlay => "candidate didn't play",
+            ServiceError::AlreadyVoted => "already voted",
+            ServiceError::UnsupportedArchive => "unsupported archive version",
+            ServiceError::EmptyTeamName => "team name can't be empty",
+            ServiceError::TooManyGames => "archive has too many games",
+        }
+    }
+}
+
+/// Why a roster CSV can't be imported. Each one has its own `code`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsvError {
+    /// The file isn't CSV. The detail says where it went wrong.
+    Unreadable(String),
+    UnknownColumn(String),
+    MissingNameColumn,
+    /// More than this many players in one import.
+    TooManyRows(usize),
+    /// Over [`crate::roster_csv::MAX_IMPORT_BYTES`].
+    TooLarge,
+}
+
+impl CsvError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            CsvError::Unreadable(_) => "csv_unreadable",
+            CsvError::UnknownColumn(_) => "unknown_column",
+            CsvError::MissingNameColumn => "missing_name_column",
+            CsvError::TooManyRows(_) => "too_many_rows",
+            CsvError::TooLarge => "csv_too_large",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            CsvError::Unreadable(_) => "the file can't be read as CSV".to_string(),
+            CsvError::UnknownColumn(name) => format!("unknown column \"{}\"", name),
+            CsvError::MissingNameColumn => "missing \"name\" column".to_string(),
+            CsvError::TooManyRows(max) => format!("can't import more than {} players at once", max),
+            CsvError::TooLarge => "the file is too large".to_string(),
+        }
+    }
+}
+
+impl From<CsvError> for ApiError {
+    fn from(err: CsvError) -> Self {
+        ApiError::Csv(err)
+    }
+}
+
+/// The "not found" reasons, and RSVPs being closed, are the same failures handlers report
+/// themselves, so they keep those codes and statuses.
+impl From<ServiceError> for ApiError {
+    fn from(err: ServiceError) -> Self {
+        match err {
+            ServiceError::PlayerNotFound => ApiError::PlayerNotFound,
+            ServiceError::GuestNotFound => ApiError::GuestNotFound,
+            ServiceError::SquadNotFound => ApiError::SquadNotFound,
+            ServiceError::JoinRequestNotFound => ApiError::JoinRequestNotFound,
+            ServiceError::RsvpClosed => ApiError::RsvpClosed,
+            err => ApiError::Refused(err),
+        }
+    }
+}
+
+/// Lets handlers return an `ApiError` with `?`; the router turns it back into a response.
+impl From<ApiError> for worker::Error {
+    fn from(err: ApiError) -> Self {
+        worker::Error::Json((err.to_json().to_string(), err.status()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn body_has_message_and_code() {
+        assert_eq!(
+            ApiError::WrongSecret.to_json(),
+            json!({"error": "wrong admin secret", "code": "wrong_secret"})
+        );
+        assert_eq!(ApiError::WrongSecret.status(), 403);
+        assert_eq!(ApiError::TeamNotFound.status(), 404);
+    }
+
+    #[test]
+    fn details_only_when_there_are_some() {
+        let err = ApiError::InvalidBody(vec![FieldError::new("name", "player name can't be empty")]);
+        assert_eq!(err.status(), 400);
+        assert_eq!(
+            err.to_json()["details"],
+            json!({"fields": [{"field": "name", "message": "player name can't be empty"}]})
+        );
+
+        let err = ApiError::UnreadableDocument {
+            kind: "game".into(),
+            key: "g1".into(),
+            detail: "expected value".into(),
+            too_new: true,
+        };
+        assert_eq!((err.code(), err.status()), ("document_too_new", 422));
+        assert_eq!(err.to_json()["details"]["key"], "g1");
+    }
+
+    #[test]
+    fn service_errors_have_their_own_codes() {
+        assert_eq!(ApiError::from(ServiceError::PlayerNotFound), ApiError::PlayerNotFound);
+        assert_eq!(ApiError::from(ServiceError::SquadNotFound).code(), "squad_not_found");
+        assert_eq!(ApiError::from(ServiceError::RsvpClosed).status(), 403);
+        let err = ApiError::from(ServiceError::GuestLimitReached);
+        assert_eq!((err.code(), err.status()), ("guest_limit_reached", 400));
+        assert_eq!(err.message(), "guest limit reached");
+    }
+
+    #[test]
+    fn request_errors_have_their_own_codes() {
+        let err = ApiError::from(CsvError::UnknownColumn("age".into()));
+        assert_eq!((err.code(), err.status()), ("unknown_column", 400));
+        assert_eq!(err.message(), "unknown column \"age\"");
+        assert_eq!(ApiError::ArchiveTooLarge.status(), 413);
+        let err = ApiError::InvalidDocument { kind: "team".into(), detail: "missing field `name`".into() };
+        assert_eq!(err.to_json()["details"], json!({"kind": "team", "detail": "missing field `name`"}));
+        assert_eq!(ApiError::ExpectedWebSocket.status(), 426);
+    }
+}
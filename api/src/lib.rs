@@ -1,13 +1,32 @@
 use std::collections::HashMap;
 
+use jiff::ToSpan;
 use serde::Serialize;
 use worker::*;
 
+mod error;
+mod mail;
+mod openapi;
+mod migrate;
+mod push;
 mod random;
+mod roster_csv;
+mod room;
 mod service;
 mod types;
-
-use types::{Game, NewTeamResponse, Team};
+mod v1;
+
+use error::{ApiError, CsvError, ServiceError};
+use mail::Transport;
+use types::{
+    AddGuestRequest, AddPlayersRequest, AttendanceRequest, AutoSquadsRequest, AutoSquadsResponse, Comment,
+    CommentRequest, ContactRequest, DefaultSquadsRequest, Game, GameKeysResponse, Guest, JoinRequest, JoinTeamRequest, LeaderboardResponse,
+    MergePlayerRequest, MvpVoteRequest, NewGameRequest, NewTeamRequest, NewTeamResponse, NoShowEntry, PlayerID,
+    PlayerUpdate, PromoteGuestRequest, PromoteGuestResponse, PushPublicKeyResponse, PushSubscription,
+    RatingsRequest, RawDocument, RemovePushSubscriptionRequest, ResultsRequest, RosterImportRequest, ScoresRequest,
+    RosterImportResponse, RsvpRequest, RsvpStatus, SettingsRequest, SquadsRequest, Team, TeamArchive,
+    TeamPageResponse, Validate,
+};
 
 const UI_DOMAIN: &str = "https://nextgame.aqd.is";
 
@@ -31,6 +50,7 @@ fn cors_headers(origin: &str) -> Headers {
     let _ = headers.set("Access-Control-Allow-Origin", origin);
     let _ = headers.set("Access-Control-Allow-Methods", "GET, POST, PUT, DELETE, OPTIONS");
     let _ = headers.set("Access-Control-Allow-Headers", "Content-Type");
+    let _ = headers.set("Access-Control-Expose-Headers", "Deprecation, Link");
     headers
 }
 
@@ -56,18 +76,118 @@ fn no_content_response(origin: &str) -> Result<Response> {
     Ok(resp)
 }
 
-fn error_json(msg: &str, status: u16, origin: &str) -> Result<Response> {
-    let body = serde_json::json!({"error": msg}).to_string();
-    let mut resp = Response::from_bytes(body.into_bytes())?.with_status(status);
+fn csv_response(body: String, filename: &str, origin: &str) -> Result<Response> {
+    let mut resp = Response::from_bytes(body.into_bytes())?;
     let headers = resp.headers_mut();
     let cors = cors_headers(origin);
     for (k, v) in cors.entries() {
         let _ = headers.set(&k, &v);
     }
-    let _ = headers.set("Content-Type", "application/json");
+    let _ = headers.set("Content-Type", "text/csv; charset=utf-8");
+    let _ = headers.set("Content-Disposition", &format!("attachment; filename=\"{}\"", filename));
     Ok(resp)
 }
 
+fn api_error(err: ApiError, origin: &str) -> Result<Response> {
+    json_error_response(err.into(), origin)
+}
+
+fn team_link(key: &str) -> String {
+    format!("{}/team/{}", UI_DOMAIN, key)
+}
+
+async fn send_emails<T: Transport>(transport: &T, emails: &[mail::Email]) {
+    for (to, e) in mail::send_all(transport, emails).await {
+        console_error!("failed to email {}: {}", to, e);
+    }
+}
+
+/// Email after the response has gone out, like [`push_to_team`]. Failures are only logged.
+fn email_later(ctx: &RouteContext<Context>, emails: Vec<mail::Email>) {
+    if emails.is_empty() {
+        return;
+    }
+    let Some(transport) = mail::MailChannels::from_env(&ctx.env) else { return };
+    ctx.data.wait_until(async move {
+        send_emails(&transport, &emails).await;
+    });
+}
+
+/// Push a message to every target at once. Returns the subscriptions the push service reports
+/// gone, as (player, endpoint), for the caller to drop.
+async fn send_pushes(
+    vapid: &push::Vapid,
+    targets: Vec<(PlayerID, PushSubscription)>,
+    message: &push::PushMessage,
+) -> Vec<(PlayerID, String)> {
+    let sends = targets.iter().map(|(_, sub)| push::send(vapid, sub, message));
+    let deliveries = futures_util::future::join_all(sends).await;
+    let mut gone = Vec::new();
+    for ((pid, sub), delivery) in targets.into_iter().zip(deliveries) {
+        match delivery {
+            push::Delivery::Sent => {}
+            push::Delivery::Gone => gone.push((pid, sub.endpoint)),
+            push::Delivery::Failed(e) => console_error!("failed to push to {}: {}", pid, e),
+        }
+    }
+    gone
+}
+
+/// Push to the team's subscribers after the response has gone out, then drop any subscriptions
+/// that are gone. Failures are only logged: the action the push is about has already happened.
+fn push_to_team(ctx: &RouteContext<Context>, team: &Team, key: &str, game: Option<&Game>, message: push::PushMessage) {
+    let vapid = match push::Vapid::from_env(&ctx.env) {
+        Some(v) => v,
+        None => return,
+    };
+    let targets = service::push_targets(team, game);
+    let env = ctx.env.clone();
+    let key = key.to_string();
+    ctx.data.wait_until(async move {
+        let gone = send_pushes(&vapid, targets, &message).await;
+        if !gone.is_empty()
+            && let Err(e) = drop_push_subscriptions(&env, &key, &gone).await
+        {
+            console_error!("failed to drop push subscriptions for {}: {}", key, e);
+        }
+    });
+}
+
+// --- Helper: remove gone push subscriptions from a fresh copy of the team ---
+async fn drop_push_subscriptions(env: &Env, key: &str, gone: &[(PlayerID, String)]) -> Result<()> {
+    let teams_kv = env.kv("teams")?;
+    let Some(mut team) = load_team(&teams_kv, key).await? else { return Ok(()) };
+    for (pid, endpoint) in gone {
+        service::remove_push_subscription(&mut team, pid, endpoint);
+    }
+    teams_kv.put(key, serde_json::to_string(&team).unwrap())?.execute().await?;
+    Ok(())
+}
+
+/// Tell the team's room the current game changed (or is gone), so it can update live viewers.
+async fn publish_game(ctx: &RouteContext<Context>, game: Option<(&str, &Game)>) {
+    publish_game_for(&ctx.env, ctx.param("teamkey").unwrap(), game).await
+}
+
+async fn publish_game_for(env: &Env, team_key: &str, game: Option<(&str, &Game)>) {
+    let update = game.map(|(k, g)| room::GameUpdate {
+        key: k.to_string(),
+        game: g.clone(),
+    });
+    let result = async {
+        let stub = env.durable_object("rooms")?.id_from_name(team_key)?.get_stub()?;
+        let mut init = RequestInit::new();
+        init.with_method(Method::Post)
+            .with_body(Some(wasm_bindgen::JsValue::from_str(&serde_json::to_string(&update).unwrap())));
+        let req = Request::new_with_init(&format!("{}/publish", room::ROOM_URL), &init)?;
+        stub.fetch_with_request(req).await
+    }
+    .await;
+    if let Err(e) = result {
+        console_error!("failed to publish game for {}: {}", team_key, e);
+    }
+}
+
 fn redirect_to_ui(path: &str) -> Result<Response> {
     let url = format!("{}{}", UI_DOMAIN, path);
     let mut resp = Response::empty()?.with_status(301);
@@ -77,86 +197,457 @@ fn redirect_to_ui(path: &str) -> Result<Response> {
 }
 
 // --- Helper: get team from KV ---
-async fn get_team(ctx: &RouteContext<()>) -> Result<Option<Team>> {
+async fn get_team(ctx: &RouteContext<Context>) -> Result<Option<Team>> {
     let key = ctx.param("teamkey").unwrap();
-    let t = ctx.kv("teams")?.get(key).text().await?;
-    Ok(t.map(|t| serde_json::from_str(&t).unwrap()))
+    load_team(&ctx.kv("teams")?, key).await
 }
 
-// --- Helper: get team with auth ---
-async fn get_team_authed(ctx: &RouteContext<()>) -> Result<Option<Team>> {
+// --- Helper: get team with auth. None if there's no such team; a wrong secret is an error ---
+async fn get_team_authed(ctx: &RouteContext<Context>) -> Result<Option<Team>> {
     let secret = ctx.param("teamsecret").unwrap();
-    let team = get_team(ctx).await?;
-    match team {
-        Some(t) if t.secret == *secret => Ok(Some(t)),
-        _ => Ok(None),
+    match get_team(ctx).await? {
+        Some(t) if t.secret != *secret => Err(ApiError::WrongSecret.into()),
+        team => Ok(team),
     }
 }
 
 // --- Helper: get game from team ---
-async fn get_game(ctx: &RouteContext<()>, team: &Team) -> Result<Option<Game>> {
+async fn get_game(ctx: &RouteContext<Context>, team: &Team) -> Result<Option<Game>> {
     if let Some(ng_key) = &team.next_game {
-        let g = ctx.kv("games")?.get(ng_key).text().await?;
-        Ok(g.map(|g| serde_json::from_str(&g).unwrap()))
+        load_game(&ctx.kv("games")?, ng_key).await
     } else {
         Ok(None)
     }
 }
 
+/// How many of a team's most recent past games the leaderboard and no-show rates look at,
+/// about a year of weekly games, so they don't read a game for every week the team has existed.
+const STATS_GAMES: usize = 52;
+
+// --- Helper: get a team's most recent earlier games, oldest first ---
+async fn get_recent_games(ctx: &RouteContext<Context>, team: &Team) -> Result<Vec<Game>> {
+    let recent = &team.past_games[team.past_games.len().saturating_sub(STATS_GAMES)..];
+    let games = load_games(&ctx.kv("games")?, recent).await?;
+    Ok(games.into_iter().map(|(_, g)| g).collect())
+}
+
+// --- Helper: read several stored games at once, in order, skipping any that are gone ---
+async fn load_games<'a>(
+    games_kv: &kv::KvStore,
+    keys: impl IntoIterator<Item = &'a String>,
+) -> Result<Vec<(String, Game)>> {
+    let keys: Vec<&String> = keys.into_iter().collect();
+    let loaded = futures_util::future::join_all(keys.iter().map(|key| load_game(games_kv, key))).await;
+    let mut games = Vec::new();
+    for (key, game) in keys.into_iter().zip(loaded) {
+        if let Some(g) = game? {
+            games.push((key.clone(), g));
+        }
+    }
+    Ok(games)
+}
+
+// --- Helper: read a stored team, writing it back if it had to be upgraded ---
+async fn load_team(teams_kv: &kv::KvStore, key: &str) -> Result<Option<Team>> {
+    let text = match teams_kv.get(key).text().await? {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+    let loaded = migrate::team_from_str(&text).map_err(|e| unreadable_document("team", key, e))?;
+    if loaded.migrated {
+        teams_kv.put(key, serde_json::to_string(&loaded.doc).unwrap())?.execute().await?;
+    }
+    Ok(Some(loaded.doc))
+}
+
+// --- Helper: read a stored game, writing it back if it had to be upgraded ---
+async fn load_game(games_kv: &kv::KvStore, key: &str) -> Result<Option<Game>> {
+    let text = match games_kv.get(key).text().await? {
+        Some(g) => g,
+        None => return Ok(None),
+    };
+    let loaded = migrate::game_from_str(&text).map_err(|e| unreadable_document("game", key, e))?;
+    if loaded.migrated {
+        games_kv.put(key, serde_json::to_string(&loaded.doc).unwrap())?.execute().await?;
+    }
+    Ok(Some(loaded.doc))
+}
+
+/// Log a stored document that can't be read, and turn it into an error naming it.
+/// The router turns these into JSON responses, 422 for documents from a newer server and 500 for
+/// broken ones. Admins can look at and fix the raw document through `/raw`.
+fn unreadable_document(kind: &str, key: &str, err: migrate::LoadError) -> Error {
+    console_error!("can't read {} {}: {}", kind, key, err);
+    ApiError::UnreadableDocument {
+        kind: kind.to_string(),
+        key: key.to_string(),
+        detail: err.to_string(),
+        too_new: matches!(err, migrate::LoadError::TooNew { .. }),
+    }
+    .into()
+}
+
+/// Turn an [`Error::Json`] from a handler into a proper JSON response. Other errors are left alone.
+fn json_error_response(err: Error, origin: &str) -> Result<Response> {
+    let (body, status) = match err {
+        Error::Json(json) => json,
+        err => return Err(err),
+    };
+    let mut resp = Response::from_bytes(body.into_bytes())?.with_status(status);
+    let headers = resp.headers_mut();
+    let cors = cors_headers(origin);
+    for (k, v) in cors.entries() {
+        let _ = headers.set(&k, &v);
+    }
+    let _ = headers.set("Content-Type", "application/json");
+    Ok(resp)
+}
+
+/// Read a request body into `T`. A body that doesn't fit becomes a 400 listing every field
+/// that's wrong, for `json_error_response` to send.
+async fn read_body<T: serde::de::DeserializeOwned + Validate>(req: &Request) -> Result<T> {
+    let text = req.clone_mut()?.text().await?;
+    parse_body(req, &text)
+}
+
+/// [`read_body`], for a body already read.
+fn parse_body<T: serde::de::DeserializeOwned + Validate>(req: &Request, text: &str) -> Result<T> {
+    let parsed = if is_v1(req) { types::parse_lenient_request(text) } else { types::parse_request(text) };
+    parsed.map_err(|fields| ApiError::InvalidBody(fields).into())
+}
+
+/// Read the body as text, unless it's longer than `max` bytes: then None, without reading it if
+/// its declared length already says so.
+async fn read_text_within(req: &Request, max: usize) -> Result<Option<String>> {
+    let declared = req.headers().get("Content-Length")?.and_then(|l| l.parse::<usize>().ok());
+    if declared.is_some_and(|len| len > max) {
+        return Ok(None);
+    }
+    let text = req.clone_mut()?.text().await?;
+    Ok(Some(text).filter(|t| t.len() <= max))
+}
+
 // ============================================================
 // Router
 // ============================================================
 
+/// When v1 (everything under `/api` outside `/api/v2`) was deprecated, as an RFC 9745 date.
+const V1_DEPRECATED_AT: &str = "@1792368000";
+
+/// Routes that are the same in v1 and v2, registered under each version's base path.
+macro_rules! shared_api_routes {
+    ($router:expr, $base:literal) => {
+        $router
+            // --- API: teams ---
+            .post_async(concat!($base, "/teams"), api_new_team)
+            .post_async(concat!($base, "/teams/import"), api_import_team)
+            .get_async(concat!($base, "/teams/:teamkey"), api_team)
+            .put_async(concat!($base, "/teams/:teamkey/players/:playerid/rsvp"), api_rsvp)
+            .post_async(concat!($base, "/teams/:teamkey/comments"), api_add_comment)
+            .post_async(concat!($base, "/teams/:teamkey/guests"), api_add_guest)
+            .post_async(concat!($base, "/teams/:teamkey/players/:playerid/guests"), api_add_player_guest)
+            .delete_async(concat!($base, "/teams/:teamkey/guests/:guestid"), api_delete_guest)
+            .post_async(concat!($base, "/teams/:teamkey/new_game"), api_new_game)
+            .put_async(concat!($base, "/teams/:teamkey/squads"), api_save_squads)
+            .post_async(concat!($base, "/teams/:teamkey/squads/auto"), api_auto_squads)
+            .delete_async(concat!($base, "/teams/:teamkey/players/:playerid/contact"), api_delete_contact)
+            .post_async(concat!($base, "/teams/:teamkey/players/:playerid/push_subscriptions"), api_add_push_subscription)
+            .delete_async(concat!($base, "/teams/:teamkey/players/:playerid/push_subscriptions"), api_delete_push_subscription)
+            .get_async(concat!($base, "/push/public_key"), api_push_public_key)
+            .get_async(concat!($base, "/teams/:teamkey/reminder.ics"), api_reminder_ics)
+            .get_async(concat!($base, "/teams/:teamkey/squads/ws"), api_squads_ws)
+            .put_async(concat!($base, "/teams/:teamkey/games/:gamekey/results"), api_record_results)
+            .post_async(concat!($base, "/teams/:teamkey/games/:gamekey/players/:playerid/mvp_vote"), api_mvp_vote)
+            .get_async(concat!($base, "/teams/:teamkey/leaderboard"), api_leaderboard)
+            .post_async(concat!($base, "/teams/:teamkey/join_requests"), api_request_to_join)
+            // --- API: admin ---
+            .get_async(concat!($base, "/admin/:teamkey/:teamsecret"), api_admin)
+            .get_async(concat!($base, "/admin/:teamkey/:teamsecret/export"), api_export_team)
+            .get_async(concat!($base, "/admin/:teamkey/:teamsecret/raw"), api_raw_documents)
+            .put_async(concat!($base, "/admin/:teamkey/:teamsecret/raw/:kind/:key"), api_repair_document)
+            .put_async(concat!($base, "/admin/:teamkey/:teamsecret/settings"), api_update_settings)
+            .post_async(concat!($base, "/admin/:teamkey/:teamsecret/players"), api_add_players)
+            .get_async(concat!($base, "/admin/:teamkey/:teamsecret/players/export"), api_export_roster)
+            .post_async(concat!($base, "/admin/:teamkey/:teamsecret/players/import"), api_import_roster)
+            .post_async(concat!($base, "/admin/:teamkey/:teamsecret/players/new"), api_create_player)
+            .put_async(concat!($base, "/admin/:teamkey/:teamsecret/players/:playerid"), api_update_player)
+            .delete_async(concat!($base, "/admin/:teamkey/:teamsecret/players/:playerid"), api_delete_player)
+            .post_async(concat!($base, "/admin/:teamkey/:teamsecret/players/:playerid/merge"), api_merge_player)
+            .put_async(concat!($base, "/admin/:teamkey/:teamsecret/players/:playerid/contact"), api_set_contact)
+            .post_async(concat!($base, "/admin/:teamkey/:teamsecret/guests"), api_admin_add_guest)
+            .post_async(concat!($base, "/admin/:teamkey/:teamsecret/players/:playerid/guests"), api_admin_add_player_guest)
+            .delete_async(concat!($base, "/admin/:teamkey/:teamsecret/guests/:guestid"), api_admin_delete_guest)
+            .post_async(concat!($base, "/admin/:teamkey/:teamsecret/guests/promote"), api_promote_guest)
+            .post_async(concat!($base, "/admin/:teamkey/:teamsecret/join_requests/:requestid/approve"), api_approve_join_request)
+            .post_async(concat!($base, "/admin/:teamkey/:teamsecret/join_requests/:requestid/reject"), api_reject_join_request)
+            .post_async(concat!($base, "/admin/:teamkey/:teamsecret/reset_game"), api_reset_game)
+            .post_async(concat!($base, "/admin/:teamkey/:teamsecret/game_off"), api_game_off)
+            .put_async(concat!($base, "/admin/:teamkey/:teamsecret/default_squads"), api_default_squads)
+            .put_async(concat!($base, "/admin/:teamkey/:teamsecret/ratings"), api_set_ratings)
+            .put_async(concat!($base, "/admin/:teamkey/:teamsecret/games/:gamekey/results"), api_admin_record_results)
+            .put_async(concat!($base, "/admin/:teamkey/:teamsecret/scores"), api_record_scores)
+            .put_async(concat!($base, "/admin/:teamkey/:teamsecret/attendance"), api_check_in)
+            .get_async(concat!($base, "/admin/:teamkey/:teamsecret/no_shows"), api_no_shows)
+    };
+}
+
 #[event(fetch)]
-async fn main(req: Request, env: Env, _: Context) -> Result<Response> {
+async fn main(req: Request, env: Env, ctx: Context) -> Result<Response> {
     console_error_panic_hook::set_once();
+    let origin = cors_origin(&req);
+    let v1 = is_v1(&req).then(|| req.path());
 
-    let router = Router::new();
-
-    router
+    let router = Router::with_data(ctx)
         // --- Old HTML routes -> redirect to UI ---
         .get_async("/", html_home_redirect)
         .get_async("/team/:teamkey", html_team_redirect)
         .get_async("/admin/:teamkey/:teamsecret", html_admin_redirect)
         // --- CORS preflight ---
         .options_async("/api/*catchall", api_options)
-        // --- API: teams ---
-        .post_async("/api/teams", api_new_team)
-        .get_async("/api/teams/:teamkey", api_team)
+        .get_async("/api/openapi.json", api_openapi)
+        // --- API v1: only the play/not_play shortcuts, which v2 replaces with PUT .../rsvp ---
         .post_async("/api/teams/:teamkey/players/:playerid/play", api_play)
         .post_async("/api/teams/:teamkey/players/:playerid/not_play", api_not_play)
-        .post_async("/api/teams/:teamkey/comments", api_add_comment)
-        .post_async("/api/teams/:teamkey/guests", api_add_guest)
-        .delete_async("/api/teams/:teamkey/guests/:idx", api_delete_guest)
-        .post_async("/api/teams/:teamkey/new_game", api_new_game)
-        .put_async("/api/teams/:teamkey/squads", api_save_squads)
-        .get_async("/api/teams/:teamkey/reminder.ics", api_reminder_ics)
-        // --- API: admin ---
-        .get_async("/api/admin/:teamkey/:teamsecret", api_admin)
-        .put_async("/api/admin/:teamkey/:teamsecret/settings", api_update_settings)
-        .post_async("/api/admin/:teamkey/:teamsecret/players", api_add_players)
-        .delete_async("/api/admin/:teamkey/:teamsecret/players/:playerid", api_delete_player)
-        .post_async("/api/admin/:teamkey/:teamsecret/reset_game", api_reset_game)
-        .post_async("/api/admin/:teamkey/:teamsecret/game_off", api_game_off)
-        .put_async("/api/admin/:teamkey/:teamsecret/default_squads", api_default_squads)
-        .run(req, env)
-        .await
+        .post_async("/api/admin/:teamkey/:teamsecret/players/:playerid/play", api_admin_play)
+        .post_async("/api/admin/:teamkey/:teamsecret/players/:playerid/not_play", api_admin_not_play);
+    let router = shared_api_routes!(router, "/api");
+    // --- API v2: only what's new ---
+    let router = shared_api_routes!(router, "/api/v2")
+        .get_async("/api/v2/teams/:teamkey/games", api_game_keys)
+        .get_async("/api/v2/teams/:teamkey/games/:gamekey", api_game)
+        .get_async("/api/v2/teams/:teamkey/events", api_events)
+        .put_async("/api/v2/admin/:teamkey/:teamsecret/players/:playerid/rsvp", api_admin_rsvp);
+
+    let resp = router.run(req, env).await.or_else(|e| json_error_response(e, &origin))?;
+    match v1 {
+        Some(path) => Ok(deprecated(v1_response(resp, &path).await?)),
+        None => Ok(resp),
+    }
+}
+
+/// Give a v1 response its v1 shape; see [`v1`]. Only JSON bodies change.
+async fn v1_response(mut resp: Response, path: &str) -> Result<Response> {
+    let json = resp.headers().get("Content-Type")?.is_some_and(|t| t.starts_with("application/json"));
+    if !json {
+        return Ok(resp);
+    }
+    let mut body: serde_json::Value = match serde_json::from_str(&resp.text().await?) {
+        Ok(body) => body,
+        Err(_) => return Ok(resp),
+    };
+    let status = v1::rewrite(path, resp.status_code(), &mut body);
+    let headers = resp.headers().clone();
+    Ok(Response::from_bytes(body.to_string().into_bytes())?
+        .with_status(status)
+        .with_headers(headers))
+}
+
+/// Whether this is a v1 API call. Preflights and the spec aren't versioned.
+fn is_v1(req: &Request) -> bool {
+    let path = req.path();
+    req.method() != Method::Options
+        && path.starts_with("/api/")
+        && !path.starts_with("/api/v2/")
+        && path != "/api/openapi.json"
+}
+
+/// Mark a v1 response as deprecated, pointing at the spec that describes v2.
+fn deprecated(mut resp: Response) -> Response {
+    let headers = resp.headers_mut();
+    // Responses passed through from the Durable Object, like the WebSocket upgrade, can't be changed.
+    let _ = headers.set("Deprecation", V1_DEPRECATED_AT);
+    let _ = headers.set("Link", "</api/openapi.json>; rel=\"deprecation\"; type=\"application/json\"");
+    resp
+}
+
+// ============================================================
+// Scheduled: hourly jobs
+// ============================================================
+
+#[event(scheduled)]
+async fn scheduled(_event: ScheduledEvent, env: Env, _ctx: ScheduleContext) {
+    console_error_panic_hook::set_once();
+
+    if let Err(e) = hourly(&env).await {
+        console_error!("failed to run hourly jobs: {}", e);
+    }
+}
+
+/// Dated games are indexed in the games namespace under `schedule:<date>:<team>:<game>`, so the
+/// hourly job only reads the games that could have something due.
+const SCHEDULE_PREFIX: &str = "schedule:";
+
+/// Set once every game from before the index has been added to it.
+const SCHEDULE_BACKFILLED: &str = "schedule-backfilled";
+
+/// How many days either side of today, in UTC, the hourly job looks at. The reminder goes out the
+/// evening before the game in the team's timezone, which can be two UTC days earlier.
+const SCHEDULE_DAYS_BEFORE: i64 = 2;
+/// MVP voting stays open up to a week after kick-off, which can be the next UTC day.
+const SCHEDULE_DAYS_AFTER: i64 = 8;
+
+/// Add a dated game to the schedule the hourly job reads. Without it the game only misses its
+/// reminders, so a failure is logged rather than failing the request.
+async fn schedule_game(games_kv: &kv::KvStore, team_key: &str, game_key: &str, game: &Game) {
+    let Some(date) = game.date else { return };
+    let Ok(day) = date.to_zoned(jiff::tz::TimeZone::UTC) else { return };
+    let expires = day.timestamp().as_second() + (SCHEDULE_DAYS_AFTER + 1) * 24 * 60 * 60;
+    let key = format!("{}{}:{}:{}", SCHEDULE_PREFIX, date, team_key, game_key);
+    let put = async { games_kv.put(&key, "")?.expiration(expires as u64).execute().await };
+    if let Err(e) = put.await {
+        console_error!("failed to schedule game {}: {}", game_key, e);
+    }
+}
+
+/// Add every team's current game to the schedule, for games created before there was one.
+/// Only does anything the first time.
+async fn backfill_schedule(env: &Env) -> Result<()> {
+    let teams_kv = env.kv("teams")?;
+    let games_kv = env.kv("games")?;
+    if games_kv.get(SCHEDULE_BACKFILLED).text().await?.is_some() {
+        return Ok(());
+    }
+
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut list = teams_kv.list();
+        if let Some(c) = cursor.take() {
+            list = list.cursor(c);
+        }
+        let page = list.execute().await?;
+        for k in page.keys {
+            // One unreadable document shouldn't hold up every other team. It's logged already.
+            let team = match load_team(&teams_kv, &k.name).await {
+                Ok(Some(t)) => t,
+                Ok(None) | Err(Error::Json(_)) => continue,
+                Err(e) => return Err(e),
+            };
+            let Some(ng_key) = &team.next_game else { continue };
+            match load_game(&games_kv, ng_key).await {
+                Ok(Some(game)) => schedule_game(&games_kv, &k.name, ng_key, &game).await,
+                Ok(None) | Err(Error::Json(_)) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        if page.list_complete {
+            break;
+        }
+        cursor = page.cursor;
+    }
+    games_kv.put(SCHEDULE_BACKFILLED, "")?.execute().await?;
+    Ok(())
+}
+
+/// Email reminders the evening before, push "starting soon" shortly before kick-off, and close
+/// MVP voting once its window has passed, for the games on the schedule around today.
+async fn hourly(env: &Env) -> Result<()> {
+    backfill_schedule(env).await?;
+
+    let games_kv = env.kv("games")?;
+    let now = jiff::Timestamp::now();
+    let today = now.to_zoned(jiff::tz::TimeZone::UTC).date();
+
+    for offset in -SCHEDULE_DAYS_AFTER..=SCHEDULE_DAYS_BEFORE {
+        let prefix = format!("{}{}:", SCHEDULE_PREFIX, today + offset.days());
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut list = games_kv.list().prefix(prefix.clone());
+            if let Some(c) = cursor.take() {
+                list = list.cursor(c);
+            }
+            let page = list.execute().await?;
+            for k in page.keys {
+                let Some((team_key, game_key)) = k.name[prefix.len()..].split_once(':') else { continue };
+                match hourly_game(env, team_key, game_key, now).await {
+                    // One unreadable document shouldn't hold up every other team. It's logged already.
+                    Ok(()) | Err(Error::Json(_)) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            if page.list_complete {
+                break;
+            }
+            cursor = page.cursor;
+        }
+    }
+    Ok(())
+}
+
+/// The hourly jobs for one scheduled game. Only a team's current game gets reminders and pushes.
+async fn hourly_game(env: &Env, team_key: &str, game_key: &str, now: jiff::Timestamp) -> Result<()> {
+    let teams_kv = env.kv("teams")?;
+    let games_kv = env.kv("games")?;
+    let Some(mut team) = load_team(&teams_kv, team_key).await? else { return Ok(()) };
+    if !service::has_game(&team, game_key) {
+        return Ok(());
+    }
+    let Some(mut game) = load_game(&games_kv, game_key).await? else { return Ok(()) };
+    // A game can be archived while its MVP voting is still open; that still has to close.
+    let current = team.next_game.as_deref() == Some(game_key);
+    let link = team_link(team_key);
+    let mut game_changed = false;
+    let mut team_changed = false;
+
+    if let Some(transport) = mail::MailChannels::from_env(env)
+        && current
+        && service::reminder_due(&team, &game, now)
+    {
+        let emails = service::reminder_emails(&team, &game, &link);
+        send_emails(&transport, &emails).await;
+        game.reminder_sent = true;
+        game_changed = true;
+    }
+
+    if let Some(vapid) = push::Vapid::from_env(env)
+        && current
+        && service::kickoff_push_due(&team, &game, now)
+    {
+        let message = service::kickoff_push(&team, &game, &link);
+        let targets = service::push_targets(&team, Some(&game));
+        for (pid, endpoint) in send_pushes(&vapid, targets, &message).await {
+            service::remove_push_subscription(&mut team, &pid, &endpoint);
+            team_changed = true;
+        }
+        game.kickoff_push_sent = true;
+        game_changed = true;
+    }
+
+    if service::close_mvp_voting(&team, &mut game, now) {
+        game_changed = true;
+    }
+
+    if game_changed {
+        games_kv
+            .put(game_key, serde_json::to_string(&game).unwrap())?
+            .execute()
+            .await?;
+        if current {
+            publish_game_for(env, team_key, Some((game_key, &game))).await;
+        }
+    }
+    if team_changed {
+        teams_kv
+            .put(team_key, serde_json::to_string(&team).unwrap())?
+            .execute()
+            .await?;
+    }
+    Ok(())
 }
 
 // ============================================================
 // HTML Redirects
 // ============================================================
 
-async fn html_home_redirect(_: Request, _ctx: RouteContext<()>) -> Result<Response> {
+async fn html_home_redirect(_: Request, _ctx: RouteContext<Context>) -> Result<Response> {
     redirect_to_ui("/")
 }
 
-async fn html_team_redirect(_: Request, ctx: RouteContext<()>) -> Result<Response> {
+async fn html_team_redirect(_: Request, ctx: RouteContext<Context>) -> Result<Response> {
     let key = ctx.param("teamkey").unwrap();
     redirect_to_ui(&format!("/team/{}", key))
 }
 
-async fn html_admin_redirect(_: Request, ctx: RouteContext<()>) -> Result<Response> {
+async fn html_admin_redirect(_: Request, ctx: RouteContext<Context>) -> Result<Response> {
     let key = ctx.param("teamkey").unwrap();
     let secret = ctx.param("teamsecret").unwrap();
     redirect_to_ui(&format!("/admin/{}/{}", key, secret))
@@ -166,7 +657,7 @@ async fn html_admin_redirect(_: Request, ctx: RouteContext<()>) -> Result<Respon
 // CORS Preflight
 // ============================================================
 
-async fn api_options(req: Request, _ctx: RouteContext<()>) -> Result<Response> {
+async fn api_options(req: Request, _ctx: RouteContext<Context>) -> Result<Response> {
     let o = cors_origin(&req);
     no_content_response(&o)
 }
@@ -175,19 +666,26 @@ async fn api_options(req: Request, _ctx: RouteContext<()>) -> Result<Response> {
 // JSON API Handlers
 // ============================================================
 
-async fn api_new_team(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+#[utoipa::path(
+    post,
+    path = "/api/teams",
+    request_body = NewTeamRequest,
+    responses((status = 200, body = NewTeamResponse)),
+    tag = "teams"
+)]
+async fn api_new_team(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
     let o = cors_origin(&req);
-    let mut r = req.clone_mut()?;
-    let body: serde_json::Value = r.json().await?;
-    let name = body["name"].as_str().unwrap_or("").to_string();
+    let body: NewTeamRequest = read_body(&req).await?;
+    let name = body.name.trim().to_string();
     if name.is_empty() {
-        return error_json("team name can't be empty", 400, &o);
+        return api_error(ServiceError::EmptyTeamName.into(), &o);
     }
 
     let key = random::hex_string();
     let secret = random::hex_string();
 
     let new_team = Team {
+        schema_version: migrate::TEAM_SCHEMA_VERSION,
         name,
         secret: secret.clone(),
         next_game: None,
@@ -196,6 +694,15 @@ async fn api_new_team(req: Request, ctx: RouteContext<()>) -> Result<Response> {
         time: None,
         weekly_schedule: None,
         default_squads: HashMap::new(),
+        push_subscriptions: HashMap::new(),
+        timezone: None,
+        ratings: HashMap::new(),
+        past_games: Vec::new(),
+        results_open: false,
+        mvp_voting_hours: None,
+        rsvp_deadline_hours: None,
+        guest_limit: None,
+        join_requests: Vec::new(),
     };
 
     match ctx
@@ -211,11 +718,81 @@ async fn api_new_team(req: Request, ctx: RouteContext<()>) -> Result<Response> {
             },
             &o,
         ),
-        Err(_) => error_json("failed to create team", 500, &o),
+        Err(_) => api_error(ApiError::Storage("failed to create team"), &o),
+    }
+}
+
+/// Largest archive [`api_import_team`] reads. Games are small, so this is plenty for
+/// [`service::MAX_ARCHIVE_GAMES`] of them.
+const MAX_ARCHIVE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Create a new team from an archive made by [`api_export_team`], under new keys. Archives are
+/// limited in size and in how many games they hold.
+#[utoipa::path(
+    post,
+    path = "/api/teams/import",
+    request_body = TeamArchive,
+    responses((status = 200, body = NewTeamResponse)),
+    tag = "teams"
+)]
+async fn api_import_team(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let Some(text) = read_text_within(&req, MAX_ARCHIVE_BYTES).await? else {
+        return api_error(ApiError::ArchiveTooLarge, &o);
+    };
+    let archive = match serde_json::from_str(&text).map(migrate::archive_from_value) {
+        Ok(Ok(a)) => a,
+        Ok(Err(e)) => return api_error(ApiError::InvalidArchive(e.to_string()), &o),
+        Err(e) => return api_error(ApiError::InvalidArchive(e.to_string()), &o),
+    };
+    let TeamArchive { team, games, .. } = match service::restore_archive(archive) {
+        Ok(restored) => restored,
+        Err(msg) => return api_error(msg.into(), &o),
+    };
+
+    // Games first, so the team never refers to one that isn't there.
+    let games_kv = ctx.kv("games")?;
+    for (game_key, game) in &games {
+        if games_kv
+            .put(game_key, serde_json::to_string(game).unwrap())?
+            .execute()
+            .await
+            .is_err()
+        {
+            return api_error(ApiError::Storage("failed to import team"), &o);
+        }
+    }
+
+    let key = random::hex_string();
+    if ctx
+        .kv("teams")?
+        .put(&key, serde_json::to_string(&team).unwrap())?
+        .execute()
+        .await
+        .is_err()
+    {
+        return api_error(ApiError::Storage("failed to import team"), &o);
     }
+    if let Some((ng_key, ng)) = team.next_game.as_ref().and_then(|k| Some((k, games.get(k)?))) {
+        schedule_game(&games_kv, &key, ng_key, ng).await;
+    }
+
+    json_response(
+        &NewTeamResponse {
+            team_key: key,
+            team_secret: team.secret,
+        },
+        &o,
+    )
 }
 
-async fn api_team(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+#[utoipa::path(
+    get,
+    path = "/api/teams/{teamkey}",
+    responses((status = 200, body = TeamPageResponse)),
+    tag = "teams"
+)]
+async fn api_team(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
     let o = cors_origin(&req);
     let key = ctx.param("teamkey").unwrap().clone();
 
@@ -224,7 +801,7 @@ async fn api_team(req: Request, ctx: RouteContext<()>) -> Result<Response> {
 
     let mut team: Team = match get_team(&ctx).await? {
         Some(t) => t,
-        None => return error_json("team not found", 404, &o),
+        None => return api_error(ApiError::TeamNotFound, &o),
     };
 
     if let Some(ng_key) = &team.next_game {
@@ -245,9 +822,10 @@ async fn api_team(req: Request, ctx: RouteContext<()>) -> Result<Response> {
                 .await
                 .is_err()
             {
-                return error_json("failed to create next game", 500, &o);
+                return api_error(ApiError::Storage("failed to create next game"), &o);
             }
 
+            service::archive_game(&mut team);
             team.next_game = Some(new_ng_key);
 
             if teams_kv
@@ -256,9 +834,15 @@ async fn api_team(req: Request, ctx: RouteContext<()>) -> Result<Response> {
                 .await
                 .is_err()
             {
-                return error_json("failed to update team", 500, &o);
+                return api_error(ApiError::Storage("failed to update team"), &o);
             }
 
+            publish_game(&ctx, team.next_game.as_deref().map(|k| (k, &new_game))).await;
+            schedule_game(&games_kv, &key, team.next_game.as_deref().unwrap(), &new_game).await;
+
+            let message = service::new_game_push(&team, &new_game, &team_link(&key));
+            push_to_team(&ctx, &team, &key, None, message);
+
             return json_response(
                 &service::team_response(&team, &key, Some(new_game)),
                 &o,
@@ -266,11 +850,14 @@ async fn api_team(req: Request, ctx: RouteContext<()>) -> Result<Response> {
         }
 
         // Populate unregistered players
-        if service::populate_unregistered_players(&team, &mut ng) {
-            let _ = games_kv
+        if service::populate_unregistered_players(&team, &mut ng)
+            && games_kv
                 .put(&ng_key, serde_json::to_string(&ng).unwrap())?
                 .execute()
-                .await;
+                .await
+                .is_ok()
+        {
+            publish_game(&ctx, Some((&ng_key, &ng))).await;
         }
 
         json_response(&service::team_response(&team, &key, Some(ng)), &o)
@@ -279,88 +866,173 @@ async fn api_team(req: Request, ctx: RouteContext<()>) -> Result<Response> {
     }
 }
 
-async fn api_play(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+/// The team's current and past game keys. Only in v2.
+#[utoipa::path(
+    get,
+    path = "/api/v2/teams/{teamkey}/games",
+    responses((status = 200, body = GameKeysResponse)),
+    tag = "games"
+)]
+async fn api_game_keys(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let team = match get_team(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+    json_response(
+        &GameKeysResponse {
+            current: team.next_game.clone(),
+            past: team.past_games.clone(),
+        },
+        &o,
+    )
+}
+
+/// One of the team's games, current or past, by key. Only in v2.
+#[utoipa::path(
+    get,
+    path = "/api/v2/teams/{teamkey}/games/{gamekey}",
+    responses((status = 200, body = Game)),
+    tag = "games"
+)]
+async fn api_game(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let gk = ctx.param("gamekey").unwrap().clone();
+    let team = match get_team(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+    if !service::has_game(&team, &gk) {
+        return api_error(ApiError::GameNotFound, &o);
+    }
+    match load_game(&ctx.kv("games")?, &gk).await? {
+        Some(g) => json_response(&service::public_game(g), &o),
+        None => api_error(ApiError::GameNotFound, &o),
+    }
+}
+
+/// Only in v1; v2 has `PUT .../rsvp` instead.
+#[utoipa::path(
+    post,
+    path = "/api/teams/{teamkey}/players/{playerid}/play",
+    responses((status = 204)),
+    tag = "players"
+)]
+async fn api_play(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
     let o = cors_origin(&req);
     let pid = ctx.param("playerid").unwrap().clone();
 
     let team = match get_team(&ctx).await? {
         Some(t) => t,
-        None => return error_json("team not found", 404, &o),
+        None => return api_error(ApiError::TeamNotFound, &o),
     };
 
     let games_kv = ctx.kv("games")?;
     if let Some(ng_key) = &team.next_game {
         let mut ng: Game = match get_game(&ctx, &team).await? {
             Some(g) => g,
-            None => return error_json("game not found", 404, &o),
+            None => return api_error(ApiError::GameNotFound, &o),
         };
 
-        service::set_player_status(&mut ng, &pid, true);
+        if service::check_rsvp_open(&team, &ng, jiff::Timestamp::now()).is_err() {
+            return api_error(ApiError::RsvpClosed, &o);
+        }
+        if let Err(msg) = service::set_player_status(&mut ng, &pid, RsvpStatus::In, None) {
+            return api_error(msg.into(), &o);
+        }
 
         match games_kv
             .put(ng_key, serde_json::to_string(&ng).unwrap())?
             .execute()
             .await
         {
-            Ok(_) => no_content_response(&o),
-            Err(_) => error_json("failed to set play", 500, &o),
+            Ok(_) => {
+                publish_game(&ctx, Some((ng_key.as_str(), &ng))).await;
+                no_content_response(&o)
+            }
+            Err(_) => api_error(ApiError::Storage("failed to set play"), &o),
         }
     } else {
-        error_json("game not found", 404, &o)
+        api_error(ApiError::GameNotFound, &o)
     }
 }
 
-async fn api_not_play(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+/// Only in v1; v2 has `PUT .../rsvp` instead.
+#[utoipa::path(
+    post,
+    path = "/api/teams/{teamkey}/players/{playerid}/not_play",
+    responses((status = 204)),
+    tag = "players"
+)]
+async fn api_not_play(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
     let o = cors_origin(&req);
     let pid = ctx.param("playerid").unwrap().clone();
 
     let team = match get_team(&ctx).await? {
         Some(t) => t,
-        None => return error_json("team not found", 404, &o),
+        None => return api_error(ApiError::TeamNotFound, &o),
     };
 
     let games_kv = ctx.kv("games")?;
     if let Some(ng_key) = &team.next_game {
         let mut ng: Game = match get_game(&ctx, &team).await? {
             Some(g) => g,
-            None => return error_json("game not found", 404, &o),
+            None => return api_error(ApiError::GameNotFound, &o),
         };
 
-        service::set_player_status(&mut ng, &pid, false);
+        if service::check_rsvp_open(&team, &ng, jiff::Timestamp::now()).is_err() {
+            return api_error(ApiError::RsvpClosed, &o);
+        }
+        if let Err(msg) = service::set_player_status(&mut ng, &pid, RsvpStatus::Out, None) {
+            return api_error(msg.into(), &o);
+        }
 
         match games_kv
             .put(ng_key, serde_json::to_string(&ng).unwrap())?
             .execute()
             .await
         {
-            Ok(_) => no_content_response(&o),
-            Err(_) => error_json("failed to set not_play", 500, &o),
+            Ok(_) => {
+                publish_game(&ctx, Some((ng_key.as_str(), &ng))).await;
+                no_content_response(&o)
+            }
+            Err(_) => api_error(ApiError::Storage("failed to set not_play"), &o),
         }
     } else {
-        error_json("game not found", 404, &o)
+        api_error(ApiError::GameNotFound, &o)
     }
 }
 
-async fn api_add_comment(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+#[utoipa::path(
+    put,
+    path = "/api/teams/{teamkey}/players/{playerid}/rsvp",
+    request_body = RsvpRequest,
+    responses((status = 204)),
+    tag = "players"
+)]
+async fn api_rsvp(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
     let o = cors_origin(&req);
+    let pid = ctx.param("playerid").unwrap().clone();
+
     let team = match get_team(&ctx).await? {
         Some(t) => t,
-        None => return error_json("team not found", 404, &o),
+        None => return api_error(ApiError::TeamNotFound, &o),
     };
 
+    let body: RsvpRequest = read_body(&req).await?;
+
     let games_kv = ctx.kv("games")?;
     if let Some(ng_key) = &team.next_game {
         let mut ng: Game = match get_game(&ctx, &team).await? {
             Some(g) => g,
-            None => return error_json("game not found", 404, &o),
+            None => return api_error(ApiError::GameNotFound, &o),
         };
 
-        let mut r = req.clone_mut()?;
-        let body: serde_json::Value = r.json().await?;
-        let comment = body["comment"].as_str().unwrap_or("");
-        let author = body["author"].as_str();
-        if let Err(msg) = service::add_comment(&mut ng, comment, author) {
-            return error_json(msg, 400, &o);
+        if service::check_rsvp_open(&team, &ng, jiff::Timestamp::now()).is_err() {
+            return api_error(ApiError::RsvpClosed, &o);
+        }
+        if let Err(msg) = service::set_player_status(&mut ng, &pid, body.status, body.note.as_deref()) {
+            return api_error(msg.into(), &o);
         }
 
         match games_kv
@@ -368,33 +1040,130 @@ async fn api_add_comment(req: Request, ctx: RouteContext<()>) -> Result<Response
             .execute()
             .await
         {
-            Ok(_) => json_response(&ng.comments, &o),
-            Err(_) => error_json("failed to add comment", 500, &o),
+            Ok(_) => {
+                publish_game(&ctx, Some((ng_key.as_str(), &ng))).await;
+                no_content_response(&o)
+            }
+            Err(_) => api_error(ApiError::Storage("failed to set rsvp"), &o),
         }
     } else {
-        error_json("game not found", 404, &o)
+        api_error(ApiError::GameNotFound, &o)
     }
 }
 
-async fn api_add_guest(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+#[utoipa::path(
+    post,
+    path = "/api/teams/{teamkey}/comments",
+    request_body = CommentRequest,
+    responses((status = 200, body = Vec<Comment>)),
+    tag = "games"
+)]
+async fn api_add_comment(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
     let o = cors_origin(&req);
     let team = match get_team(&ctx).await? {
         Some(t) => t,
-        None => return error_json("team not found", 404, &o),
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    let games_kv = ctx.kv("games")?;
+    if let Some(ng_key) = &team.next_game {
+        let mut ng: Game = match get_game(&ctx, &team).await? {
+            Some(g) => g,
+            None => return api_error(ApiError::GameNotFound, &o),
+        };
+
+        let body: CommentRequest = read_body(&req).await?;
+        if let Err(msg) = service::add_comment(&mut ng, &body.comment, body.author.as_deref()) {
+            return api_error(msg.into(), &o);
+        }
+
+        match games_kv
+            .put(ng_key, serde_json::to_string(&ng).unwrap())?
+            .execute()
+            .await
+        {
+            Ok(_) => {
+                publish_game(&ctx, Some((ng_key.as_str(), &ng))).await;
+                json_response(&ng.comments, &o)
+            }
+            Err(_) => api_error(ApiError::Storage("failed to add comment"), &o),
+        }
+    } else {
+        api_error(ApiError::GameNotFound, &o)
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/teams/{teamkey}/guests",
+    request_body = AddGuestRequest,
+    responses((status = 200, body = Vec<Guest>)),
+    tag = "games"
+)]
+async fn api_add_guest(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    add_guest(req, ctx, false).await
+}
+
+/// Add guests a player is bringing, counted against the team's guest limit for them.
+#[utoipa::path(
+    post,
+    path = "/api/teams/{teamkey}/players/{playerid}/guests",
+    request_body = AddGuestRequest,
+    responses((status = 200, body = Vec<Guest>)),
+    tag = "games"
+)]
+async fn api_add_player_guest(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    add_guest(req, ctx, false).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/{teamkey}/{teamsecret}/guests",
+    request_body = AddGuestRequest,
+    responses((status = 200, body = Vec<Guest>)),
+    tag = "admin"
+)]
+async fn api_admin_add_guest(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    add_guest(req, ctx, true).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/{teamkey}/{teamsecret}/players/{playerid}/guests",
+    request_body = AddGuestRequest,
+    responses((status = 200, body = Vec<Guest>)),
+    tag = "admin"
+)]
+async fn api_admin_add_player_guest(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    add_guest(req, ctx, true).await
+}
+
+/// Add guests to the current game, brought by the player in the path if there is one. Admins can
+/// still do this after the RSVP deadline, and aren't held to the guest limit.
+async fn add_guest(req: Request, ctx: RouteContext<Context>, admin: bool) -> Result<Response> {
+    let o = cors_origin(&req);
+    let team = if admin { get_team_authed(&ctx).await? } else { get_team(&ctx).await? };
+    let team = match team {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
     };
 
     let games_kv = ctx.kv("games")?;
     if let Some(ng_key) = &team.next_game {
         let mut ng: Game = match get_game(&ctx, &team).await? {
             Some(g) => g,
-            None => return error_json("game not found", 404, &o),
+            None => return api_error(ApiError::GameNotFound, &o),
         };
 
-        let mut r = req.clone_mut()?;
-        let body: serde_json::Value = r.json().await?;
-        let guest_name = body["guest_name"].as_str().unwrap_or("");
-        if let Err(msg) = service::add_guests(&mut ng, guest_name) {
-            return error_json(msg, 400, &o);
+        if !admin && service::check_rsvp_open(&team, &ng, jiff::Timestamp::now()).is_err() {
+            return api_error(ApiError::RsvpClosed, &o);
+        }
+
+        let body: AddGuestRequest = read_body(&req).await?;
+        let invited_by = ctx.param("playerid").map(String::as_str);
+        let limit = if admin { None } else { team.guest_limit };
+        if let Err(msg) = service::add_guests(&team, &mut ng, &body.guest_name, invited_by, limit) {
+            return api_error(msg.into(), &o);
         }
 
         match games_kv
@@ -402,50 +1171,89 @@ async fn api_add_guest(req: Request, ctx: RouteContext<()>) -> Result<Response>
             .execute()
             .await
         {
-            Ok(_) => json_response(&ng.guests, &o),
-            Err(_) => error_json("failed to add guest", 500, &o),
+            Ok(_) => {
+                publish_game(&ctx, Some((ng_key.as_str(), &ng))).await;
+                json_response(&ng.guests, &o)
+            }
+            Err(_) => api_error(ApiError::Storage("failed to add guest"), &o),
         }
     } else {
-        error_json("game not found", 404, &o)
+        api_error(ApiError::GameNotFound, &o)
     }
 }
 
-async fn api_delete_guest(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+#[utoipa::path(
+    delete,
+    path = "/api/teams/{teamkey}/guests/{guestid}",
+    responses((status = 204)),
+    tag = "games"
+)]
+async fn api_delete_guest(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    delete_guest(req, ctx, false).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/{teamkey}/{teamsecret}/guests/{guestid}",
+    responses((status = 204)),
+    tag = "admin"
+)]
+async fn api_admin_delete_guest(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    delete_guest(req, ctx, true).await
+}
+
+/// Remove a guest from the current game. Admins can still do this after the RSVP deadline.
+async fn delete_guest(req: Request, ctx: RouteContext<Context>, admin: bool) -> Result<Response> {
     let o = cors_origin(&req);
-    let idx: usize = ctx
-        .param("idx")
-        .unwrap()
-        .parse()
-        .unwrap_or(usize::MAX);
+    let guest_id = ctx.param("guestid").unwrap().clone();
 
-    let team = match get_team(&ctx).await? {
+    let team = if admin { get_team_authed(&ctx).await? } else { get_team(&ctx).await? };
+    let team = match team {
         Some(t) => t,
-        None => return error_json("team not found", 404, &o),
+        None => return api_error(ApiError::TeamNotFound, &o),
     };
 
     let games_kv = ctx.kv("games")?;
     if let Some(ng_key) = &team.next_game {
         let mut ng: Game = match get_game(&ctx, &team).await? {
             Some(g) => g,
-            None => return error_json("game not found", 404, &o),
+            None => return api_error(ApiError::GameNotFound, &o),
         };
 
-        service::delete_guest(&mut ng, idx);
+        if !admin && service::check_rsvp_open(&team, &ng, jiff::Timestamp::now()).is_err() {
+            return api_error(ApiError::RsvpClosed, &o);
+        }
+
+        // v1 clients only see guests' names, so they say which one by position.
+        let guest_id = if is_v1(&req) { v1::guest_id(&ng, &guest_id) } else { Some(guest_id) };
+        if let Some(id) = guest_id {
+            service::delete_guest(&mut ng, &id);
+        }
 
         match games_kv
             .put(ng_key, serde_json::to_string(&ng).unwrap())?
             .execute()
             .await
         {
-            Ok(_) => no_content_response(&o),
-            Err(_) => error_json("failed to delete guest", 500, &o),
+            Ok(_) => {
+                publish_game(&ctx, Some((ng_key.as_str(), &ng))).await;
+                no_content_response(&o)
+            }
+            Err(_) => api_error(ApiError::Storage("failed to delete guest"), &o),
         }
     } else {
-        error_json("game not found", 404, &o)
+        api_error(ApiError::GameNotFound, &o)
     }
 }
 
-async fn api_new_game(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+#[utoipa::path(
+    post,
+    path = "/api/teams/{teamkey}/new_game",
+    request_body = NewGameRequest,
+    responses((status = 200, body = Game)),
+    tag = "games"
+)]
+async fn api_new_game(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
     let o = cors_origin(&req);
     let key = ctx.param("teamkey").unwrap().clone();
 
@@ -454,14 +1262,11 @@ async fn api_new_game(req: Request, ctx: RouteContext<()>) -> Result<Response> {
 
     let mut team: Team = match get_team(&ctx).await? {
         Some(t) => t,
-        None => return error_json("team not found", 404, &o),
+        None => return api_error(ApiError::TeamNotFound, &o),
     };
 
-    let mut r = req.clone_mut()?;
-    let body: serde_json::Value = r.json().await.unwrap_or(serde_json::json!({}));
-    let description = body["description"].as_str().unwrap_or("").to_string();
-
-    let ng = service::make_new_game(&team, description);
+    let body: NewGameRequest = read_body(&req).await?;
+    let ng = service::make_new_game(&team, body.description);
     let ng_key = random::hex_string();
 
     if games_kv
@@ -470,86 +1275,367 @@ async fn api_new_game(req: Request, ctx: RouteContext<()>) -> Result<Response> {
         .await
         .is_err()
     {
-        return error_json("failed to create next game", 500, &o);
+        return api_error(ApiError::Storage("failed to create next game"), &o);
     }
 
+    service::archive_game(&mut team);
     team.next_game = Some(ng_key);
 
-    match teams_kv
+    if teams_kv
         .put(&key, serde_json::to_string(&team).unwrap())?
         .execute()
         .await
+        .is_err()
     {
-        Ok(_) => json_response(&ng, &o),
-        Err(_) => error_json("failed to set next game for team", 500, &o),
+        return api_error(ApiError::Storage("failed to set next game for team"), &o);
     }
+
+    publish_game(&ctx, team.next_game.as_deref().map(|k| (k, &ng))).await;
+    schedule_game(&games_kv, &key, team.next_game.as_deref().unwrap(), &ng).await;
+
+    let message = service::new_game_push(&team, &ng, &team_link(&key));
+    push_to_team(&ctx, &team, &key, None, message);
+
+    json_response(&ng, &o)
 }
 
-async fn api_save_squads(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+#[utoipa::path(
+    put,
+    path = "/api/teams/{teamkey}/squads",
+    request_body = SquadsRequest,
+    responses((status = 204)),
+    tag = "squads"
+)]
+async fn api_save_squads(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
     let o = cors_origin(&req);
     let team = match get_team(&ctx).await? {
         Some(t) => t,
-        None => return error_json("team not found", 404, &o),
+        None => return api_error(ApiError::TeamNotFound, &o),
     };
 
     let games_kv = ctx.kv("games")?;
     if let Some(ng_key) = &team.next_game {
         let mut ng: Game = match get_game(&ctx, &team).await? {
             Some(g) => g,
-            None => return error_json("game not found", 404, &o),
+            None => return api_error(ApiError::GameNotFound, &o),
         };
 
-        let mut r = req.clone_mut()?;
-        let body: serde_json::Value = r.json().await?;
-
-        if let Some(squads) = body["squads"].as_object() {
-            ng.squads = squads
-                .iter()
-                .map(|(k, v)| (k.clone(), v.as_str().unwrap_or("").to_string()))
-                .collect();
+        let body: SquadsRequest = read_body(&req).await?;
+        if let Some(squads) = body.squads {
+            ng.squads = squads;
         }
-
-        if let Some(assignments) = body["assignments"].as_object() {
-            let a = assignments
-                .iter()
-                .map(|(k, v)| (k.clone(), v.as_str().unwrap_or("").to_string()))
-                .collect();
-            service::save_squad_assignments(&mut ng, a);
+        if let Some(assignments) = body.assignments {
+            service::save_squad_assignments(&mut ng, assignments);
         }
+        // A save replaces the squad board, including ops made since it was read.
+        ng.squad_revision += 1;
 
         match games_kv
             .put(ng_key, serde_json::to_string(&ng).unwrap())?
             .execute()
             .await
         {
-            Ok(_) => no_content_response(&o),
-            Err(_) => error_json("failed to save squads", 500, &o),
+            Ok(_) => {
+                publish_game(&ctx, Some((ng_key.as_str(), &ng))).await;
+                no_content_response(&o)
+            }
+            Err(_) => api_error(ApiError::Storage("failed to save squads"), &o),
         }
     } else {
-        error_json("game not found", 404, &o)
+        api_error(ApiError::GameNotFound, &o)
     }
 }
 
-async fn api_reminder_ics(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
-    let team = match get_team(&ctx).await? {
+/// Only admins can set an address, so the team link can't be used to sign strangers up for mail.
+/// Anyone can opt a player out again with `DELETE .../contact`.
+#[utoipa::path(
+    put,
+    path = "/api/admin/{teamkey}/{teamsecret}/players/{playerid}/contact",
+    request_body = ContactRequest,
+    responses((status = 204)),
+    tag = "admin"
+)]
+async fn api_set_contact(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let key = ctx.param("teamkey").unwrap().clone();
+    let pid = ctx.param("playerid").unwrap().clone();
+
+    let teams_kv = ctx.kv("teams")?;
+
+    let mut team = match get_team_authed(&ctx).await? {
         Some(t) => t,
-        None => return Response::error("not found", 404),
-    };
-    let game = match get_game(&ctx, &team).await? {
-        Some(g) => g,
-        None => return Response::error("no game", 404),
-    };
-    let date = match game.date {
-        Some(d) => d,
-        None => return Response::error("no date", 404),
+        None => return api_error(ApiError::TeamNotFound, &o),
     };
 
-    use jiff::ToSpan;
-    let reminder = date.checked_sub(1.days()).unwrap();
-    let key = ctx.param("teamkey").unwrap();
+    let contact: ContactRequest = read_body(&req).await?;
+    if let Err(msg) = service::set_contact(&mut team, &pid, &contact) {
+        return api_error(msg.into(), &o);
+    }
 
-    let mut lines = vec![
-        "BEGIN:VCALENDAR".to_string(),
+    match teams_kv
+        .put(&key, serde_json::to_string(&team).unwrap())?
+        .execute()
+        .await
+    {
+        Ok(_) => no_content_response(&o),
+        Err(_) => api_error(ApiError::Storage("failed to save contact"), &o),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/teams/{teamkey}/players/{playerid}/contact",
+    responses((status = 204)),
+    tag = "players"
+)]
+async fn api_delete_contact(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let key = ctx.param("teamkey").unwrap().clone();
+    let pid = ctx.param("playerid").unwrap().clone();
+
+    let teams_kv = ctx.kv("teams")?;
+
+    let mut team = match get_team(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    service::remove_contact(&mut team, &pid);
+
+    match teams_kv
+        .put(&key, serde_json::to_string(&team).unwrap())?
+        .execute()
+        .await
+    {
+        Ok(_) => no_content_response(&o),
+        Err(_) => api_error(ApiError::Storage("failed to delete contact"), &o),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/teams/{teamkey}/players/{playerid}/push_subscriptions",
+    request_body = PushSubscription,
+    responses((status = 204)),
+    tag = "players"
+)]
+async fn api_add_push_subscription(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let key = ctx.param("teamkey").unwrap().clone();
+    let pid = ctx.param("playerid").unwrap().clone();
+
+    let teams_kv = ctx.kv("teams")?;
+
+    let mut team = match get_team(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    let sub: PushSubscription = read_body(&req).await?;
+    if let Err(msg) = service::add_push_subscription(&mut team, &pid, sub) {
+        return api_error(msg.into(), &o);
+    }
+
+    match teams_kv
+        .put(&key, serde_json::to_string(&team).unwrap())?
+        .execute()
+        .await
+    {
+        Ok(_) => no_content_response(&o),
+        Err(_) => api_error(ApiError::Storage("failed to save push subscription"), &o),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/teams/{teamkey}/players/{playerid}/push_subscriptions",
+    request_body = RemovePushSubscriptionRequest,
+    responses((status = 204)),
+    tag = "players"
+)]
+async fn api_delete_push_subscription(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let key = ctx.param("teamkey").unwrap().clone();
+    let pid = ctx.param("playerid").unwrap().clone();
+
+    let teams_kv = ctx.kv("teams")?;
+
+    let mut team = match get_team(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    let body: RemovePushSubscriptionRequest = read_body(&req).await?;
+    service::remove_push_subscription(&mut team, &pid, &body.endpoint);
+
+    match teams_kv
+        .put(&key, serde_json::to_string(&team).unwrap())?
+        .execute()
+        .await
+    {
+        Ok(_) => no_content_response(&o),
+        Err(_) => api_error(ApiError::Storage("failed to delete push subscription"), &o),
+    }
+}
+
+/// This API, described as OpenAPI 3.
+#[utoipa::path(
+    get,
+    path = "/api/openapi.json",
+    responses((status = 200, body = Object)),
+    tag = "meta"
+)]
+async fn api_openapi(req: Request, _ctx: RouteContext<Context>) -> Result<Response> {
+    json_response(&openapi::spec(), &cors_origin(&req))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/push/public_key",
+    responses((status = 200, body = PushPublicKeyResponse)),
+    tag = "players"
+)]
+async fn api_push_public_key(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    match push::Vapid::from_env(&ctx.env) {
+        Some(vapid) => json_response(&PushPublicKeyResponse { public_key: vapid.public_key() }, &o),
+        None => api_error(ApiError::PushNotConfigured, &o),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/teams/{teamkey}/events",
+    responses((status = 200, content_type = "text/event-stream", description = "The current game, sent again whenever it changes")),
+    tag = "games"
+)]
+async fn api_events(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let key = ctx.param("teamkey").unwrap().clone();
+
+    if get_team(&ctx).await?.is_none() {
+        return api_error(ApiError::TeamNotFound, &o);
+    }
+
+    let headers = Headers::new();
+    let _ = headers.set(room::ORIGIN_HEADER, &o);
+    let mut init = RequestInit::new();
+    init.with_headers(headers);
+    let stub = ctx.durable_object("rooms")?.id_from_name(&key)?.get_stub()?;
+    stub.fetch_with_request(Request::new_with_init(&format!("{}/events", room::ROOM_URL), &init)?)
+        .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/teams/{teamkey}/squads/ws",
+    responses((status = 101, description = "The squad board WebSocket: send `SquadOp`s, receive `SquadBoardEvent`s")),
+    tag = "squads"
+)]
+async fn api_squads_ws(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let key = ctx.param("teamkey").unwrap().clone();
+
+    let upgrade = req.headers().get("Upgrade")?.unwrap_or_default();
+    if !upgrade.eq_ignore_ascii_case("websocket") {
+        return api_error(ApiError::ExpectedWebSocket, &o);
+    }
+    if get_team(&ctx).await?.is_none() {
+        return api_error(ApiError::TeamNotFound, &o);
+    }
+
+    let headers = req.headers().clone();
+    let _ = headers.set(room::TEAM_HEADER, &key);
+    let _ = headers.set(room::ORIGIN_HEADER, &o);
+    let mut init = RequestInit::new();
+    init.with_headers(headers);
+    let stub = ctx.durable_object("rooms")?.id_from_name(&key)?.get_stub()?;
+    stub.fetch_with_request(Request::new_with_init(&format!("{}/squads", room::ROOM_URL), &init)?)
+        .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/teams/{teamkey}/squads/auto",
+    request_body = AutoSquadsRequest,
+    responses((status = 200, body = AutoSquadsResponse)),
+    tag = "squads"
+)]
+async fn api_auto_squads(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let team = match get_team(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    let games_kv = ctx.kv("games")?;
+    if let Some(ng_key) = &team.next_game {
+        let mut ng: Game = match get_game(&ctx, &team).await? {
+            Some(g) => g,
+            None => return api_error(ApiError::GameNotFound, &o),
+        };
+
+        let body: AutoSquadsRequest = read_body(&req).await?;
+        let seed = body.seed.unwrap_or_else(random::seed);
+
+        let previous: Option<Game> = match team.past_games.last() {
+            Some(k) => load_game(&games_kv, k).await?,
+            None => None,
+        };
+        if let Err(msg) = service::auto_squads(&team, &mut ng, previous.as_ref(), seed) {
+            return api_error(msg.into(), &o);
+        }
+
+        match games_kv
+            .put(ng_key, serde_json::to_string(&ng).unwrap())?
+            .execute()
+            .await
+        {
+            Ok(_) => {
+                publish_game(&ctx, Some((ng_key.as_str(), &ng))).await;
+                json_response(
+                    &AutoSquadsResponse {
+                        seed,
+                        squads: ng.squads,
+                        assignments: ng.squad_assignments,
+                    },
+                    &o,
+                )
+            }
+            Err(_) => api_error(ApiError::Storage("failed to save squads"), &o),
+        }
+    } else {
+        api_error(ApiError::GameNotFound, &o)
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/teams/{teamkey}/reminder.ics",
+    responses((status = 200, content_type = "text/calendar", body = String)),
+    tag = "games"
+)]
+async fn api_reminder_ics(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let team = match get_team(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+    let game = match get_game(&ctx, &team).await? {
+        Some(g) => g,
+        None => return api_error(ApiError::GameNotFound, &o),
+    };
+    let date = match game.date {
+        Some(d) => d,
+        None => return api_error(ApiError::NoDate, &o),
+    };
+
+    use jiff::ToSpan;
+    let reminder = date.checked_sub(1.days()).unwrap();
+    let key = ctx.param("teamkey").unwrap();
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
         "VERSION:2.0".to_string(),
         "PRODID:-//nextgame//EN".to_string(),
         "BEGIN:VEVENT".to_string(),
@@ -579,20 +1665,33 @@ async fn api_reminder_ics(_req: Request, ctx: RouteContext<()>) -> Result<Respon
 
 // --- Admin API ---
 
-async fn api_admin(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+#[utoipa::path(
+    get,
+    path = "/api/admin/{teamkey}/{teamsecret}",
+    responses((status = 200, body = TeamPageResponse)),
+    tag = "admin"
+)]
+async fn api_admin(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
     let o = cors_origin(&req);
     let key = ctx.param("teamkey").unwrap().clone();
 
     let team = match get_team_authed(&ctx).await? {
         Some(t) => t,
-        None => return error_json("team not found", 404, &o),
+        None => return api_error(ApiError::TeamNotFound, &o),
     };
 
     let game = get_game(&ctx, &team).await?;
-    json_response(&service::team_response(&team, &key, game), &o)
+    json_response(&service::admin_team_response(&team, &key, game), &o)
 }
 
-async fn api_update_settings(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+#[utoipa::path(
+    put,
+    path = "/api/admin/{teamkey}/{teamsecret}/settings",
+    request_body = SettingsRequest,
+    responses((status = 200, body = TeamPageResponse)),
+    tag = "admin"
+)]
+async fn api_update_settings(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
     let o = cors_origin(&req);
     let key = ctx.param("teamkey").unwrap().clone();
 
@@ -600,11 +1699,10 @@ async fn api_update_settings(req: Request, ctx: RouteContext<()>) -> Result<Resp
 
     let mut team = match get_team_authed(&ctx).await? {
         Some(t) => t,
-        None => return error_json("team not found", 404, &o),
+        None => return api_error(ApiError::TeamNotFound, &o),
     };
 
-    let mut r = req.clone_mut()?;
-    let body: serde_json::Value = r.json().await?;
+    let body: SettingsRequest = read_body(&req).await?;
     service::apply_settings(&mut team, &body);
 
     match teams_kv
@@ -614,13 +1712,20 @@ async fn api_update_settings(req: Request, ctx: RouteContext<()>) -> Result<Resp
     {
         Ok(_) => {
             let game = get_game(&ctx, &team).await?;
-            json_response(&service::team_response(&team, &key, game), &o)
+            json_response(&service::admin_team_response(&team, &key, game), &o)
         }
-        Err(_) => error_json("failed to update team settings", 500, &o),
+        Err(_) => api_error(ApiError::Storage("failed to update team settings"), &o),
     }
 }
 
-async fn api_add_players(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+#[utoipa::path(
+    post,
+    path = "/api/admin/{teamkey}/{teamsecret}/players",
+    request_body = AddPlayersRequest,
+    responses((status = 200, body = HashMap<PlayerID, String>)),
+    tag = "admin"
+)]
+async fn api_add_players(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
     let o = cors_origin(&req);
     let key = ctx.param("teamkey").unwrap().clone();
 
@@ -628,14 +1733,12 @@ async fn api_add_players(req: Request, ctx: RouteContext<()>) -> Result<Response
 
     let mut team = match get_team_authed(&ctx).await? {
         Some(t) => t,
-        None => return error_json("team not found", 404, &o),
+        None => return api_error(ApiError::TeamNotFound, &o),
     };
 
-    let mut r = req.clone_mut()?;
-    let body: serde_json::Value = r.json().await?;
-    let names = body["names"].as_str().unwrap_or("");
-    if let Err(msg) = service::add_players(&mut team, names) {
-        return error_json(msg, 400, &o);
+    let body: AddPlayersRequest = read_body(&req).await?;
+    if let Err(msg) = service::add_players(&mut team, &body.names) {
+        return api_error(msg.into(), &o);
     }
 
     match teams_kv
@@ -643,107 +1746,298 @@ async fn api_add_players(req: Request, ctx: RouteContext<()>) -> Result<Response
         .execute()
         .await
     {
-        Ok(_) => json_response(&team.players, &o),
-        Err(_) => error_json("failed to add players", 500, &o),
+        Ok(_) => json_response(&service::roster_names(&team), &o),
+        Err(_) => api_error(ApiError::Storage("failed to add players"), &o),
+    }
+}
+
+/// The whole team with every game it has, as a versioned archive.
+#[utoipa::path(
+    get,
+    path = "/api/admin/{teamkey}/{teamsecret}/export",
+    responses((status = 200, body = TeamArchive)),
+    tag = "admin"
+)]
+async fn api_export_team(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+
+    let team = match get_team_authed(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    let keys = team.past_games.iter().chain(team.next_game.iter());
+    let games: HashMap<String, Game> = load_games(&ctx.kv("games")?, keys).await?.into_iter().collect();
+
+    json_response(&service::team_archive(team, games, jiff::Timestamp::now()), &o)
+}
+
+// --- Helper: the team's raw document and what it refers to, if the secret matches ---
+// Goes around load_team, so admins can still get at a team that can't be read.
+async fn get_raw_team(ctx: &RouteContext<Context>) -> Result<Option<(String, migrate::TeamRefs)>> {
+    let key = ctx.param("teamkey").unwrap();
+    let secret = ctx.param("teamsecret").unwrap();
+    let text = match ctx.kv("teams")?.get(key).text().await? {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+    match migrate::TeamRefs::from_str(&text) {
+        Some(refs) if refs.secret == *secret => Ok(Some((text, refs))),
+        _ => Err(ApiError::WrongSecret.into()),
     }
 }
 
-async fn api_delete_player(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+/// The team and all its games as stored, with why each can't be read if it can't.
+#[utoipa::path(
+    get,
+    path = "/api/admin/{teamkey}/{teamsecret}/raw",
+    responses((status = 200, body = Vec<RawDocument>)),
+    tag = "admin"
+)]
+async fn api_raw_documents(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
     let o = cors_origin(&req);
     let key = ctx.param("teamkey").unwrap().clone();
-    let pid = ctx.param("playerid").unwrap().clone();
 
-    let teams_kv = ctx.kv("teams")?;
+    let (text, refs) = match get_raw_team(&ctx).await? {
+        Some(raw) => raw,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    let mut docs = vec![RawDocument {
+        kind: "team".to_string(),
+        key,
+        error: migrate::team_from_str(&text).err().map(|e| e.to_string()),
+        document: Some(text),
+    }];
+    let games_kv = ctx.kv("games")?;
+    for game_key in refs.game_keys() {
+        let text = games_kv.get(game_key).text().await?;
+        docs.push(RawDocument {
+            kind: "game".to_string(),
+            key: game_key.clone(),
+            error: text.as_deref().and_then(|t| migrate::game_from_str(t).err()).map(|e| e.to_string()),
+            document: text,
+        });
+    }
+    json_response(&docs, &o)
+}
+
+/// Replace a stored team or game with a fixed version. The fix has to be readable, and a game has
+/// to be one the team refers to.
+#[utoipa::path(
+    put,
+    path = "/api/admin/{teamkey}/{teamsecret}/raw/{kind}/{key}",
+    request_body(content = serde_json::Value, description = "The document to store, as JSON"),
+    responses((status = 204)),
+    tag = "admin"
+)]
+async fn api_repair_document(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let team_key = ctx.param("teamkey").unwrap().clone();
+    let kind = ctx.param("kind").unwrap().clone();
+    let key = ctx.param("key").unwrap().clone();
+
+    let (_, refs) = match get_raw_team(&ctx).await? {
+        Some(raw) => raw,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    let mut r = req.clone_mut()?;
+    let body: serde_json::Value = match r.json().await {
+        Ok(b) => b,
+        Err(e) => return api_error(ApiError::InvalidDocument { kind, detail: e.to_string() }, &o),
+    };
+    match kind.as_str() {
+        "team" if key == team_key => {
+            let team = match migrate::team_from_value(body) {
+                Ok(loaded) => loaded.doc,
+                Err(e) => return api_error(ApiError::InvalidDocument { kind: "team".into(), detail: e.to_string() }, &o),
+            };
+            ctx.kv("teams")?
+                .put(&key, serde_json::to_string(&team).unwrap())?
+                .execute()
+                .await?;
+        }
+        "game" if refs.game_keys().any(|k| *k == key) => {
+            let game = match migrate::game_from_value(body) {
+                Ok(loaded) => loaded.doc,
+                Err(e) => return api_error(ApiError::InvalidDocument { kind: "game".into(), detail: e.to_string() }, &o),
+            };
+            ctx.kv("games")?
+                .put(&key, serde_json::to_string(&game).unwrap())?
+                .execute()
+                .await?;
+            if refs.next_game.as_deref() == Some(key.as_str()) {
+                publish_game(&ctx, Some((key.as_str(), &game))).await;
+            }
+        }
+        _ => return api_error(ApiError::DocumentNotFound, &o),
+    }
+    no_content_response(&o)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/{teamkey}/{teamsecret}/players/export",
+    responses((status = 200, content_type = "text/csv", body = String)),
+    tag = "admin"
+)]
+async fn api_export_roster(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+
+    let team = match get_team_authed(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    csv_response(roster_csv::export(&team), "roster.csv", &o)
+}
+
+/// Add players from a CSV. With `dry_run` set, only reports what would happen. Otherwise the
+/// players are added unless any row has an error; duplicates are skipped either way. Imports are
+/// limited to 500 rows and 256 KiB.
+#[utoipa::path(
+    post,
+    path = "/api/admin/{teamkey}/{teamsecret}/players/import",
+    request_body = RosterImportRequest,
+    responses((status = 200, body = RosterImportResponse)),
+    tag = "admin"
+)]
+async fn api_import_roster(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let key = ctx.param("teamkey").unwrap().clone();
 
     let mut team = match get_team_authed(&ctx).await? {
         Some(t) => t,
-        None => return error_json("team not found", 404, &o),
+        None => return api_error(ApiError::TeamNotFound, &o),
     };
 
-    service::delete_player(&mut team, &pid);
+    let Some(text) = read_text_within(&req, roster_csv::MAX_IMPORT_BYTES).await? else {
+        return api_error(CsvError::TooLarge.into(), &o);
+    };
+    let body: RosterImportRequest = parse_body(&req, &text)?;
+    let mut import = match roster_csv::preview(&team, &body.csv) {
+        Ok(i) => i,
+        Err(err) => return api_error(err.into(), &o),
+    };
+    if body.dry_run || !roster_csv::apply(&mut team, &mut import) {
+        return json_response(&import, &o);
+    }
 
-    match teams_kv
+    match ctx
+        .kv("teams")?
         .put(&key, serde_json::to_string(&team).unwrap())?
         .execute()
         .await
     {
-        Ok(_) => no_content_response(&o),
-        Err(_) => error_json("failed to delete player", 500, &o),
+        Ok(_) => json_response(&import, &o),
+        Err(_) => api_error(ApiError::Storage("failed to import players"), &o),
     }
 }
 
-async fn api_reset_game(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+/// Add one player with their profile. `POST .../players` adds several by name only.
+#[utoipa::path(
+    post,
+    path = "/api/admin/{teamkey}/{teamsecret}/players/new",
+    request_body = PlayerUpdate,
+    responses((status = 200, body = TeamPageResponse)),
+    tag = "admin"
+)]
+async fn api_create_player(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
     let o = cors_origin(&req);
     let key = ctx.param("teamkey").unwrap().clone();
 
-    let teams_kv = ctx.kv("teams")?;
-    let games_kv = ctx.kv("games")?;
-
     let mut team = match get_team_authed(&ctx).await? {
         Some(t) => t,
-        None => return error_json("team not found", 404, &o),
+        None => return api_error(ApiError::TeamNotFound, &o),
     };
 
-    if let Some(ng_key) = service::reset_game(&mut team) {
-        let _ = games_kv.delete(&ng_key).await;
+    let body: PlayerUpdate = read_body(&req).await?;
+    if let Err(msg) = service::create_player(&mut team, &body) {
+        return api_error(msg.into(), &o);
     }
 
-    match teams_kv
+    if ctx
+        .kv("teams")?
         .put(&key, serde_json::to_string(&team).unwrap())?
         .execute()
         .await
+        .is_err()
     {
-        Ok(_) => no_content_response(&o),
-        Err(_) => error_json("failed to reset game", 500, &o),
+        return api_error(ApiError::Storage("failed to add player"), &o);
     }
+
+    let game = get_game(&ctx, &team).await?;
+    json_response(&service::admin_team_response(&team, &key, game), &o)
 }
 
-async fn api_game_off(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+/// Rename a player, or (de)activate them. Deactivating also takes them out of the current game
+/// if they hadn't answered yet.
+#[utoipa::path(
+    put,
+    path = "/api/admin/{teamkey}/{teamsecret}/players/{playerid}",
+    request_body = PlayerUpdate,
+    responses((status = 200, body = TeamPageResponse)),
+    tag = "admin"
+)]
+async fn api_update_player(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
     let o = cors_origin(&req);
-    let team = match get_team_authed(&ctx).await? {
+    let key = ctx.param("teamkey").unwrap().clone();
+    let pid = ctx.param("playerid").unwrap().clone();
+
+    let mut team = match get_team_authed(&ctx).await? {
         Some(t) => t,
-        None => return error_json("team not found", 404, &o),
+        None => return api_error(ApiError::TeamNotFound, &o),
     };
 
-    let games_kv = ctx.kv("games")?;
-    if let Some(ng_key) = &team.next_game {
-        let mut ng: Game = match get_game(&ctx, &team).await? {
-            Some(g) => g,
-            None => return error_json("game not found", 404, &o),
-        };
+    let body: PlayerUpdate = read_body(&req).await?;
+    if let Err(msg) = service::update_player(&mut team, &pid, &body) {
+        return api_error(msg.into(), &o);
+    }
 
-        service::toggle_game_off(&mut ng);
+    if ctx
+        .kv("teams")?
+        .put(&key, serde_json::to_string(&team).unwrap())?
+        .execute()
+        .await
+        .is_err()
+    {
+        return api_error(ApiError::Storage("failed to update player"), &o);
+    }
 
-        match games_kv
+    let mut game = get_game(&ctx, &team).await?;
+    if let (Some(ng_key), Some(ng)) = (&team.next_game, game.as_mut())
+        && !team.players[&pid].active
+        && service::drop_unanswered(ng, &pid)
+    {
+        ctx.kv("games")?
             .put(ng_key, serde_json::to_string(&ng).unwrap())?
             .execute()
-            .await
-        {
-            Ok(_) => no_content_response(&o),
-            Err(_) => error_json("failed to toggle game off", 500, &o),
-        }
-    } else {
-        error_json("game not found", 404, &o)
+            .await?;
+        publish_game(&ctx, Some((ng_key.as_str(), ng))).await;
     }
+    json_response(&service::admin_team_response(&team, &key, game), &o)
 }
 
-async fn api_default_squads(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+#[utoipa::path(
+    delete,
+    path = "/api/admin/{teamkey}/{teamsecret}/players/{playerid}",
+    responses((status = 204)),
+    tag = "admin"
+)]
+async fn api_delete_player(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
     let o = cors_origin(&req);
     let key = ctx.param("teamkey").unwrap().clone();
+    let pid = ctx.param("playerid").unwrap().clone();
 
     let teams_kv = ctx.kv("teams")?;
 
     let mut team = match get_team_authed(&ctx).await? {
         Some(t) => t,
-        None => return error_json("team not found", 404, &o),
+        None => return api_error(ApiError::TeamNotFound, &o),
     };
 
-    let mut r = req.clone_mut()?;
-    let body: serde_json::Value = r.json().await?;
-
-    if let Some(squads) = body["squads"].as_object() {
-        service::set_default_squads(&mut team, squads);
-    }
+    service::delete_player(&mut team, &pid);
 
     match teams_kv
         .put(&key, serde_json::to_string(&team).unwrap())?
@@ -751,6 +2045,692 @@ async fn api_default_squads(req: Request, ctx: RouteContext<()>) -> Result<Respo
         .await
     {
         Ok(_) => no_content_response(&o),
-        Err(_) => error_json("failed to update default squads", 500, &o),
+        Err(_) => api_error(ApiError::Storage("failed to delete player"), &o),
+    }
+}
+
+/// Only in v1; v2 has `PUT .../rsvp` instead.
+#[utoipa::path(
+    post,
+    path = "/api/admin/{teamkey}/{teamsecret}/players/{playerid}/play",
+    responses((status = 204)),
+    tag = "admin"
+)]
+async fn api_admin_play(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    admin_set_player_status(req, ctx, RsvpStatus::In, None).await
+}
+
+/// Only in v1; v2 has `PUT .../rsvp` instead.
+#[utoipa::path(
+    post,
+    path = "/api/admin/{teamkey}/{teamsecret}/players/{playerid}/not_play",
+    responses((status = 204)),
+    tag = "admin"
+)]
+async fn api_admin_not_play(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    admin_set_player_status(req, ctx, RsvpStatus::Out, None).await
+}
+
+/// Set any RSVP, note included, on a player's behalf. Only in v2.
+#[utoipa::path(
+    put,
+    path = "/api/v2/admin/{teamkey}/{teamsecret}/players/{playerid}/rsvp",
+    request_body = RsvpRequest,
+    responses((status = 204)),
+    tag = "admin"
+)]
+async fn api_admin_rsvp(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let body: RsvpRequest = read_body(&req).await?;
+    admin_set_player_status(req, ctx, body.status, body.note).await
+}
+
+/// Set a player's status on their behalf, and let them know by email if they opted in.
+/// Unlike players, admins aren't held to the RSVP deadline.
+async fn admin_set_player_status(
+    req: Request,
+    ctx: RouteContext<Context>,
+    status: RsvpStatus,
+    note: Option<String>,
+) -> Result<Response> {
+    let o = cors_origin(&req);
+    let key = ctx.param("teamkey").unwrap().clone();
+    let pid = ctx.param("playerid").unwrap().clone();
+
+    let team = match get_team_authed(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    let games_kv = ctx.kv("games")?;
+    if let Some(ng_key) = &team.next_game {
+        let mut ng: Game = match get_game(&ctx, &team).await? {
+            Some(g) => g,
+            None => return api_error(ApiError::GameNotFound, &o),
+        };
+
+        if let Err(msg) = service::set_player_status(&mut ng, &pid, status, note.as_deref()) {
+            return api_error(msg.into(), &o);
+        }
+
+        if games_kv
+            .put(ng_key, serde_json::to_string(&ng).unwrap())?
+            .execute()
+            .await
+            .is_err()
+        {
+            return api_error(ApiError::Storage("failed to set player status"), &o);
+        }
+
+        publish_game(&ctx, Some((ng_key.as_str(), &ng))).await;
+
+        if let Some(email) = service::rsvp_changed_email(&team, &ng, &pid, status, &team_link(&key)) {
+            email_later(&ctx, vec![email]);
+        }
+
+        no_content_response(&o)
+    } else {
+        api_error(ApiError::GameNotFound, &o)
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/{teamkey}/{teamsecret}/reset_game",
+    responses((status = 204)),
+    tag = "admin"
+)]
+async fn api_reset_game(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let key = ctx.param("teamkey").unwrap().clone();
+
+    let teams_kv = ctx.kv("teams")?;
+    let games_kv = ctx.kv("games")?;
+
+    let mut team = match get_team_authed(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    if let Some(ng_key) = service::reset_game(&mut team) {
+        let _ = games_kv.delete(&ng_key).await;
+    }
+
+    match teams_kv
+        .put(&key, serde_json::to_string(&team).unwrap())?
+        .execute()
+        .await
+    {
+        Ok(_) => {
+            publish_game(&ctx, None).await;
+            no_content_response(&o)
+        }
+        Err(_) => api_error(ApiError::Storage("failed to reset game"), &o),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/{teamkey}/{teamsecret}/game_off",
+    responses((status = 204)),
+    tag = "admin"
+)]
+async fn api_game_off(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let key = ctx.param("teamkey").unwrap().clone();
+    let team = match get_team_authed(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    let games_kv = ctx.kv("games")?;
+    if let Some(ng_key) = team.next_game.clone() {
+        let mut ng: Game = match get_game(&ctx, &team).await? {
+            Some(g) => g,
+            None => return api_error(ApiError::GameNotFound, &o),
+        };
+
+        service::toggle_game_off(&mut ng);
+
+        if games_kv
+            .put(&ng_key, serde_json::to_string(&ng).unwrap())?
+            .execute()
+            .await
+            .is_err()
+        {
+            return api_error(ApiError::Storage("failed to toggle game off"), &o);
+        }
+
+        publish_game(&ctx, Some((ng_key.as_str(), &ng))).await;
+
+        if ng.is_game_off {
+            email_later(&ctx, service::game_off_emails(&team, &ng, &team_link(&key)));
+        }
+
+        if ng.is_game_off {
+            let message = service::game_off_push(&team, &ng, &team_link(&key));
+            push_to_team(&ctx, &team, &key, Some(&ng), message);
+        }
+
+        no_content_response(&o)
+    } else {
+        api_error(ApiError::GameNotFound, &o)
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/{teamkey}/{teamsecret}/default_squads",
+    request_body = DefaultSquadsRequest,
+    responses((status = 204)),
+    tag = "admin"
+)]
+async fn api_default_squads(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let key = ctx.param("teamkey").unwrap().clone();
+
+    let teams_kv = ctx.kv("teams")?;
+
+    let mut team = match get_team_authed(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    let body: DefaultSquadsRequest = read_body(&req).await?;
+    service::set_default_squads(&mut team, &body.squads);
+
+    match teams_kv
+        .put(&key, serde_json::to_string(&team).unwrap())?
+        .execute()
+        .await
+    {
+        Ok(_) => no_content_response(&o),
+        Err(_) => api_error(ApiError::Storage("failed to update default squads"), &o),
+    }
+}
+
+/// Add a regular guest to the roster, turning their appearances as a guest in this and earlier
+/// games into appearances as the new player.
+#[utoipa::path(
+    post,
+    path = "/api/admin/{teamkey}/{teamsecret}/guests/promote",
+    request_body = PromoteGuestRequest,
+    responses((status = 200, body = PromoteGuestResponse)),
+    tag = "admin"
+)]
+async fn api_promote_guest(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let key = ctx.param("teamkey").unwrap().clone();
+
+    let mut team = match get_team_authed(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    let body: PromoteGuestRequest = read_body(&req).await?;
+    let pid = match service::promote_guest(&mut team, &body.name) {
+        Ok(pid) => pid,
+        Err(msg) => return api_error(msg.into(), &o),
+    };
+
+    let games_kv = ctx.kv("games")?;
+    let mut converted = load_games(&games_kv, team.past_games.iter().chain(team.next_game.iter())).await?;
+    converted.retain_mut(|(_, game)| service::convert_guest(game, &body.name, &pid));
+    if converted.is_empty() {
+        return api_error(ApiError::GuestNotFound, &o);
+    }
+
+    // Save the games first, as merging does, so the roster never gains a player whose games
+    // still list them as a guest.
+    for (game_key, game) in &converted {
+        if games_kv
+            .put(game_key, serde_json::to_string(game).unwrap())?
+            .execute()
+            .await
+            .is_err()
+        {
+            return api_error(ApiError::Storage("failed to promote guest"), &o);
+        }
+        if team.next_game.as_deref() == Some(game_key.as_str()) {
+            publish_game(&ctx, Some((game_key.as_str(), game))).await;
+        }
+    }
+    if ctx
+        .kv("teams")?
+        .put(&key, serde_json::to_string(&team).unwrap())?
+        .execute()
+        .await
+        .is_err()
+    {
+        return api_error(ApiError::Storage("failed to promote guest"), &o);
+    }
+
+    json_response(
+        &PromoteGuestResponse {
+            player_id: pid,
+            games: converted.len(),
+        },
+        &o,
+    )
+}
+
+/// Fold a duplicate player into another, across the roster and every game the team has played.
+#[utoipa::path(
+    post,
+    path = "/api/admin/{teamkey}/{teamsecret}/players/{playerid}/merge",
+    request_body = MergePlayerRequest,
+    responses((status = 200, body = TeamPageResponse)),
+    tag = "admin"
+)]
+async fn api_merge_player(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let key = ctx.param("teamkey").unwrap().clone();
+    let from = ctx.param("playerid").unwrap().clone();
+
+    let mut team = match get_team_authed(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    let body: MergePlayerRequest = read_body(&req).await?;
+    if let Err(msg) = service::merge_players(&mut team, &from, &body.into) {
+        return api_error(msg.into(), &o);
+    }
+
+    let games_kv = ctx.kv("games")?;
+    let mut merged = load_games(&games_kv, team.past_games.iter().chain(team.next_game.iter())).await?;
+    merged.retain_mut(|(_, game)| service::merge_player_in_game(game, &from, &body.into));
+
+    // Save the games first, so none is left pointing at a player the roster no longer has.
+    for (game_key, game) in &merged {
+        if games_kv
+            .put(game_key, serde_json::to_string(game).unwrap())?
+            .execute()
+            .await
+            .is_err()
+        {
+            return api_error(ApiError::Storage("failed to merge players"), &o);
+        }
+        if team.next_game.as_deref() == Some(game_key.as_str()) {
+            publish_game(&ctx, Some((game_key.as_str(), game))).await;
+        }
+    }
+    if ctx
+        .kv("teams")?
+        .put(&key, serde_json::to_string(&team).unwrap())?
+        .execute()
+        .await
+        .is_err()
+    {
+        return api_error(ApiError::Storage("failed to merge players"), &o);
+    }
+
+    let game = get_game(&ctx, &team).await?;
+    json_response(&service::admin_team_response(&team, &key, game), &o)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/teams/{teamkey}/join_requests",
+    request_body = JoinTeamRequest,
+    responses((status = 200, body = JoinRequest)),
+    tag = "players"
+)]
+async fn api_request_to_join(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let key = ctx.param("teamkey").unwrap().clone();
+
+    let mut team = match get_team(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    let body: JoinTeamRequest = read_body(&req).await?;
+    let request = match service::request_to_join(&mut team, &body.name, jiff::Timestamp::now()) {
+        Ok(request) => request,
+        Err(msg) => return api_error(msg.into(), &o),
+    };
+
+    match ctx
+        .kv("teams")?
+        .put(&key, serde_json::to_string(&team).unwrap())?
+        .execute()
+        .await
+    {
+        Ok(_) => json_response(&request, &o),
+        Err(_) => api_error(ApiError::Storage("failed to request to join"), &o),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/{teamkey}/{teamsecret}/join_requests/{requestid}/approve",
+    responses((status = 200, body = TeamPageResponse)),
+    tag = "admin"
+)]
+async fn api_approve_join_request(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    decide_join_request(req, ctx, true).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/{teamkey}/{teamsecret}/join_requests/{requestid}/reject",
+    responses((status = 200, body = TeamPageResponse)),
+    tag = "admin"
+)]
+async fn api_reject_join_request(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    decide_join_request(req, ctx, false).await
+}
+
+/// Approve or reject a join request. Approved newcomers join the current game's roster the next
+/// time it's loaded, like players added by an admin.
+async fn decide_join_request(req: Request, ctx: RouteContext<Context>, approve: bool) -> Result<Response> {
+    let o = cors_origin(&req);
+    let key = ctx.param("teamkey").unwrap().clone();
+    let request_id = ctx.param("requestid").unwrap().clone();
+
+    let mut team = match get_team_authed(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    let decided = if approve {
+        service::approve_join_request(&mut team, &request_id).map(|_| ())
+    } else {
+        service::reject_join_request(&mut team, &request_id)
+    };
+    if let Err(msg) = decided {
+        return api_error(msg.into(), &o);
+    }
+
+    match ctx
+        .kv("teams")?
+        .put(&key, serde_json::to_string(&team).unwrap())?
+        .execute()
+        .await
+    {
+        Ok(_) => {
+            let game = get_game(&ctx, &team).await?;
+            json_response(&service::admin_team_response(&team, &key, game), &o)
+        }
+        Err(_) => api_error(ApiError::Storage("failed to update join request"), &o),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/{teamkey}/{teamsecret}/ratings",
+    request_body = RatingsRequest,
+    responses((status = 200, body = HashMap<PlayerID, f64>)),
+    tag = "admin"
+)]
+async fn api_set_ratings(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let key = ctx.param("teamkey").unwrap().clone();
+
+    let teams_kv = ctx.kv("teams")?;
+
+    let mut team = match get_team_authed(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    let body: RatingsRequest = read_body(&req).await?;
+    service::set_ratings(&mut team, &body.ratings);
+
+    match teams_kv
+        .put(&key, serde_json::to_string(&team).unwrap())?
+        .execute()
+        .await
+    {
+        Ok(_) => json_response(&team.ratings, &o),
+        Err(_) => api_error(ApiError::Storage("failed to update ratings"), &o),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/teams/{teamkey}/games/{gamekey}/results",
+    request_body = ResultsRequest,
+    responses((status = 200, body = TeamPageResponse)),
+    tag = "results"
+)]
+async fn api_record_results(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let gk = ctx.param("gamekey").cloned();
+    let body: ResultsRequest = read_body(&req).await?;
+    record_results(req, ctx, false, gk, body).await
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/{teamkey}/{teamsecret}/games/{gamekey}/results",
+    request_body = ResultsRequest,
+    responses((status = 200, body = TeamPageResponse)),
+    tag = "admin"
+)]
+async fn api_admin_record_results(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let gk = ctx.param("gamekey").cloned();
+    let body: ResultsRequest = read_body(&req).await?;
+    record_results(req, ctx, true, gk, body).await
+}
+
+/// Squad scores for the current game. Kept from before results had stats; the same as
+/// `PUT .../games/{gamekey}/results` with only `scores`.
+#[utoipa::path(
+    put,
+    path = "/api/admin/{teamkey}/{teamsecret}/scores",
+    request_body = ScoresRequest,
+    responses((status = 200, body = TeamPageResponse)),
+    tag = "admin"
+)]
+async fn api_record_scores(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let body: ScoresRequest = read_body(&req).await?;
+    let results = ResultsRequest { scores: Some(body.scores), stats: None };
+    record_results(req, ctx, true, None, results).await
+}
+
+/// Record scores and scorers for one of the team's games, current or past, or the current one
+/// without a key. Players can only do this when the team has opened results up to them, and
+/// only results an admin records update ratings.
+async fn record_results(
+    req: Request,
+    ctx: RouteContext<Context>,
+    admin: bool,
+    game_key: Option<String>,
+    body: ResultsRequest,
+) -> Result<Response> {
+    let o = cors_origin(&req);
+    let key = ctx.param("teamkey").unwrap().clone();
+
+    let team = if admin { get_team_authed(&ctx).await? } else { get_team(&ctx).await? };
+    let mut team = match team {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+    if !admin && !team.results_open {
+        return api_error(ApiError::AdminOnly("only admins can record results"), &o);
+    }
+    let gk = match game_key.or_else(|| team.next_game.clone()) {
+        Some(k) if service::has_game(&team, &k) => k,
+        _ => return api_error(ApiError::GameNotFound, &o),
+    };
+    let games_kv = ctx.kv("games")?;
+    let mut game = match load_game(&games_kv, &gk).await? {
+        Some(g) => g,
+        None => return api_error(ApiError::GameNotFound, &o),
+    };
+
+    if let Err(msg) = service::record_results(&mut game, &body) {
+        return api_error(msg.into(), &o);
+    }
+    if admin {
+        service::apply_game_ratings(&mut team, &mut game);
+    }
+
+    if games_kv
+        .put(&gk, serde_json::to_string(&game).unwrap())?
+        .execute()
+        .await
+        .is_err()
+    {
+        return api_error(ApiError::Storage("failed to record results"), &o);
+    }
+    // Only the current game is published; results for a past one don't change what's live.
+    let ng = if team.next_game.as_deref() == Some(gk.as_str()) {
+        publish_game(&ctx, Some((gk.as_str(), &game))).await;
+        Some(game)
+    } else {
+        get_game(&ctx, &team).await?
+    };
+    if !admin {
+        return json_response(&service::team_response(&team, &key, ng), &o);
+    }
+
+    match ctx
+        .kv("teams")?
+        .put(&key, serde_json::to_string(&team).unwrap())?
+        .execute()
+        .await
+    {
+        Ok(_) => json_response(&service::admin_team_response(&team, &key, ng), &o),
+        Err(_) => api_error(ApiError::Storage("failed to update ratings"), &o),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/teams/{teamkey}/games/{gamekey}/players/{playerid}/mvp_vote",
+    request_body = MvpVoteRequest,
+    responses((status = 204)),
+    tag = "results"
+)]
+async fn api_mvp_vote(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+    let gk = ctx.param("gamekey").unwrap().clone();
+    let pid = ctx.param("playerid").unwrap().clone();
+
+    let team = match get_team(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+    if !service::has_game(&team, &gk) {
+        return api_error(ApiError::GameNotFound, &o);
+    }
+    let games_kv = ctx.kv("games")?;
+    let mut game = match load_game(&games_kv, &gk).await? {
+        Some(g) => g,
+        None => return api_error(ApiError::GameNotFound, &o),
+    };
+
+    let body: MvpVoteRequest = read_body(&req).await?;
+    if let Err(msg) = service::cast_mvp_vote(&team, &mut game, &pid, &body.candidate, jiff::Timestamp::now()) {
+        return api_error(msg.into(), &o);
+    }
+
+    match games_kv
+        .put(&gk, serde_json::to_string(&game).unwrap())?
+        .execute()
+        .await
+    {
+        Ok(_) => {
+            if team.next_game.as_deref() == Some(gk.as_str()) {
+                publish_game(&ctx, Some((gk.as_str(), &game))).await;
+            }
+            no_content_response(&o)
+        }
+        Err(_) => api_error(ApiError::Storage("failed to record vote"), &o),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/{teamkey}/{teamsecret}/attendance",
+    request_body = AttendanceRequest,
+    responses((status = 204)),
+    tag = "admin"
+)]
+async fn api_check_in(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+
+    let team = match get_team_authed(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+    let ng_key = match team.next_game.clone() {
+        Some(k) => k,
+        None => return api_error(ApiError::GameNotFound, &o),
+    };
+    let mut ng: Game = match get_game(&ctx, &team).await? {
+        Some(g) => g,
+        None => return api_error(ApiError::GameNotFound, &o),
+    };
+
+    let body: AttendanceRequest = read_body(&req).await?;
+    if let Err(msg) = service::check_in(&mut ng, &body.attendance) {
+        return api_error(msg.into(), &o);
+    }
+
+    match ctx
+        .kv("games")?
+        .put(&ng_key, serde_json::to_string(&ng).unwrap())?
+        .execute()
+        .await
+    {
+        Ok(_) => {
+            publish_game(&ctx, Some((ng_key.as_str(), &ng))).await;
+            no_content_response(&o)
+        }
+        Err(_) => api_error(ApiError::Storage("failed to check in"), &o),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/{teamkey}/{teamsecret}/no_shows",
+    responses((status = 200, body = Vec<NoShowEntry>)),
+    tag = "admin"
+)]
+async fn api_no_shows(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+
+    let team = match get_team_authed(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    let mut games = get_recent_games(&ctx, &team).await?;
+    if let Some(g) = get_game(&ctx, &team).await? {
+        games.push(g);
+    }
+    json_response(&service::no_show_rates(&team, &games), &o)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/teams/{teamkey}/leaderboard",
+    params(("season" = Option<i16>, Query, description = "Only count games from this year")),
+    responses((status = 200, body = LeaderboardResponse)),
+    tag = "results"
+)]
+async fn api_leaderboard(req: Request, ctx: RouteContext<Context>) -> Result<Response> {
+    let o = cors_origin(&req);
+
+    let team = match get_team(&ctx).await? {
+        Some(t) => t,
+        None => return api_error(ApiError::TeamNotFound, &o),
+    };
+
+    let season = match req.url()?.query_pairs().find(|(k, _)| k == "season") {
+        Some((_, v)) => match v.parse::<i16>() {
+            Ok(y) => Some(y),
+            Err(_) => return api_error(ApiError::InvalidSeason, &o),
+        },
+        None => None,
+    };
+
+    let mut games = get_recent_games(&ctx, &team).await?;
+    if let Some(g) = get_game(&ctx, &team).await? {
+        games.push(g);
     }
+    let entries = service::leaderboard(&team, &games, season);
+    json_response(&LeaderboardResponse { season, entries }, &o)
 }
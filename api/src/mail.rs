@@ -0,0 +1,298 @@
+use worker::{Fetch, Headers, Method, Request, RequestInit, wasm_bindgen::JsValue};
+
+use crate::types::{Game, RsvpStatus, Team};
+
+/// A rendered email, ready to hand to a [`Transport`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+}
+
+/// Something that can deliver an [`Email`].
+#[allow(async_fn_in_trait)]
+pub trait Transport {
+    async fn send(&self, email: &Email) -> Result<(), String>;
+}
+
+/// Send every email, returning the (recipient, error) pairs that failed.
+/// A failure doesn't stop the rest from being sent.
+pub async fn send_all<T: Transport>(transport: &T, emails: &[Email]) -> Vec<(String, String)> {
+    let mut failed = Vec::new();
+    for email in emails {
+        if let Err(e) = transport.send(email).await {
+            failed.push((email.to.clone(), e));
+        }
+    }
+    failed
+}
+
+// ============================================================
+// MailChannels
+// ============================================================
+
+const MAILCHANNELS_URL: &str = "https://api.mailchannels.net/tx/v1/send";
+
+/// Sends mail through the MailChannels HTTP API.
+pub struct MailChannels {
+    api_key: String,
+    from: String,
+}
+
+impl MailChannels {
+    /// Build from the `MAILCHANNELS_API_KEY` secret and `MAIL_FROM` var.
+    /// Returns None when either is missing, e.g. in local dev.
+    pub fn from_env(env: &worker::Env) -> Option<Self> {
+        let api_key = env.secret("MAILCHANNELS_API_KEY").ok()?.to_string();
+        let from = env.var("MAIL_FROM").ok()?.to_string();
+        if api_key.is_empty() || from.is_empty() {
+            return None;
+        }
+        Some(MailChannels { api_key, from })
+    }
+}
+
+impl Transport for MailChannels {
+    async fn send(&self, email: &Email) -> Result<(), String> {
+        let body = serde_json::json!({
+            "personalizations": [{ "to": [{ "email": email.to }] }],
+            "from": { "email": self.from, "name": "nextgame" },
+            "subject": email.subject,
+            "content": [
+                { "type": "text/plain", "value": email.text },
+                { "type": "text/html", "value": email.html },
+            ],
+        });
+
+        let headers = Headers::new();
+        let _ = headers.set("Content-Type", "application/json");
+        let _ = headers.set("X-Api-Key", &self.api_key);
+        let mut init = RequestInit::new();
+        init.with_method(Method::Post)
+            .with_headers(headers)
+            .with_body(Some(JsValue::from_str(&body.to_string())));
+
+        let req = Request::new_with_init(MAILCHANNELS_URL, &init).map_err(|e| e.to_string())?;
+        let resp = Fetch::Request(req).send().await.map_err(|e| e.to_string())?;
+        if (200..300).contains(&resp.status_code()) {
+            Ok(())
+        } else {
+            Err(format!("mailchannels returned {}", resp.status_code()))
+        }
+    }
+}
+
+// ============================================================
+// Templates
+// ============================================================
+
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// A short human description of when and where the game is.
+pub fn when(team: &Team, game: &Game) -> String {
+    let mut parts = Vec::new();
+    if let Some(d) = game.date {
+        parts.push(d.strftime("%A %-d %B").to_string());
+    }
+    if let Some(t) = &team.time {
+        parts.push(t.clone());
+    }
+    if let Some(l) = &team.location {
+        parts.push(format!("at {}", l));
+    }
+    if parts.is_empty() { "soon".to_string() } else { parts.join(", ") }
+}
+
+fn render(to: &str, subject: String, lines: &[String], link: &str) -> Email {
+    let text = format!("{}\n\n{}\n", lines.join("\n\n"), link);
+    let html = format!(
+        "<html><body>{}<p><a href=\"{}\">{}</a></p></body></html>",
+        lines
+            .iter()
+            .map(|l| format!("<p>{}</p>", escape_html(l)))
+            .collect::<String>(),
+        escape_html(link),
+        escape_html(link),
+    );
+    Email {
+        to: to.to_string(),
+        subject,
+        text,
+        html,
+    }
+}
+
+/// Reminder sent ahead of the game.
+pub fn reminder_email(to: &str, player: &str, team: &Team, game: &Game, link: &str) -> Email {
+    render(
+        to,
+        format!("{}: are you playing?", team.name),
+        &[
+            format!("Hi {},", player),
+            format!("{} plays {}.", team.name, when(team, game)),
+            "Let everyone know whether you're in:".to_string(),
+        ],
+        link,
+    )
+}
+
+/// Notice that the game has been called off.
+pub fn game_off_email(to: &str, player: &str, team: &Team, game: &Game, link: &str) -> Email {
+    render(
+        to,
+        format!("{}: game is off", team.name),
+        &[
+            format!("Hi {},", player),
+            format!("The {} game {} has been called off.", team.name, when(team, game)),
+        ],
+        link,
+    )
+}
+
+/// Confirmation that an organiser changed a player's RSVP.
+pub fn rsvp_changed_email(to: &str, player: &str, team: &Team, game: &Game, status: RsvpStatus, link: &str) -> Email {
+    let status = match status {
+        RsvpStatus::In => "playing",
+        RsvpStatus::Out => "not playing",
+        RsvpStatus::Maybe => "maybe playing",
+        RsvpStatus::Late => "arriving late",
+    };
+    render(
+        to,
+        format!("{}: your RSVP was updated", team.name),
+        &[
+            format!("Hi {},", player),
+            format!(
+                "An organiser marked you as {} for {} {}.",
+                status,
+                team.name,
+                when(team, game)
+            ),
+            "If that's not right, you can change it here:".to_string(),
+        ],
+        link,
+    )
+}
+
+#[cfg(test)]
+pub mod testing {
+    use std::cell::RefCell;
+
+    use super::{Email, Transport};
+
+    /// Transport that keeps every email instead of sending it.
+    #[derive(Default)]
+    pub struct RecordingTransport {
+        pub sent: RefCell<Vec<Email>>,
+        pub fail_for: Option<String>,
+    }
+
+    impl Transport for RecordingTransport {
+        async fn send(&self, email: &Email) -> Result<(), String> {
+            if self.fail_for.as_deref() == Some(email.to.as_str()) {
+                return Err("rejected".to_string());
+            }
+            self.sent.borrow_mut().push(email.clone());
+            Ok(())
+        }
+    }
+
+    /// Drive a future that never actually waits, which is all the fakes need.
+    pub fn block_on<F: std::future::Future>(fut: F) -> F::Output {
+        use std::task::{Context, Poll, Waker};
+        let mut fut = std::pin::pin!(fut);
+        let mut cx = Context::from_waker(Waker::noop());
+        match fut.as_mut().poll(&mut cx) {
+            Poll::Ready(v) => v,
+            Poll::Pending => panic!("future was not ready"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::testing::*;
+    use super::*;
+
+    fn make_team() -> Team {
+        serde_json::from_value(json!({
+            "name": "FC <Test>",
+            "secret": "s",
+            "players": {},
+            "location": "Park",
+            "time": "19:00",
+        }))
+        .unwrap()
+    }
+
+    fn make_game() -> Game {
+        serde_json::from_value(json!({"description": "", "players": {}, "guests": [], "date": "2026-03-11"})).unwrap()
+    }
+
+    #[test]
+    fn reminder_renders_text_and_html() {
+        let e = reminder_email("a@example.com", "Alice", &make_team(), &make_game(), "https://x/team/k");
+        assert_eq!(e.to, "a@example.com");
+        assert_eq!(e.subject, "FC <Test>: are you playing?");
+        assert!(e.text.contains("Wednesday 11 March, 19:00, at Park"));
+        assert!(e.text.ends_with("https://x/team/k\n"));
+        assert!(e.html.contains("FC &lt;Test&gt;"));
+        assert!(!e.html.contains("<Test>"));
+    }
+
+    #[test]
+    fn game_off_mentions_called_off() {
+        let e = game_off_email("a@example.com", "Alice", &make_team(), &make_game(), "l");
+        assert!(e.subject.ends_with("game is off"));
+        assert!(e.text.contains("called off"));
+    }
+
+    #[test]
+    fn rsvp_changed_says_status() {
+        let e = rsvp_changed_email("a@example.com", "Alice", &make_team(), &make_game(), RsvpStatus::Out, "l");
+        assert!(e.text.contains("marked you as not playing"));
+    }
+
+    #[test]
+    fn when_without_details() {
+        let mut team = make_team();
+        team.location = None;
+        team.time = None;
+        let mut game = make_game();
+        game.date = None;
+        assert_eq!(when(&team, &game), "soon");
+    }
+
+    #[test]
+    fn send_all_records_and_skips_failures() {
+        let transport = RecordingTransport {
+            fail_for: Some("bad@example.com".to_string()),
+            ..Default::default()
+        };
+        let emails = vec![
+            reminder_email("a@example.com", "A", &make_team(), &make_game(), "l"),
+            reminder_email("bad@example.com", "B", &make_team(), &make_game(), "l"),
+        ];
+        let failed = block_on(send_all(&transport, &emails));
+        assert_eq!(failed, vec![("bad@example.com".to_string(), "rejected".to_string())]);
+        assert_eq!(transport.sent.borrow().len(), 1);
+        assert_eq!(transport.sent.borrow()[0].to, "a@example.com");
+    }
+}
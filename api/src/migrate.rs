@@ -0,0 +1,347 @@
+//! Upgrading stored teams and games written by older versions.
+//!
+//! Every stored document carries a `schema_version`. Documents from before versioning have none and
+//! count as version 0. On read, the migrations from the document's version up to the current one
+//! run in order on the raw JSON, before it's deserialized. Fields that are only added, with a
+//! sensible default, don't need a migration; anything that changes shape does.
+
+use serde::de::Error as _;
+use serde_json::{Map, Value};
+
+use crate::types::{Game, Team, TeamArchive};
+
+/// A migration upgrades a document by exactly one version.
+type Migration = fn(&mut Map<String, Value>);
+
+/// The `i`th migration upgrades a team from version `i` to `i + 1`.
+const TEAM_MIGRATIONS: &[Migration] = &[team_v1_player_objects, team_v2_contacts_on_players];
+
+/// The `i`th migration upgrades a game from version `i` to `i + 1`.
+const GAME_MIGRATIONS: &[Migration] = &[game_v1_structured_entries];
+
+pub const TEAM_SCHEMA_VERSION: u32 = TEAM_MIGRATIONS.len() as u32;
+pub const GAME_SCHEMA_VERSION: u32 = GAME_MIGRATIONS.len() as u32;
+
+/// The parts of a stored team needed to check the secret and find its games, read on their own so
+/// they still work when the rest of the document doesn't.
+#[derive(serde::Deserialize, Debug, PartialEq)]
+pub struct TeamRefs {
+    pub secret: String,
+    #[serde(default)]
+    pub next_game: Option<String>,
+    #[serde(default)]
+    pub past_games: Vec<String>,
+}
+
+impl TeamRefs {
+    /// None if the document isn't JSON at all, or its secret or game keys are broken too.
+    pub fn from_str(text: &str) -> Option<TeamRefs> {
+        serde_json::from_str(text).ok()
+    }
+
+    /// Keys of every game the team refers to, oldest first.
+    pub fn game_keys(&self) -> impl Iterator<Item = &String> {
+        self.past_games.iter().chain(self.next_game.iter())
+    }
+}
+
+/// Why a stored document couldn't be read.
+#[derive(Debug)]
+pub enum LoadError {
+    /// Not JSON, or not shaped like the document it should be.
+    Malformed(serde_json::Error),
+    /// Written by a newer version of the server, which this one can't downgrade.
+    TooNew { version: u64, supported: u32 },
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Malformed(e) => write!(f, "{}", e),
+            LoadError::TooNew { version, supported } => {
+                write!(f, "schema version {} is newer than this server supports ({})", version, supported)
+            }
+        }
+    }
+}
+
+impl From<serde_json::Error> for LoadError {
+    fn from(e: serde_json::Error) -> Self {
+        LoadError::Malformed(e)
+    }
+}
+
+fn malformed(msg: &str) -> LoadError {
+    LoadError::Malformed(serde_json::Error::custom(msg))
+}
+
+/// A stored document, upgraded to the current schema.
+#[derive(Debug)]
+pub struct Loaded<T> {
+    pub doc: T,
+    /// Whether any migration ran, so the caller can write the upgraded document back.
+    pub migrated: bool,
+}
+
+pub fn team_from_str(text: &str) -> Result<Loaded<Team>, LoadError> {
+    load(serde_json::from_str(text)?, TEAM_MIGRATIONS)
+}
+
+pub fn game_from_str(text: &str) -> Result<Loaded<Game>, LoadError> {
+    load(serde_json::from_str(text)?, GAME_MIGRATIONS)
+}
+
+/// Like [`team_from_str`], for a team that's already been parsed, say as part of an archive.
+pub fn team_from_value(value: Value) -> Result<Loaded<Team>, LoadError> {
+    load(value, TEAM_MIGRATIONS)
+}
+
+/// Like [`game_from_str`], for a game that's already been parsed.
+pub fn game_from_value(value: Value) -> Result<Loaded<Game>, LoadError> {
+    load(value, GAME_MIGRATIONS)
+}
+
+/// An exported archive, with its team and games upgraded like stored ones.
+pub fn archive_from_value(mut value: Value) -> Result<TeamArchive, LoadError> {
+    let archive = value
+        .as_object_mut()
+        .ok_or_else(|| malformed("expected a JSON object"))?;
+    let team = team_from_value(archive.remove("team").unwrap_or_default())?.doc;
+    let games = match archive.remove("games") {
+        Some(Value::Object(games)) => games
+            .into_iter()
+            .map(|(key, game)| Ok((key, game_from_value(game)?.doc)))
+            .collect::<Result<_, LoadError>>()?,
+        _ => return Err(malformed("missing games")),
+    };
+    Ok(TeamArchive {
+        version: serde_json::from_value(archive.remove("version").unwrap_or_default())?,
+        exported_at: serde_json::from_value(archive.remove("exported_at").unwrap_or_default())?,
+        team,
+        games,
+    })
+}
+
+fn load<T: serde::de::DeserializeOwned>(mut value: Value, migrations: &[Migration]) -> Result<Loaded<T>, LoadError> {
+    let doc = value
+        .as_object_mut()
+        .ok_or_else(|| malformed("expected a JSON object"))?;
+    let version = match doc.get("schema_version") {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .ok_or_else(|| malformed("schema_version must be a number"))? as usize,
+    };
+    if version > migrations.len() {
+        return Err(LoadError::TooNew {
+            version: version as u64,
+            supported: migrations.len() as u32,
+        });
+    }
+    for migrate in &migrations[version..] {
+        migrate(doc);
+    }
+    doc.insert("schema_version".to_string(), migrations.len().into());
+    Ok(Loaded {
+        doc: serde_json::from_value(value)?,
+        migrated: version < migrations.len(),
+    })
+}
+
+/// v1: roster players went from a bare name to an object, so they could be deactivated.
+fn team_v1_player_objects(team: &mut Map<String, Value>) {
+    if let Some(Value::Object(players)) = team.get_mut("players") {
+        for player in players.values_mut() {
+            if let Value::String(name) = player {
+                *player = serde_json::json!({"name": name, "active": true});
+            }
+        }
+    }
+}
+
+/// v2: notification settings moved from `contacts` onto the players, so a player has one email
+/// address. The one they opted in with wins.
+fn team_v2_contacts_on_players(team: &mut Map<String, Value>) {
+    let Some(Value::Object(contacts)) = team.remove("contacts") else { return };
+    let Some(Value::Object(players)) = team.get_mut("players") else { return };
+    for (pid, mut contact) in contacts {
+        let (Some(Value::Object(player)), Some(contact)) = (players.get_mut(&pid), contact.as_object_mut()) else {
+            continue;
+        };
+        if let Some(email) = contact.remove("email") {
+            player.insert("email".to_string(), email);
+        }
+        player.insert("notifications".to_string(), Value::Object(std::mem::take(contact)));
+    }
+}
+
+/// v1: RSVPs went from a bool to a status, guests from a bare name to an object with an id, and
+/// comments from a bare string to an object with an optional author, marked as legacy.
+fn game_v1_structured_entries(game: &mut Map<String, Value>) {
+    if let Some(Value::Object(players)) = game.get_mut("players") {
+        for rsvp in players.values_mut() {
+            if let Value::Bool(playing) = rsvp {
+                *rsvp = serde_json::json!({"status": if *playing { "in" } else { "out" }});
+            }
+        }
+    }
+    // Guests were referred to by position, as `guest:<idx>`, so the position becomes their id and
+    // existing squad assignments, stats and votes still match.
+    if let Some(Value::Array(guests)) = game.get_mut("guests") {
+        for (idx, guest) in guests.iter_mut().enumerate() {
+            if let Value::String(name) = guest {
+                *guest = serde_json::json!({"id": idx.to_string(), "name": name});
+            }
+        }
+    }
+    if let Some(Value::Array(comments)) = game.get_mut("comments") {
+        for comment in comments.iter_mut() {
+            if let Value::String(text) = comment {
+                *comment = serde_json::json!({"text": text, "legacy": true});
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Notifications, Player, Rsvp, RsvpStatus};
+
+    // Fixtures are documents as older versions stored them.
+
+    const TEAM_V0: &str = r#"{
+        "name": "Sunday Football",
+        "secret": "s",
+        "next_game": "g1",
+        "players": {"a": "Alice", "b": "Bob"}
+    }"#;
+
+    const GAME_V0: &str = r#"{
+        "description": "",
+        "players": {"a": true, "b": false, "c": null},
+        "guests": ["Gus", "Gina"],
+        "comments": ["see you there", {"text": "bring bibs", "author": "Alice"}],
+        "squad_assignments": {"guest:1": "blue"}
+    }"#;
+
+    #[test]
+    fn team_v0_players_become_objects() {
+        let loaded = team_from_str(TEAM_V0).unwrap();
+        assert!(loaded.migrated);
+        let team = loaded.doc;
+        assert_eq!(team.schema_version, TEAM_SCHEMA_VERSION);
+        assert_eq!(team.players["a"], Player::from("Alice"));
+        assert!(team.players["b"].active);
+    }
+
+    #[test]
+    fn team_v1_contacts_move_onto_players() {
+        let text = r#"{
+            "schema_version": 1,
+            "name": "T",
+            "secret": "s",
+            "next_game": null,
+            "players": {
+                "a": {"name": "Alice", "email": "old@example.com"},
+                "b": {"name": "Bob"}
+            },
+            "contacts": {
+                "a": {"email": "alice@example.com", "reminders": true},
+                "gone": {"email": "gone@example.com", "game_off": true}
+            }
+        }"#;
+        let team = team_from_str(text).unwrap().doc;
+        let alice = &team.players["a"];
+        assert_eq!(alice.email.as_deref(), Some("alice@example.com"));
+        assert_eq!(alice.notifications, Notifications { reminders: true, ..Notifications::default() });
+        assert_eq!(team.players["b"], Player::from("Bob"));
+        assert!(!serde_json::to_string(&team).unwrap().contains("gone@example.com"));
+    }
+
+    #[test]
+    fn game_v0_entries_become_objects() {
+        let loaded = game_from_str(GAME_V0).unwrap();
+        assert!(loaded.migrated);
+        let game = loaded.doc;
+        assert_eq!(game.schema_version, GAME_SCHEMA_VERSION);
+        assert_eq!(game.players["a"], Some(Rsvp { status: RsvpStatus::In, note: None }));
+        assert_eq!(game.players["b"].as_ref().map(|r| r.status), Some(RsvpStatus::Out));
+        assert_eq!(game.players["c"], None);
+        assert_eq!(game.guests[1].id, "1");
+        assert_eq!(game.guests[1].name, "Gina");
+        assert_eq!(game.squad_assignments[&game.guests[1].board_id()], "blue");
+        assert_eq!(game.comments[0].text, "see you there");
+        assert_eq!(game.comments[0].author, None);
+        assert!(game.comments[0].legacy);
+        assert_eq!(game.comments[1].author.as_deref(), Some("Alice"));
+        assert!(!game.comments[1].legacy);
+    }
+
+    #[test]
+    fn entries_already_migrated_are_kept() {
+        let game = game_from_value(serde_json::json!({
+            "description": "",
+            "players": {"a": true, "d": {"status": "late", "note": "traffic"}},
+            "guests": ["Ann", {"id": "x1", "name": "Bo", "invited_by": "p1"}, "Cy"]
+        }))
+        .unwrap()
+        .doc;
+        assert_eq!(game.players["a"], Some(Rsvp { status: RsvpStatus::In, note: None }));
+        assert_eq!(game.players["d"].as_ref().unwrap().note.as_deref(), Some("traffic"));
+        assert_eq!(game.guests[0].id, "0");
+        assert_eq!(game.guests[1].board_id(), "guest:x1");
+        assert_eq!(game.guests[1].invited_by.as_deref(), Some("p1"));
+        assert_eq!(game.guests[2].board_id(), "guest:2");
+
+        let team = team_from_value(serde_json::json!({
+            "name": "T",
+            "secret": "s",
+            "next_game": null,
+            "players": {"a": "Alice", "b": {"name": "Bob", "active": false}}
+        }))
+        .unwrap()
+        .doc;
+        assert_eq!(team.players["a"], Player::from("Alice"));
+        assert!(!team.players["b"].active);
+    }
+
+    #[test]
+    fn current_documents_are_left_alone() {
+        let team = team_from_str(TEAM_V0).unwrap().doc;
+        let loaded = team_from_str(&serde_json::to_string(&team).unwrap()).unwrap();
+        assert!(!loaded.migrated);
+        assert_eq!(loaded.doc.players, team.players);
+
+        let game = game_from_str(GAME_V0).unwrap().doc;
+        let loaded = game_from_str(&serde_json::to_string(&game).unwrap()).unwrap();
+        assert!(!loaded.migrated);
+        assert_eq!(loaded.doc.guests, game.guests);
+    }
+
+    #[test]
+    fn newer_documents_are_refused() {
+        let text = format!(r#"{{"name": "T", "secret": "s", "next_game": null, "players": {{}}, "schema_version": {}}}"#, TEAM_SCHEMA_VERSION + 1);
+        assert!(matches!(
+            team_from_str(&text),
+            Err(LoadError::TooNew { supported: TEAM_SCHEMA_VERSION, .. })
+        ));
+    }
+
+    #[test]
+    fn team_refs_survive_a_broken_team() {
+        let text = r#"{"name": 3, "secret": "s", "next_game": "g2", "past_games": ["g1"], "players": []}"#;
+        assert!(team_from_str(text).is_err());
+        let refs = TeamRefs::from_str(text).unwrap();
+        assert_eq!(refs.secret, "s");
+        assert_eq!(refs.game_keys().collect::<Vec<_>>(), vec!["g1", "g2"]);
+        assert_eq!(TeamRefs::from_str("{\"secret\": "), None);
+    }
+
+    #[test]
+    fn malformed_documents_are_errors() {
+        assert!(matches!(game_from_str("[]"), Err(LoadError::Malformed(_))));
+        assert!(matches!(game_from_str("{\"description\": "), Err(LoadError::Malformed(_))));
+        assert!(matches!(team_from_str(r#"{"name": "T", "players": {}}"#), Err(LoadError::Malformed(_))));
+    }
+}
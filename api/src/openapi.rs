@@ -0,0 +1,336 @@
+//! The OpenAPI description of the HTTP API, generated from the handlers' `#[utoipa::path]`
+//! attributes and the types in [`crate::types`]. Served at `/api/openapi.json`.
+//!
+//! A copy is checked in as `api/openapi.json` for people and tools that want it without running
+//! the worker. The tests fail when that copy is out of date; regenerate it with
+//! `UPDATE_OPENAPI=1 cargo test`.
+//!
+//! The handlers are annotated with their v1 paths, under `/api`. [`Versions`] copies each one
+//! that v2 shares to `/api/v2` and marks the v1 originals deprecated; handlers that are only in v2
+//! are annotated with their `/api/v2` path directly. The schemas are v2's; v1 operations say how
+//! v1 differs (see `v1.rs`).
+
+use utoipa::openapi::path::{Parameter, ParameterBuilder, ParameterIn};
+use utoipa::openapi::schema::{ObjectBuilder, Type};
+use utoipa::openapi::{ContentBuilder, Deprecated, OpenApi as Spec, Ref, Required, ResponseBuilder};
+use utoipa::{Modify, OpenApi};
+
+use crate::error::ErrorBody;
+use crate::types::{FieldError, SquadBoardEvent, SquadOp};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "nextgame", description = "Who's playing?!"),
+    paths(
+        crate::api_new_team,
+        crate::api_import_team,
+        crate::api_team,
+        crate::api_game_keys,
+        crate::api_game,
+        crate::api_play,
+        crate::api_not_play,
+        crate::api_rsvp,
+        crate::api_add_comment,
+        crate::api_add_guest,
+        crate::api_add_player_guest,
+        crate::api_delete_guest,
+        crate::api_new_game,
+        crate::api_save_squads,
+        crate::api_auto_squads,
+        crate::api_set_contact,
+        crate::api_delete_contact,
+        crate::api_add_push_subscription,
+        crate::api_delete_push_subscription,
+        crate::api_openapi,
+        crate::api_push_public_key,
+        crate::api_reminder_ics,
+        crate::api_events,
+        crate::api_squads_ws,
+        crate::api_record_results,
+        crate::api_mvp_vote,
+        crate::api_leaderboard,
+        crate::api_request_to_join,
+        crate::api_admin,
+        crate::api_export_team,
+        crate::api_raw_documents,
+        crate::api_repair_document,
+        crate::api_update_settings,
+        crate::api_add_players,
+        crate::api_export_roster,
+        crate::api_import_roster,
+        crate::api_create_player,
+        crate::api_update_player,
+        crate::api_delete_player,
+        crate::api_merge_player,
+        crate::api_admin_play,
+        crate::api_admin_not_play,
+        crate::api_admin_rsvp,
+        crate::api_admin_add_guest,
+        crate::api_admin_add_player_guest,
+        crate::api_admin_delete_guest,
+        crate::api_promote_guest,
+        crate::api_approve_join_request,
+        crate::api_reject_join_request,
+        crate::api_reset_game,
+        crate::api_game_off,
+        crate::api_default_squads,
+        crate::api_set_ratings,
+        crate::api_admin_record_results,
+        crate::api_record_scores,
+        crate::api_check_in,
+        crate::api_no_shows,
+    ),
+    components(schemas(ErrorBody, FieldError, SquadOp, SquadBoardEvent)),
+    modifiers(&Versions, &PathParams, &ErrorResponses),
+)]
+struct ApiDoc;
+
+pub fn spec() -> Spec {
+    ApiDoc::openapi()
+}
+
+/// v1 paths that v2 dropped, in favour of `PUT .../rsvp`.
+const V1_ONLY: &[&str] = &[
+    "/api/teams/{teamkey}/players/{playerid}/play",
+    "/api/teams/{teamkey}/players/{playerid}/not_play",
+    "/api/admin/{teamkey}/{teamsecret}/players/{playerid}/play",
+    "/api/admin/{teamkey}/{teamsecret}/players/{playerid}/not_play",
+];
+
+/// How v1 responses differ from the v2 schemas, added to every v1 operation.
+const V1_NOTE: &str = "v1: in games, RSVPs are true, false or null and guests are names; comments \
+    written before authors are strings. A wrong admin secret is a 404. Bodies are read loosely: \
+    a value that can't be used clears the setting it was for, or is ignored.";
+
+/// Describes every shared v1 path again under `/api/v2`, and deprecates v1. The v2 operations
+/// keep the handlers' operation ids; the v1 ones get a `v1_` prefix.
+struct Versions;
+
+impl Modify for Versions {
+    fn modify(&self, openapi: &mut Spec) {
+        let v1: Vec<String> = openapi
+            .paths
+            .paths
+            .keys()
+            .filter(|path| !path.starts_with("/api/v2/") && *path != "/api/openapi.json")
+            .cloned()
+            .collect();
+        for path in v1 {
+            let item = openapi.paths.paths.get_mut(&path).unwrap();
+            if !V1_ONLY.contains(&path.as_str()) {
+                let v2 = item.clone();
+                openapi.paths.paths.insert(path.replacen("/api", "/api/v2", 1), v2);
+            }
+            let item = openapi.paths.paths.get_mut(&path).unwrap();
+            let ops = [&mut item.get, &mut item.put, &mut item.post, &mut item.delete];
+            for op in ops.into_iter().flatten() {
+                op.deprecated = Some(Deprecated::True);
+                op.description = Some(match op.description.take() {
+                    Some(d) => format!("{}\n\n{}", d, V1_NOTE),
+                    None => V1_NOTE.to_string(),
+                });
+                op.operation_id = op.operation_id.take().map(|id| format!("v1_{}", id));
+            }
+        }
+    }
+}
+
+/// Declares the `{teamkey}`-style parameters in every path, so the attributes don't have to.
+struct PathParams;
+
+impl Modify for PathParams {
+    fn modify(&self, openapi: &mut Spec) {
+        for (path, item) in openapi.paths.paths.iter_mut() {
+            let names: Vec<&str> =
+                path.split('/').filter_map(|s| s.strip_prefix('{')?.strip_suffix('}')).collect();
+            let ops = [&mut item.get, &mut item.put, &mut item.post, &mut item.delete];
+            for op in ops.into_iter().flatten() {
+                let params = op.parameters.get_or_insert_with(Vec::new);
+                for (i, name) in names.iter().enumerate() {
+                    params.insert(i, path_param(name));
+                }
+            }
+        }
+    }
+}
+
+fn path_param(name: &str) -> Parameter {
+    let description = match name {
+        "teamkey" => "The team's key, from its link",
+        "teamsecret" => "The team's admin secret",
+        "playerid" => "A roster player's id",
+        "guestid" => "A guest's id in the current game",
+        "requestid" => "A join request's id",
+        "kind" => "\"team\" or \"game\"",
+        "key" => "The document's key",
+        "gamekey" => "A game's key, current or past",
+        _ => "",
+    };
+    ParameterBuilder::new()
+        .name(name)
+        .parameter_in(ParameterIn::Path)
+        .required(Required::True)
+        .description(Some(description))
+        .schema(Some(ObjectBuilder::new().schema_type(Type::String)))
+        .build()
+}
+
+/// Every endpoint can fail with an [`ErrorBody`]; see `error.rs` for the codes.
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut Spec) {
+        let response = ResponseBuilder::new()
+            .description("An error, with a stable `code`")
+            .content("application/json", ContentBuilder::new().schema(Some(Ref::from_schema_name("ErrorBody"))).build())
+            .build();
+        for item in openapi.paths.paths.values_mut() {
+            let ops = [&mut item.get, &mut item.put, &mut item.post, &mut item.delete];
+            for op in ops.into_iter().flatten() {
+                op.responses.responses.insert("default".to_string(), response.clone().into());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const CHECKED_IN: &str = include_str!("../openapi.json");
+
+    #[test]
+    fn checked_in_spec_is_current() {
+        let current = spec().to_pretty_json().unwrap() + "\n";
+        if std::env::var_os("UPDATE_OPENAPI").is_some() {
+            std::fs::write(concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json"), &current).unwrap();
+            return;
+        }
+        assert!(
+            current == CHECKED_IN,
+            "api/openapi.json is out of date, regenerate it with `UPDATE_OPENAPI=1 cargo test`"
+        );
+    }
+
+    /// Routes in the router, as (method, spec path). Shared routes are registered once per
+    /// version base, so those lines are expanded for both.
+    fn routes() -> Vec<(String, String)> {
+        let mut routes = Vec::new();
+        for line in include_str!("lib.rs").lines() {
+            let Some(line) = line.trim().strip_prefix('.') else { continue };
+            let Some((method, rest)) = line.split_once("_async(") else { continue };
+            let (bases, rest) = match rest.strip_prefix("concat!($base, ") {
+                Some(rest) => (vec!["/api", "/api/v2"], rest),
+                None => (vec![""], rest),
+            };
+            let Some((path, _)) = rest.strip_prefix('"').and_then(|r| r.split_once('"')) else { continue };
+            let path: Vec<String> = path
+                .split('/')
+                .map(|s| s.strip_prefix(':').map_or(s.to_string(), |p| format!("{{{}}}", p)))
+                .collect();
+            for base in bases {
+                routes.push((method.to_string(), format!("{}{}", base, path.join("/"))));
+            }
+        }
+        routes.retain(|(method, path)| path.starts_with("/api/") && method != "options");
+        routes
+    }
+
+    /// Every `/api` route in the router has to be described, with the same method.
+    #[test]
+    fn every_route_is_described() {
+        let spec = spec();
+        let routes = routes();
+        assert!(routes.len() > 80);
+        for (method, path) in routes {
+            let item = spec.paths.paths.get(&path).unwrap_or_else(|| panic!("{} isn't in the spec", path));
+            let op = match method.as_str() {
+                "get" => &item.get,
+                "put" => &item.put,
+                "post" => &item.post,
+                "delete" => &item.delete,
+                m => panic!("unexpected method {}", m),
+            };
+            assert!(op.is_some(), "{} {} isn't in the spec", method, path);
+        }
+    }
+
+    /// And the other way round: nothing in the spec that the router doesn't have.
+    #[test]
+    fn every_described_path_is_routed() {
+        let routes = routes();
+        for path in spec().paths.paths.keys() {
+            assert!(routes.iter().any(|(_, p)| p == path), "{} isn't routed", path);
+        }
+    }
+
+    /// Functions in lib.rs, by name, with their source from the signature on.
+    fn functions(source: &str) -> HashMap<&str, &str> {
+        let mut fns = HashMap::new();
+        for (start, _) in source.match_indices("\nfn ").chain(source.match_indices("\nasync fn ")) {
+            let rest = &source[start + 1..];
+            let rest = rest.strip_prefix("async ").unwrap_or(rest);
+            let name_end = rest[3..].find(['(', '<']).unwrap() + 3;
+            let end = rest.find("\n}\n").map_or(rest.len(), |e| e + 2);
+            fns.insert(&rest[3..name_end], &rest[..end]);
+        }
+        fns
+    }
+
+    /// Whether `name`, or a function in lib.rs it calls, calls `target`.
+    fn reaches(fns: &HashMap<&str, &str>, name: &str, target: &str, seen: &mut Vec<String>) -> bool {
+        if seen.iter().any(|s| s == name) {
+            return false;
+        }
+        seen.push(name.to_string());
+        let body = fns[name];
+        let body = &body[body.find('{').unwrap_or(0)..];
+        if body.contains(&format!("{}(", target)) {
+            return true;
+        }
+        fns.keys()
+            .filter(|f| **f != name && body.contains(&format!("{}(", f)))
+            .any(|f| reaches(fns, f, target, seen))
+    }
+
+    /// A handler documented as 204 never sends a JSON body, and one documented with a JSON body
+    /// sends one.
+    #[test]
+    fn declared_responses_match_the_handlers() {
+        let source = include_str!("lib.rs");
+        let fns = functions(source);
+        let mut checked = 0;
+        for (start, _) in source.match_indices("#[utoipa::path(") {
+            let attr = &source[start..];
+            let attr = &attr[..attr.find(")]\n").unwrap()];
+            let handler = source[start + attr.len()..].split_once("fn ").unwrap().1;
+            let handler = &handler[..handler.find('(').unwrap()];
+            let Some(responses) = attr.split_once("responses((").map(|(_, r)| r) else { continue };
+            let json = reaches(&fns, handler, "json_response", &mut Vec::new());
+            let empty = reaches(&fns, handler, "no_content_response", &mut Vec::new());
+            if responses.starts_with("status = 204") {
+                assert!(!json, "{} is documented as 204 but sends JSON", handler);
+                assert!(empty, "{} is documented as 204 but never sends it", handler);
+                checked += 1;
+            } else if responses.starts_with("status = 200, body =") {
+                assert!(json, "{} is documented with a JSON body but never sends one", handler);
+                assert!(!empty, "{} is documented with a JSON body but can send 204", handler);
+                checked += 1;
+            }
+        }
+        assert!(checked > 50);
+    }
+
+    #[test]
+    fn v1_is_deprecated_and_v2_is_not() {
+        let spec = spec();
+        let op = |path: &str| spec.paths.paths[path].get.clone().unwrap();
+        assert!(matches!(op("/api/teams/{teamkey}").deprecated, Some(Deprecated::True)));
+        assert_eq!(op("/api/teams/{teamkey}").operation_id.as_deref(), Some("v1_api_team"));
+        assert!(op("/api/v2/teams/{teamkey}").deprecated.is_none());
+        assert_eq!(op("/api/v2/teams/{teamkey}").operation_id.as_deref(), Some("api_team"));
+        assert!(op("/api/openapi.json").deprecated.is_none());
+        assert!(!spec.paths.paths.contains_key("/api/v2/teams/{teamkey}/players/{playerid}/play"));
+    }
+}
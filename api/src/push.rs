@@ -0,0 +1,303 @@
+use aes_gcm::{Aes128Gcm, KeyInit, aead::Aead};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as B64;
+use hkdf::Hkdf;
+use p256::ecdsa::{Signature, SigningKey, signature::Signer};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::{PublicKey, SecretKey};
+use serde::Serialize;
+use sha2::Sha256;
+use worker::{Fetch, Headers, Method, Request, RequestInit, js_sys::Uint8Array};
+
+use crate::types::PushSubscription;
+
+/// Record size advertised in the aes128gcm header. Payloads are always a single record.
+const RECORD_SIZE: u32 = 4096;
+
+/// How long push services should hold on to an undelivered message, in seconds.
+const TTL: &str = "86400";
+
+/// What the service worker on the client shows.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PushMessage {
+    pub title: String,
+    pub body: String,
+    pub url: String,
+}
+
+fn hkdf(salt: &[u8], ikm: &[u8], info: &[u8], out: &mut [u8]) {
+    Hkdf::<Sha256>::new(Some(salt), ikm).expand(info, out).unwrap();
+}
+
+/// Encrypt a payload for one subscriber as described in RFC 8291, using the given
+/// ephemeral application server key and salt.
+/// Returns the full request body: the aes128gcm header followed by a single record.
+pub fn encrypt(
+    plaintext: &[u8],
+    ua_public: &[u8],
+    auth_secret: &[u8],
+    as_secret: &SecretKey,
+    salt: &[u8; 16],
+) -> Result<Vec<u8>, &'static str> {
+    if plaintext.len() + 1 + 16 > RECORD_SIZE as usize {
+        return Err("payload too large");
+    }
+    let ua_key = PublicKey::from_sec1_bytes(ua_public).map_err(|_| "invalid p256dh key")?;
+    let as_public = as_secret.public_key().to_encoded_point(false);
+    let shared = p256::ecdh::diffie_hellman(as_secret.to_nonzero_scalar(), ua_key.as_affine());
+
+    let mut key_info = b"WebPush: info\0".to_vec();
+    key_info.extend_from_slice(ua_public);
+    key_info.extend_from_slice(as_public.as_bytes());
+    let mut ikm = [0u8; 32];
+    hkdf(auth_secret, shared.raw_secret_bytes(), &key_info, &mut ikm);
+
+    let mut cek = [0u8; 16];
+    hkdf(salt, &ikm, b"Content-Encoding: aes128gcm\0", &mut cek);
+    let mut nonce = [0u8; 12];
+    hkdf(salt, &ikm, b"Content-Encoding: nonce\0", &mut nonce);
+
+    // A single, final record: the payload followed by the 0x02 delimiter.
+    let mut record = plaintext.to_vec();
+    record.push(2);
+    let ciphertext = Aes128Gcm::new(&cek.into())
+        .encrypt(&nonce.into(), record.as_slice())
+        .map_err(|_| "encryption failed")?;
+
+    let mut body = salt.to_vec();
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(as_public.len() as u8);
+    body.extend_from_slice(as_public.as_bytes());
+    body.extend_from_slice(&ciphertext);
+    Ok(body)
+}
+
+/// The application server's VAPID identity (RFC 8292).
+pub struct Vapid {
+    key: SigningKey,
+    subject: String,
+}
+
+impl Vapid {
+    /// Build from a base64url-encoded P-256 private key and a contact URI.
+    pub fn new(private_key: &str, subject: &str) -> Option<Self> {
+        let bytes = B64.decode(private_key.trim()).ok()?;
+        let key = SigningKey::from_slice(&bytes).ok()?;
+        Some(Vapid {
+            key,
+            subject: subject.to_string(),
+        })
+    }
+
+    /// Build from the `VAPID_PRIVATE_KEY` secret and `VAPID_SUBJECT` var.
+    /// Returns None when either is missing, e.g. in local dev.
+    pub fn from_env(env: &worker::Env) -> Option<Self> {
+        let private_key = env.secret("VAPID_PRIVATE_KEY").ok()?.to_string();
+        let subject = env.var("VAPID_SUBJECT").ok()?.to_string();
+        Self::new(&private_key, &subject)
+    }
+
+    /// The uncompressed public key, base64url-encoded, as browsers expect for `applicationServerKey`.
+    pub fn public_key(&self) -> String {
+        B64.encode(self.key.verifying_key().to_encoded_point(false).as_bytes())
+    }
+
+    /// A signed ES256 JWT for the push service that owns `endpoint`, valid for 12 hours from `now`.
+    pub fn token(&self, endpoint: &str, now: i64) -> Option<String> {
+        let url = worker::Url::parse(endpoint).ok()?;
+        let aud = url.origin().ascii_serialization();
+        let header = B64.encode(r#"{"typ":"JWT","alg":"ES256"}"#);
+        let claims = B64.encode(
+            serde_json::json!({
+                "aud": aud,
+                "exp": now + 12 * 60 * 60,
+                "sub": self.subject,
+            })
+            .to_string(),
+        );
+        let signing_input = format!("{}.{}", header, claims);
+        let sig: Signature = self.key.sign(signing_input.as_bytes());
+        Some(format!("{}.{}", signing_input, B64.encode(sig.to_bytes())))
+    }
+
+    /// The `Authorization` header value for a push request to `endpoint`.
+    pub fn authorization(&self, endpoint: &str, now: i64) -> Option<String> {
+        Some(format!("vapid t={}, k={}", self.token(endpoint, now)?, self.public_key()))
+    }
+}
+
+/// How a delivery attempt went.
+#[derive(Debug, PartialEq)]
+pub enum Delivery {
+    Sent,
+    /// The push service says the subscription no longer exists; it should be dropped.
+    Gone,
+    Failed(String),
+}
+
+/// Encrypt and deliver a message to one subscription.
+pub async fn send(vapid: &Vapid, sub: &PushSubscription, message: &PushMessage) -> Delivery {
+    let ua_public = match B64.decode(sub.keys.p256dh.trim_end_matches('=')) {
+        Ok(k) => k,
+        Err(_) => return Delivery::Gone,
+    };
+    let auth = match B64.decode(sub.keys.auth.trim_end_matches('=')) {
+        Ok(a) => a,
+        Err(_) => return Delivery::Gone,
+    };
+
+    let mut secret = [0u8; 32];
+    let mut salt = [0u8; 16];
+    if getrandom::fill(&mut secret).is_err() || getrandom::fill(&mut salt).is_err() {
+        return Delivery::Failed("no randomness".to_string());
+    }
+    let as_secret = match SecretKey::from_slice(&secret) {
+        Ok(s) => s,
+        Err(_) => return Delivery::Failed("bad ephemeral key".to_string()),
+    };
+
+    let payload = serde_json::to_vec(message).unwrap();
+    let body = match encrypt(&payload, &ua_public, &auth, &as_secret, &salt) {
+        Ok(b) => b,
+        Err(e) => return Delivery::Failed(e.to_string()),
+    };
+    let now = jiff::Timestamp::now().as_second();
+    let authorization = match vapid.authorization(&sub.endpoint, now) {
+        Some(a) => a,
+        None => return Delivery::Gone,
+    };
+
+    let headers = Headers::new();
+    let _ = headers.set("Content-Type", "application/octet-stream");
+    let _ = headers.set("Content-Encoding", "aes128gcm");
+    let _ = headers.set("TTL", TTL);
+    let _ = headers.set("Authorization", &authorization);
+    let mut init = RequestInit::new();
+    init.with_method(Method::Post)
+        .with_headers(headers)
+        .with_body(Some(Uint8Array::from(body.as_slice()).into()));
+
+    let req = match Request::new_with_init(&sub.endpoint, &init) {
+        Ok(r) => r,
+        Err(e) => return Delivery::Failed(e.to_string()),
+    };
+    match Fetch::Request(req).send().await {
+        Ok(resp) if (200..300).contains(&resp.status_code()) => Delivery::Sent,
+        Ok(resp) if resp.status_code() == 404 || resp.status_code() == 410 => Delivery::Gone,
+        Ok(resp) => Delivery::Failed(format!("push service returned {}", resp.status_code())),
+        Err(e) => Delivery::Failed(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aes_gcm::aead::Aead;
+    use p256::ecdsa::{VerifyingKey, signature::Verifier};
+
+    use super::*;
+
+    fn b64(s: &str) -> Vec<u8> {
+        B64.decode(s).unwrap()
+    }
+
+    // Test vector from RFC 8291, Appendix A.
+    const PLAINTEXT: &str = "V2hlbiBJIGdyb3cgdXAsIEkgd2FudCB0byBiZSBhIHdhdGVybWVsb24";
+    const AS_PUBLIC: &str =
+        "BP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A8";
+    const AS_PRIVATE: &str = "yfWPiYE-n46HLnH0KqZOF1fJJU3MYrct3AELtAQ-oRw";
+    const UA_PUBLIC: &str =
+        "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4";
+    const UA_PRIVATE: &str = "q1dXpw3UpT5VOmu_cf_v6ih07Aems3njxI-JWgLcM94";
+    const SALT: &str = "DGv6ra1nlYgDCS1FRnbzlw";
+    const AUTH_SECRET: &str = "BTBZMqHH6r4Tts7J_aSIgg";
+    const BODY: &str = "DGv6ra1nlYgDCS1FRnbzlwAAEABBBP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A_yl95bQpu6cVPTpK4Mqgkf1CXztLVBSt2Ks3oZwbuwXPXLWyouBWLVWGNWQexSgSxsj_Qulcy4a-fN";
+
+    fn rfc_encrypt() -> Vec<u8> {
+        let as_secret = SecretKey::from_slice(&b64(AS_PRIVATE)).unwrap();
+        let salt: [u8; 16] = b64(SALT).try_into().unwrap();
+        encrypt(&b64(PLAINTEXT), &b64(UA_PUBLIC), &b64(AUTH_SECRET), &as_secret, &salt).unwrap()
+    }
+
+    #[test]
+    fn rfc8291_public_key_matches_private() {
+        let as_secret = SecretKey::from_slice(&b64(AS_PRIVATE)).unwrap();
+        assert_eq!(as_secret.public_key().to_encoded_point(false).as_bytes(), b64(AS_PUBLIC).as_slice());
+    }
+
+    #[test]
+    fn rfc8291_known_vector() {
+        assert_eq!(B64.encode(rfc_encrypt()), BODY);
+    }
+
+    #[test]
+    fn rfc8291_receiver_can_decrypt() {
+        // Derive the keys again from the user agent's side and open the record.
+        let body = rfc_encrypt();
+        let salt = &body[..16];
+        assert_eq!(u32::from_be_bytes(body[16..20].try_into().unwrap()), RECORD_SIZE);
+        let id_len = body[20] as usize;
+        let as_public = PublicKey::from_sec1_bytes(&body[21..21 + id_len]).unwrap();
+        let ciphertext = &body[21 + id_len..];
+
+        let ua_secret = SecretKey::from_slice(&b64(UA_PRIVATE)).unwrap();
+        let shared = p256::ecdh::diffie_hellman(ua_secret.to_nonzero_scalar(), as_public.as_affine());
+        let mut key_info = b"WebPush: info\0".to_vec();
+        key_info.extend_from_slice(&b64(UA_PUBLIC));
+        key_info.extend_from_slice(&body[21..21 + id_len]);
+        let mut ikm = [0u8; 32];
+        hkdf(&b64(AUTH_SECRET), shared.raw_secret_bytes(), &key_info, &mut ikm);
+        let mut cek = [0u8; 16];
+        hkdf(salt, &ikm, b"Content-Encoding: aes128gcm\0", &mut cek);
+        let mut nonce = [0u8; 12];
+        hkdf(salt, &ikm, b"Content-Encoding: nonce\0", &mut nonce);
+
+        let record = Aes128Gcm::new(&cek.into()).decrypt(&nonce.into(), ciphertext).unwrap();
+        assert_eq!(record.last(), Some(&2));
+        assert_eq!(&record[..record.len() - 1], b"When I grow up, I want to be a watermelon");
+    }
+
+    #[test]
+    fn encrypt_rejects_bad_key_and_oversized_payload() {
+        let as_secret = SecretKey::from_slice(&b64(AS_PRIVATE)).unwrap();
+        let salt = [0u8; 16];
+        assert!(encrypt(b"hi", &[4u8; 65], &b64(AUTH_SECRET), &as_secret, &salt).is_err());
+        let big = vec![0u8; RECORD_SIZE as usize];
+        assert!(encrypt(&big, &b64(UA_PUBLIC), &b64(AUTH_SECRET), &as_secret, &salt).is_err());
+    }
+
+    #[test]
+    fn vapid_token_is_signed_for_endpoint_origin() {
+        let vapid = Vapid::new(AS_PRIVATE, "mailto:admin@example.com").unwrap();
+        assert_eq!(vapid.public_key(), AS_PUBLIC);
+
+        let token = vapid
+            .token("https://updates.push.services.mozilla.com/wpush/v2/abc", 1_700_000_000)
+            .unwrap();
+        let parts: Vec<&str> = token.split('.').collect();
+        assert_eq!(parts.len(), 3);
+
+        let claims: serde_json::Value = serde_json::from_slice(&b64(parts[1])).unwrap();
+        assert_eq!(claims["aud"], "https://updates.push.services.mozilla.com");
+        assert_eq!(claims["exp"], 1_700_000_000 + 12 * 60 * 60);
+        assert_eq!(claims["sub"], "mailto:admin@example.com");
+
+        let verifying = VerifyingKey::from_sec1_bytes(&b64(AS_PUBLIC)).unwrap();
+        let sig = Signature::from_slice(&b64(parts[2])).unwrap();
+        let signed = format!("{}.{}", parts[0], parts[1]);
+        assert!(verifying.verify(signed.as_bytes(), &sig).is_ok());
+    }
+
+    #[test]
+    fn vapid_authorization_header() {
+        let vapid = Vapid::new(AS_PRIVATE, "mailto:admin@example.com").unwrap();
+        let header = vapid.authorization("https://fcm.googleapis.com/fcm/send/x", 0).unwrap();
+        assert!(header.starts_with("vapid t="));
+        assert!(header.ends_with(&format!(", k={}", AS_PUBLIC)));
+        assert!(vapid.authorization("not a url", 0).is_none());
+    }
+
+    #[test]
+    fn vapid_rejects_bad_key() {
+        assert!(Vapid::new("nope", "mailto:a@example.com").is_none());
+    }
+}
@@ -14,6 +14,38 @@ pub fn hex_string() -> String {
   hex::encode(res1)
 }
 
+/// A fresh random seed for [`Rng`].
+pub fn seed() -> u64 {
+  let mut buf = [0u8; 8];
+  getrandom::fill(&mut buf).unwrap();
+  u64::from_le_bytes(buf)
+}
+
+/// A small seeded generator (SplitMix64), for choices that must be reproducible from a seed.
+pub struct Rng(u64);
+
+impl Rng {
+  pub fn new(seed: u64) -> Self {
+    Rng(seed)
+  }
+
+  pub fn next_u64(&mut self) -> u64 {
+    self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = self.0;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+  }
+
+  /// Fisher-Yates shuffle.
+  pub fn shuffle<T>(&mut self, items: &mut [T]) {
+    for i in (1..items.len()).rev() {
+      let j = (self.next_u64() % (i as u64 + 1)) as usize;
+      items.swap(i, j);
+    }
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -23,4 +55,24 @@ mod tests {
     let hs = hex_string();
     assert_eq!(hs.len(), 20);
   }
+
+  #[test]
+  fn rng_is_deterministic() {
+    let mut a = Rng::new(42);
+    let mut b = Rng::new(42);
+    assert_eq!(a.next_u64(), b.next_u64());
+    let mut xs: Vec<u32> = (0..10).collect();
+    let mut ys = xs.clone();
+    a.shuffle(&mut xs);
+    b.shuffle(&mut ys);
+    assert_eq!(xs, ys);
+  }
+
+  #[test]
+  fn rng_shuffle_keeps_items() {
+    let mut xs: Vec<u32> = (0..10).collect();
+    Rng::new(7).shuffle(&mut xs);
+    xs.sort();
+    assert_eq!(xs, (0..10).collect::<Vec<_>>());
+  }
 }
@@ -0,0 +1,248 @@
+use std::cell::RefCell;
+
+use futures_channel::mpsc::{UnboundedSender, unbounded};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use worker::*;
+
+use crate::error::ApiError;
+use crate::service;
+use crate::types::{Game, SquadBoardEvent, SquadOp};
+
+/// Internal URL the worker uses to reach a team's room.
+pub const ROOM_URL: &str = "https://room";
+
+/// Header carrying the browser's Origin through to the room, so it can answer with CORS headers.
+pub const ORIGIN_HEADER: &str = "X-Nextgame-Origin";
+
+/// Header carrying the team key through to the room, for squad board sockets.
+pub const TEAM_HEADER: &str = "X-Nextgame-Team";
+
+/// Format one Server-Sent Events message. Multi-line data is split across `data:` lines.
+pub fn sse_event(event: &str, data: &str) -> String {
+    let mut msg = format!("event: {}\n", event);
+    for line in data.lines() {
+        msg.push_str("data: ");
+        msg.push_str(line);
+        msg.push('\n');
+    }
+    msg.push('\n');
+    msg
+}
+
+/// What the worker posts to `/publish` after writing a game: the game's KV key and its contents,
+/// or null when the team no longer has a current game.
+#[derive(Serialize, Deserialize)]
+pub struct GameUpdate {
+    pub key: String,
+    pub game: Game,
+}
+
+fn board_state(op: Option<SquadOp>, game: &Game) -> SquadBoardEvent {
+    SquadBoardEvent::State {
+        op,
+        squads: game.squads.clone(),
+        assignments: game.squad_assignments.clone(),
+    }
+}
+
+fn board_error(message: &str) -> SquadBoardEvent {
+    SquadBoardEvent::Error {
+        message: message.to_string(),
+    }
+}
+
+/// One per team. Fans out every game update to open event streams, and owns the squad board:
+/// squad board sockets send individual ops, which are applied here to the latest known game.
+#[durable_object]
+pub struct TeamRoom {
+    state: State,
+    env: Env,
+    listeners: RefCell<Vec<UnboundedSender<String>>>,
+    /// The current game and its key: loaded from KV, then updated by ops and publishes. Handlers
+    /// write games without going through the room, so a publish can carry a game read before
+    /// some ops; those are merged back in rather than lost (see [`service::merge_squad_board`]).
+    current: RefCell<Option<(String, Game)>>,
+}
+
+impl TeamRoom {
+    fn fan_out(&self, game: Option<&Game>) {
+        let game = game.cloned().map(crate::service::public_game);
+        let msg = sse_event("game", &serde_json::to_string(&game).unwrap());
+        self.listeners
+            .borrow_mut()
+            .retain(|tx| tx.unbounded_send(msg.clone()).is_ok());
+    }
+
+    async fn current_game(&self, team_key: &str) -> Result<Option<Game>> {
+        if self.current.borrow().is_none() {
+            let loaded = self.load_game(team_key).await?;
+            if self.current.borrow().is_none() {
+                *self.current.borrow_mut() = loaded;
+            }
+        }
+        Ok(self.current.borrow().as_ref().map(|(_, g)| g.clone()))
+    }
+
+    async fn load_game(&self, team_key: &str) -> Result<Option<(String, Game)>> {
+        let team = match crate::load_team(&self.env.kv("teams")?, team_key).await? {
+            Some(t) => t,
+            None => return Ok(None),
+        };
+        let ng_key = match team.next_game {
+            Some(k) => k,
+            None => return Ok(None),
+        };
+        let game = crate::load_game(&self.env.kv("games")?, &ng_key).await?;
+        Ok(game.map(|g| (ng_key, g)))
+    }
+
+    async fn apply_op(&self, team_key: &str, op: &SquadOp) -> Result<std::result::Result<Game, &'static str>> {
+        if self.current_game(team_key).await?.is_none() {
+            return Ok(Err("game not found"));
+        }
+        // Apply without awaiting in between, so concurrent ops each see the previous one's result.
+        let (ng_key, game) = {
+            let mut current = self.current.borrow_mut();
+            let (ng_key, game) = current.as_mut().unwrap();
+            if let Err(msg) = service::apply_squad_op(game, op) {
+                return Ok(Err(msg.message()));
+            }
+            (ng_key.clone(), game.clone())
+        };
+        if let Err(e) = self.save_game(&ng_key, &game).await {
+            // Forget the op, and anything applied on top of it, by reading the game again next time.
+            *self.current.borrow_mut() = None;
+            return Err(e);
+        }
+        Ok(Ok(game))
+    }
+
+    async fn save_game(&self, key: &str, game: &Game) -> Result<()> {
+        self.env
+            .kv("games")?
+            .put(key, serde_json::to_string(game).unwrap())?
+            .execute()
+            .await?;
+        Ok(())
+    }
+
+    /// Take a game a handler just wrote, keeping any squad board ops it missed.
+    async fn publish(&self, update: Option<GameUpdate>) {
+        let mut update = update;
+        let merged = match (update.as_mut(), self.current.borrow().as_ref()) {
+            (Some(u), Some((key, known))) if u.key == *key => service::merge_squad_board(&mut u.game, known),
+            _ => false,
+        };
+        self.fan_out(update.as_ref().map(|u| &u.game));
+        *self.current.borrow_mut() = update.as_ref().map(|u| (u.key.clone(), u.game.clone()));
+        if let Some(u) = update.filter(|_| merged)
+            && let Err(e) = self.save_game(&u.key, &u.game).await
+        {
+            console_error!("failed to save merged squads for game {}: {}", u.key, e);
+        }
+    }
+}
+
+impl DurableObject for TeamRoom {
+    fn new(state: State, env: Env) -> Self {
+        TeamRoom {
+            state,
+            env,
+            listeners: RefCell::new(Vec::new()),
+            current: RefCell::new(None),
+        }
+    }
+
+    async fn fetch(&self, mut req: Request) -> Result<Response> {
+        let path = req.path();
+        match (req.method(), path.as_str()) {
+            (Method::Get, "/events") => {
+                let origin = req.headers().get(ORIGIN_HEADER)?.unwrap_or_default();
+                let (tx, rx) = unbounded();
+                // Tell the browser how long to wait before reconnecting if the room goes away.
+                let _ = tx.unbounded_send("retry: 3000\n\n".to_string());
+                self.listeners.borrow_mut().push(tx);
+
+                let mut resp = Response::from_stream(rx.map(Ok::<String, Error>))?;
+                let headers = resp.headers_mut();
+                for (k, v) in crate::cors_headers(&origin).entries() {
+                    let _ = headers.set(&k, &v);
+                }
+                let _ = headers.set("Content-Type", "text/event-stream");
+                let _ = headers.set("Cache-Control", "no-cache");
+                Ok(resp)
+            }
+            (Method::Post, "/publish") => {
+                let update: Option<GameUpdate> = req.json().await?;
+                self.publish(update).await;
+                Response::empty()
+            }
+            (Method::Get, "/squads") => {
+                let team_key = req.headers().get(TEAM_HEADER)?.unwrap_or_default();
+                let origin = req.headers().get(ORIGIN_HEADER)?.unwrap_or_default();
+                let game = match self.current_game(&team_key).await? {
+                    Some(g) => g,
+                    None => return crate::api_error(ApiError::GameNotFound, &origin),
+                };
+
+                let pair = WebSocketPair::new()?;
+                self.state.accept_web_socket(&pair.server);
+                pair.server.serialize_attachment(&team_key)?;
+                pair.server.send(&board_state(None, &game))?;
+                Response::from_websocket(pair.client)
+            }
+            _ => crate::api_error(ApiError::NotFound, &req.headers().get(ORIGIN_HEADER)?.unwrap_or_default()),
+        }
+    }
+
+    async fn websocket_message(&self, ws: WebSocket, message: WebSocketIncomingMessage) -> Result<()> {
+        let op: SquadOp = match message {
+            WebSocketIncomingMessage::String(s) => match serde_json::from_str(&s) {
+                Ok(op) => op,
+                Err(_) => return ws.send(&board_error("invalid squad op")),
+            },
+            WebSocketIncomingMessage::Binary(_) => return ws.send(&board_error("invalid squad op")),
+        };
+        let team_key: String = match ws.deserialize_attachment()? {
+            Some(k) => k,
+            None => return ws.send(&board_error("team not found")),
+        };
+
+        match self.apply_op(&team_key, &op).await? {
+            Ok(game) => {
+                let event = board_state(Some(op), &game);
+                for socket in self.state.get_websockets() {
+                    let _ = socket.send(&event);
+                }
+                self.fan_out(Some(&game));
+                Ok(())
+            }
+            Err(msg) => ws.send(&board_error(msg)),
+        }
+    }
+
+    async fn websocket_close(&self, ws: WebSocket, code: usize, reason: String, _was_clean: bool) -> Result<()> {
+        ws.close(Some(code as u16), Some(reason))
+    }
+
+    async fn websocket_error(&self, _ws: WebSocket, error: Error) -> Result<()> {
+        console_error!("squad board socket error: {}", error);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sse_event_single_line() {
+        assert_eq!(sse_event("game", r#"{"a":1}"#), "event: game\ndata: {\"a\":1}\n\n");
+    }
+
+    #[test]
+    fn sse_event_multi_line() {
+        assert_eq!(sse_event("game", "a\nb"), "event: game\ndata: a\ndata: b\n\n");
+    }
+}
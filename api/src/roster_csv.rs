@@ -0,0 +1,294 @@
+//! Moving the roster in and out of spreadsheets.
+
+use crate::error::CsvError;
+use crate::random;
+use crate::service::{apply_player_update, same_name};
+use crate::types::{
+    FieldError, ImportDuplicate, ImportError, Player, PlayerUpdate, RosterImportRequest, RosterImportResponse, Team,
+    Validate,
+};
+
+/// Columns, in export order. Imports need `name`; the rest are optional and can come in any order.
+const COLUMNS: [&str; 7] = ["name", "position", "shirt_number", "phone", "email", "notes", "active"];
+
+/// Most rows one import can have.
+const MAX_IMPORT_ROWS: usize = 500;
+
+/// Largest import, CSV or request body, in bytes: plenty for [`MAX_IMPORT_ROWS`] rows.
+pub const MAX_IMPORT_BYTES: usize = 256 * 1024;
+
+/// The roster as CSV with a header row, sorted by name.
+pub fn export(team: &Team) -> String {
+    let mut players: Vec<&Player> = team.players.values().collect();
+    players.sort_by_key(|p| p.name.to_lowercase());
+
+    let mut w = csv::Writer::from_writer(Vec::new());
+    w.write_record(COLUMNS).unwrap();
+    for p in players {
+        let shirt = p.shirt_number.map(|n| n.to_string()).unwrap_or_default();
+        w.write_record([
+            cell(&p.name),
+            cell(p.position.as_deref().unwrap_or("")),
+            shirt,
+            cell(p.phone.as_deref().unwrap_or("")),
+            cell(p.email.as_deref().unwrap_or("")),
+            cell(p.notes.as_deref().unwrap_or("")),
+            (if p.active { "yes" } else { "no" }).to_string(),
+        ])
+        .unwrap();
+    }
+    String::from_utf8(w.into_inner().unwrap()).unwrap()
+}
+
+/// Spreadsheets run a cell that starts with one of these as a formula.
+const FORMULA_STARTS: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+/// A text cell that a spreadsheet will show as text: anything that would start a formula gets a
+/// `'` in front. [`preview`] takes it off again.
+fn cell(value: &str) -> String {
+    if value.starts_with(FORMULA_STARTS) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Undo [`cell`].
+fn uncell(value: &str) -> &str {
+    match value.strip_prefix('\'') {
+        Some(rest) if rest.starts_with(FORMULA_STARTS) => rest,
+        _ => value,
+    }
+}
+
+/// Work out what importing this CSV would add. Nothing on the team changes; hand the result to
+/// [`apply`] to add the players. Err if the file itself can't be read.
+pub fn preview(team: &Team, text: &str) -> Result<RosterImportResponse, CsvError> {
+    if text.len() > MAX_IMPORT_BYTES {
+        return Err(CsvError::TooLarge);
+    }
+    let mut r = csv::ReaderBuilder::new().flexible(true).trim(csv::Trim::All).from_reader(text.as_bytes());
+    let headers: Vec<String> = r.headers().map_err(|e| CsvError::Unreadable(e.to_string()))?.iter().map(|h| h.to_lowercase()).collect();
+    if let Some(h) = headers.iter().find(|h| !COLUMNS.contains(&h.as_str())) {
+        return Err(CsvError::UnknownColumn(h.clone()));
+    }
+    if !headers.iter().any(|h| h == "name") {
+        return Err(CsvError::MissingNameColumn);
+    }
+
+    let mut out = RosterImportResponse::default();
+    let mut rows = 0;
+    for record in r.records() {
+        let record = record.map_err(|e| CsvError::Unreadable(e.to_string()))?;
+        let line = record.position().map(|p| p.line()).unwrap_or(0);
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        rows += 1;
+        if rows > MAX_IMPORT_ROWS {
+            return Err(CsvError::TooManyRows(MAX_IMPORT_ROWS));
+        }
+
+        let mut body = serde_json::Map::new();
+        for (header, value) in headers.iter().zip(record.iter()) {
+            let value = uncell(value);
+            let value = match header.as_str() {
+                "shirt_number" if value.is_empty() => serde_json::Value::Null,
+                // Left as text if it isn't a number, so validation reports it.
+                "shirt_number" => value.parse::<u64>().map_or(value.into(), Into::into),
+                "active" => parse_yes_no(value).map_or(value.into(), Into::into),
+                _ => value.into(),
+            };
+            body.insert(header.clone(), value);
+        }
+
+        let name = body.get("name").and_then(|n| n.as_str()).unwrap_or("").to_string();
+        if let Some((pid, _)) = team.players.iter().find(|(_, p)| same_name(&p.name, &name)) {
+            out.duplicates.push(ImportDuplicate {
+                line,
+                name,
+                player_id: Some(pid.clone()),
+            });
+            continue;
+        }
+        if out.added.iter().any(|p| same_name(&p.name, &name)) {
+            out.duplicates.push(ImportDuplicate {
+                line,
+                name,
+                player_id: None,
+            });
+            continue;
+        }
+
+        match row_update(body) {
+            Ok(update) => {
+                let mut player = Player::from("");
+                apply_player_update(&mut player, &update);
+                out.added.push(player);
+            }
+            Err(message) => out.errors.push(ImportError { line, message }),
+        }
+    }
+    Ok(out)
+}
+
+impl Validate for RosterImportRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.csv.trim().is_empty() {
+            errors.push(FieldError::new("csv", "csv can't be empty"));
+        }
+        errors
+    }
+}
+
+/// Add the players from a [`preview`] to the roster, unless any row had an error.
+/// Returns whether they were added.
+pub fn apply(team: &mut Team, import: &mut RosterImportResponse) -> bool {
+    if !import.errors.is_empty() {
+        return false;
+    }
+    for player in &import.added {
+        team.players.insert(random::hex_string(), player.clone());
+    }
+    import.imported = true;
+    true
+}
+
+/// A row as a player update, checked the same way as edits through the API. Every row needs a name.
+fn row_update(row: serde_json::Map<String, serde_json::Value>) -> Result<PlayerUpdate, String> {
+    let update: PlayerUpdate = serde_path_to_error::deserialize(serde_json::Value::Object(row))
+        .map_err(|e| format!("invalid {}", e.path().to_string().replace('_', " ")))?;
+    if let Some(e) = update.validate().into_iter().next() {
+        return Err(e.message);
+    }
+    match &update.name {
+        Some(name) if !name.trim().is_empty() => Ok(update),
+        _ => Err("player name can't be empty".to_string()),
+    }
+}
+
+fn parse_yes_no(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "" | "yes" | "y" | "true" | "1" => Some(true),
+        "no" | "n" | "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn team_with(names: &[(&str, &str)]) -> Team {
+        let mut team: Team = serde_json::from_value(serde_json::json!({
+            "name": "T",
+            "secret": "s",
+            "next_game": null,
+            "players": {}
+        }))
+        .unwrap();
+        team.players = names.iter().map(|(pid, n)| (pid.to_string(), Player::from(*n))).collect::<HashMap<_, _>>();
+        team
+    }
+
+    #[test]
+    fn export_round_trips() {
+        let mut team = team_with(&[("p1", "Bob"), ("p2", "alice")]);
+        team.players.get_mut("p1").unwrap().shirt_number = Some(9);
+        team.players.get_mut("p1").unwrap().notes = Some("Prefers, \"left\" wing".into());
+        team.players.get_mut("p2").unwrap().active = false;
+
+        let csv = export(&team);
+        assert_eq!(csv.lines().next(), Some("name,position,shirt_number,phone,email,notes,active"));
+        assert!(csv.lines().nth(1).unwrap().starts_with("alice,"));
+
+        let mut empty = team_with(&[]);
+        let mut import = preview(&empty, &csv).unwrap();
+        assert!(import.errors.is_empty());
+        assert!(apply(&mut empty, &mut import));
+        let mut players: Vec<Player> = empty.players.into_values().collect();
+        let mut original: Vec<Player> = team.players.into_values().collect();
+        players.sort_by(|a, b| a.name.cmp(&b.name));
+        original.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(players, original);
+    }
+
+    #[test]
+    fn export_escapes_formulas() {
+        let mut team = team_with(&[("p1", "=HYPERLINK(\"http://x\")")]);
+        team.players.get_mut("p1").unwrap().phone = Some("+44 7700 900000".into());
+        team.players.get_mut("p1").unwrap().notes = Some("-1 goal, @ref".into());
+
+        let csv = export(&team);
+        assert_eq!(
+            csv.lines().nth(1),
+            Some("\"'=HYPERLINK(\"\"http://x\"\")\",,,'+44 7700 900000,,\"'-1 goal, @ref\",yes")
+        );
+
+        let mut empty = team_with(&[]);
+        let mut import = preview(&empty, &csv).unwrap();
+        assert!(apply(&mut empty, &mut import));
+        let player = empty.players.into_values().next().unwrap();
+        assert_eq!(player, team.players["p1"]);
+    }
+
+    #[test]
+    fn export_escapes_tabs_and_returns() {
+        let team = team_with(&[("p1", "\tTab"), ("p2", "\rReturn")]);
+        let csv = export(&team);
+        assert!(csv.contains("'\tTab"));
+        assert!(csv.contains("'\rReturn"));
+    }
+
+    #[test]
+    fn preview_stops_at_too_many_rows() {
+        let team = team_with(&[]);
+        let rows = |n: usize| (0..n).fold("name\n".to_string(), |csv, i| csv + &format!("Player {}\n", i));
+        assert_eq!(preview(&team, &rows(MAX_IMPORT_ROWS)).unwrap().added.len(), MAX_IMPORT_ROWS);
+        // Duplicates count too: it's rows that are limited, not players added.
+        let dupes = rows(MAX_IMPORT_ROWS) + "Player 0\n";
+        assert_eq!(preview(&team, &dupes), Err(CsvError::TooManyRows(MAX_IMPORT_ROWS)));
+        let big = format!("name\n{}\n", "x".repeat(MAX_IMPORT_BYTES));
+        assert_eq!(preview(&team, &big), Err(CsvError::TooLarge));
+    }
+
+    #[test]
+    fn preview_finds_duplicates_and_errors() {
+        let team = team_with(&[("p1", "Jon")]);
+        let csv = "Name,Shirt_Number,Email\n jon ,1,\nAnna,2,\nanna,3,\nBen,100,\n,4,\nCat,,cat\n\nDan,,dan@example.com\n";
+        let import = preview(&team, csv).unwrap();
+        let added: Vec<&str> = import.added.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(added, vec!["Anna", "Dan"]);
+        assert_eq!(
+            import.duplicates,
+            vec![
+                ImportDuplicate { line: 2, name: "jon".into(), player_id: Some("p1".into()) },
+                ImportDuplicate { line: 4, name: "anna".into(), player_id: None },
+            ]
+        );
+        let errors: Vec<(u64, &str)> = import.errors.iter().map(|e| (e.line, e.message.as_str())).collect();
+        assert_eq!(
+            errors,
+            vec![
+                (5, "shirt number must be between 0 and 99"),
+                (6, "player name can't be empty"),
+                (7, "invalid email"),
+            ]
+        );
+
+        let mut team = team;
+        let mut import = import;
+        assert!(!apply(&mut team, &mut import));
+        assert_eq!(team.players.len(), 1);
+        assert!(!import.imported);
+    }
+
+    #[test]
+    fn preview_rejects_bad_headers() {
+        let team = team_with(&[]);
+        assert_eq!(preview(&team, "name,age\nJon,30\n"), Err(CsvError::UnknownColumn("age".into())));
+        assert_eq!(preview(&team, "email\na@b.c\n"), Err(CsvError::MissingNameColumn));
+    }
+}
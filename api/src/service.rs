@@ -1,13 +1,27 @@
 use std::collections::{HashMap, HashSet};
 
-use jiff::ToSpan;
-use jiff::civil::Weekday;
-use crate::types::{Comment, Game, Team, TeamPageResponse};
+use jiff::civil::{Time, Weekday};
+use jiff::tz::TimeZone;
+use jiff::{Timestamp, ToSpan};
+use crate::error::ServiceError;
+use crate::mail::{self, Email};
+use crate::migrate;
+use crate::push::PushMessage;
+use crate::types::{
+    AddGuestRequest, AddPlayersRequest, AttendanceRequest, AutoSquadsRequest, Comment, CommentRequest,
+    ContactRequest, DefaultSquadsRequest, FieldError, Game, Guest, JoinRequest, JoinTeamRequest, LeaderboardEntry,
+    MergePlayerRequest, MvpVoteRequest, NewGameRequest, NewTeamRequest, NoShowEntry, Notifications, Player, PlayerID, PlayerStats,
+    PlayerUpdate, PromoteGuestRequest, PublicProfile, PushSubscription, RatingsRequest, RemovePushSubscriptionRequest,
+    ResultsRequest, Rsvp, ScoresRequest, RsvpCounts, RsvpRequest, RsvpStatus, SettingsRequest, SquadID,
+    SquadOp, SquadsRequest, Team, TeamArchive, TeamPageResponse, Validate,
+};
 
 use crate::random;
 
 /// Build the API response from team + game data.
 pub fn team_response(team: &Team, key: &str, game: Option<Game>) -> TeamPageResponse {
+    let window = game.as_ref().and_then(|g| mvp_voting_window(team, g));
+    let rsvp_counts = game.as_ref().map(rsvp_counts);
     TeamPageResponse {
         team_name: team.name.clone(),
         team_key: key.to_string(),
@@ -15,14 +29,70 @@ pub fn team_response(team: &Team, key: &str, game: Option<Game>) -> TeamPageResp
         time: team.time.clone(),
         weekly_schedule: team.weekly_schedule,
         default_squads: team.default_squads.clone(),
-        players: team.players.clone(),
-        game,
+        players: roster_names(team),
+        inactive_players: inactive_players(team),
+        profiles: public_profiles(team),
+        rsvp_counts,
+        rsvp_deadline_hours: team.rsvp_deadline_hours,
+        guest_limit: team.guest_limit,
+        rsvp_deadline: game.as_ref().and_then(|g| rsvp_deadline(team, g)),
+        results_open: team.results_open,
+        mvp_voting_hours: team.mvp_voting_hours,
+        mvp_voting_opens: window.map(|(opens, _)| opens),
+        mvp_voting_closes: window.map(|(_, closes)| closes),
+        game: game.map(public_game),
+        ratings: None,
+        join_requests: None,
+        player_details: None,
     }
 }
 
+/// Like [`team_response`], plus the details only admins get to see.
+pub fn admin_team_response(team: &Team, key: &str, game: Option<Game>) -> TeamPageResponse {
+    TeamPageResponse {
+        ratings: Some(team.ratings.clone()),
+        join_requests: Some(team.join_requests.clone()),
+        player_details: Some(team.players.clone()),
+        ..team_response(team, key, game)
+    }
+}
+
+/// Whether the game under this key is the team's, current or past.
+pub fn has_game(team: &Team, key: &str) -> bool {
+    team.next_game.as_deref() == Some(key) || team.past_games.iter().any(|k| k == key)
+}
+
+/// Every roster player's name, by id.
+pub fn roster_names(team: &Team) -> HashMap<PlayerID, String> {
+    team.players.iter().map(|(pid, p)| (pid.clone(), p.name.clone())).collect()
+}
+
+/// Ids of inactive players, sorted.
+fn inactive_players(team: &Team) -> Vec<PlayerID> {
+    let mut ids: Vec<PlayerID> = team.players.iter().filter(|(_, p)| !p.active).map(|(pid, _)| pid.clone()).collect();
+    ids.sort();
+    ids
+}
+
+/// Position and shirt number of every player who has set either. Contact details stay out.
+fn public_profiles(team: &Team) -> HashMap<PlayerID, PublicProfile> {
+    team.players
+        .iter()
+        .filter(|(_, p)| p.position.is_some() || p.shirt_number.is_some())
+        .map(|(pid, p)| {
+            let profile = PublicProfile {
+                position: p.position.clone(),
+                shirt_number: p.shirt_number,
+            };
+            (pid.clone(), profile)
+        })
+        .collect()
+}
+
 /// Create a new game from the team's defaults.
 pub fn make_new_game(team: &Team, description: String) -> Game {
     Game {
+        schema_version: migrate::GAME_SCHEMA_VERSION,
         description,
         players: HashMap::new(),
         guests: Vec::new(),
@@ -37,6 +107,29 @@ pub fn make_new_game(team: &Team, description: String) -> Game {
         squads: team.default_squads.clone(),
         squad_assignments: HashMap::new(),
         is_game_off: false,
+        reminder_sent: false,
+        kickoff_push_sent: false,
+        squad_revision: 0,
+        rating_changes: HashMap::new(),
+        squad_scores: HashMap::new(),
+        player_stats: HashMap::new(),
+        mvp_votes: HashMap::new(),
+        mvp_tally: HashMap::new(),
+        mvp: None,
+        attendance: HashMap::new(),
+    }
+}
+
+/// Longest game description, in characters.
+const MAX_DESCRIPTION_CHARS: usize = 200;
+
+impl Validate for NewGameRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.description.trim().chars().count() > MAX_DESCRIPTION_CHARS {
+            errors.push(FieldError::new("description", "description is too long"));
+        }
+        errors
     }
 }
 
@@ -55,7 +148,7 @@ pub fn should_reset_game(team: &Team, game: &Game) -> bool {
 /// Ensure all team players exist in the game's player map.
 /// Returns true if any were added.
 pub fn populate_unregistered_players(team: &Team, game: &mut Game) -> bool {
-    let tp_set: HashSet<_> = team.players.keys().cloned().collect();
+    let tp_set: HashSet<_> = team.players.iter().filter(|(_, p)| p.active).map(|(pid, _)| pid.clone()).collect();
     let gp_set: HashSet<_> = game.players.keys().cloned().collect();
     let new_players: HashMap<_, _> = tp_set
         .difference(&gp_set)
@@ -69,39 +162,167 @@ pub fn populate_unregistered_players(team: &Team, game: &mut Game) -> bool {
     }
 }
 
-/// Set a player's status.
-pub fn set_player_status(game: &mut Game, player_id: &str, playing: bool) {
-    game.players.insert(player_id.to_string(), Some(playing));
+/// Longest RSVP note, in characters.
+const MAX_RSVP_NOTE_CHARS: usize = 100;
+
+/// Set a player's RSVP. A note replaces the one they had, and an empty note clears it;
+/// without one, any earlier note is kept.
+pub fn set_player_status(
+    game: &mut Game,
+    player_id: &str,
+    status: RsvpStatus,
+    note: Option<&str>,
+) -> Result<(), ServiceError> {
+    let note = match note.map(str::trim) {
+        Some(n) if n.chars().count() > MAX_RSVP_NOTE_CHARS => return Err(ServiceError::NoteTooLong),
+        Some("") => None,
+        Some(n) => Some(n.to_string()),
+        None => game.players.get(player_id).cloned().flatten().and_then(|r| r.note),
+    };
+    game.players.insert(player_id.to_string(), Some(Rsvp { status, note }));
+    Ok(())
+}
+
+impl Validate for RsvpRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.note.as_ref().is_some_and(|n| n.trim().chars().count() > MAX_RSVP_NOTE_CHARS) {
+            errors.push(FieldError::new("note", "note is too long"));
+        }
+        errors
+    }
+}
+
+/// The longest RSVP deadline a team can set: a week before kick-off.
+const MAX_RSVP_DEADLINE_HOURS: u64 = 168;
+
+/// When RSVPs for the game lock, if the team has a deadline and the game has a kick-off time.
+pub fn rsvp_deadline(team: &Team, game: &Game) -> Option<Timestamp> {
+    let hours = team.rsvp_deadline_hours?;
+    kickoff(team, game)?.checked_sub(i64::from(hours).hours()).ok()
+}
+
+/// Err once the RSVP deadline has passed. Admins aren't held to it, so their handlers skip this.
+pub fn check_rsvp_open(team: &Team, game: &Game, now: Timestamp) -> Result<(), ServiceError> {
+    match rsvp_deadline(team, game) {
+        Some(deadline) if now >= deadline => Err(ServiceError::RsvpClosed),
+        _ => Ok(()),
+    }
+}
+
+/// Whether a player said they're coming, even if late.
+fn is_playing(game: &Game, player_id: &str) -> bool {
+    game.players.get(player_id).is_some_and(|r| r.as_ref().is_some_and(Rsvp::is_playing))
+}
+
+/// Whether a player said they're not coming.
+fn has_declined(game: &Game, player_id: &str) -> bool {
+    game.players
+        .get(player_id)
+        .is_some_and(|r| r.as_ref().is_some_and(|r| r.status == RsvpStatus::Out))
+}
+
+/// Tally the game's RSVPs.
+pub fn rsvp_counts(game: &Game) -> RsvpCounts {
+    let mut counts = RsvpCounts {
+        guests: game.guests.len(),
+        ..Default::default()
+    };
+    for rsvp in game.players.values() {
+        match rsvp.as_ref().map(|r| r.status) {
+            Some(RsvpStatus::In) => counts.r#in += 1,
+            Some(RsvpStatus::Late) => counts.late += 1,
+            Some(RsvpStatus::Maybe) => counts.maybe += 1,
+            Some(RsvpStatus::Out) => counts.out += 1,
+            None => counts.no_answer += 1,
+        }
+    }
+    counts.playing = counts.r#in + counts.late + counts.guests;
+    counts
 }
 
+/// Longest comment, in characters.
+const MAX_COMMENT_CHARS: usize = 500;
+
 /// Add a comment. Returns Err if empty.
-pub fn add_comment(game: &mut Game, text: &str, author: Option<&str>) -> Result<(), &'static str> {
+pub fn add_comment(game: &mut Game, text: &str, author: Option<&str>) -> Result<(), ServiceError> {
     if text.is_empty() {
-        return Err("comment can't be empty");
+        return Err(ServiceError::EmptyComment);
     }
-    game.comments.push(Comment::Full {
+    game.comments.push(Comment {
         text: text.to_string(),
         author: author.and_then(|a| if a.is_empty() { None } else { Some(a.to_string()) }),
+        legacy: false,
     });
     Ok(())
 }
 
+impl Validate for CommentRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_text(&mut errors, "comment", &self.comment, MAX_COMMENT_CHARS, "comment");
+        if self.author.as_ref().is_some_and(|a| a.trim().chars().count() > MAX_NAME_CHARS) {
+            errors.push(FieldError::new("author", "author is too long"));
+        }
+        errors
+    }
+}
+
 /// Add guests from a comma-separated string. Returns Err if empty.
-pub fn add_guests(game: &mut Game, names: &str) -> Result<(), &'static str> {
+/// `invited_by` must be a roster player. With a limit, they can't bring more than that many guests
+/// in total, and guests nobody brought share one allowance of the same size.
+pub fn add_guests(
+    team: &Team,
+    game: &mut Game,
+    names: &str,
+    invited_by: Option<&str>,
+    limit: Option<u32>,
+) -> Result<(), ServiceError> {
+    let names: Vec<&str> = names.trim().split(',').map(str::trim).filter(|n| !n.is_empty()).collect();
     if names.is_empty() {
-        return Err("guest_name can't be empty");
+        return Err(ServiceError::EmptyGuestName);
+    }
+    if let Some(pid) = invited_by
+        && !team.players.contains_key(pid)
+    {
+        return Err(ServiceError::PlayerNotFound);
+    }
+    if let Some(limit) = limit {
+        let brought = game.guests.iter().filter(|g| g.invited_by.as_deref() == invited_by).count();
+        if brought + names.len() > limit as usize {
+            return Err(ServiceError::GuestLimitReached);
+        }
     }
-    names
-        .trim()
-        .split(',')
-        .for_each(|g| game.guests.push(g.trim().to_string()));
+    game.guests.extend(names.into_iter().map(|n| Guest {
+        id: random::hex_string(),
+        name: n.to_string(),
+        invited_by: invited_by.map(str::to_string),
+    }));
     Ok(())
 }
 
-/// Remove a guest by index.
-pub fn delete_guest(game: &mut Game, idx: usize) {
-    if idx < game.guests.len() {
-        game.guests.remove(idx);
+impl Validate for AddGuestRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_names(&mut errors, "guest_name", &self.guest_name, "guest name");
+        errors
+    }
+}
+
+/// Whether a squad board id (a player id, or `guest:<id>`) is someone in the game.
+fn in_game(game: &Game, id: &str) -> bool {
+    match id.strip_prefix("guest:") {
+        Some(gid) => game.guests.iter().any(|g| g.id == gid),
+        None => game.players.contains_key(id),
+    }
+}
+
+/// Remove a guest by id, along with their squad assignment and check-in.
+pub fn delete_guest(game: &mut Game, id: &str) {
+    if let Some(idx) = game.guests.iter().position(|g| g.id == id) {
+        let board_id = game.guests.remove(idx).board_id();
+        game.squad_assignments.remove(&board_id);
+        game.attendance.remove(&board_id);
     }
 }
 
@@ -113,281 +334,2703 @@ pub fn save_squad_assignments(
     game.squad_assignments = assignments;
 }
 
-/// Apply settings from a JSON body to a team.
-pub fn apply_settings(team: &mut Team, body: &serde_json::Value) {
-    if let Some(name) = body.get("name") {
-        if let Some(n) = name.as_str() {
-            let n = n.trim();
-            if !n.is_empty() {
-                team.name = n.to_string();
+/// Longest squad name, in characters.
+const MAX_SQUAD_NAME_CHARS: usize = 30;
+
+impl Validate for SquadsRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if let Some(squads) = &self.squads {
+            check_squad_names(&mut errors, "squads", squads);
+        }
+        errors
+    }
+}
+
+/// Apply one squad board change. Returns Err if it refers to a squad that doesn't exist (or
+/// already does), or assigns someone who isn't in the game.
+pub fn apply_squad_op(game: &mut Game, op: &SquadOp) -> Result<(), ServiceError> {
+    match op {
+        SquadOp::Assign { player, squad } => {
+            if !game.squads.contains_key(squad) {
+                return Err(ServiceError::SquadNotFound);
+            }
+            if !in_game(game, player) {
+                return Err(ServiceError::PlayerNotFound);
+            }
+            game.squad_assignments.insert(player.clone(), squad.clone());
+        }
+        SquadOp::Unassign { player } => {
+            game.squad_assignments.remove(player);
+        }
+        SquadOp::AddSquad { squad, name } => {
+            if squad.is_empty() || name.trim().is_empty() {
+                return Err(ServiceError::EmptySquad);
+            }
+            if game.squads.contains_key(squad) {
+                return Err(ServiceError::SquadExists);
+            }
+            game.squads.insert(squad.clone(), name.trim().to_string());
+        }
+        SquadOp::RenameSquad { squad, name } => {
+            if name.trim().is_empty() {
+                return Err(ServiceError::EmptySquadName);
+            }
+            match game.squads.get_mut(squad) {
+                Some(n) => *n = name.trim().to_string(),
+                None => return Err(ServiceError::SquadNotFound),
+            }
+        }
+        SquadOp::RemoveSquad { squad } => {
+            if game.squads.remove(squad).is_none() {
+                return Err(ServiceError::SquadNotFound);
+            }
+            game.squad_assignments.retain(|_, s| s != squad);
+        }
+    }
+    game.squad_revision += 1;
+    Ok(())
+}
+
+/// Keep squad board changes a publish would lose. When `published` was read before some of the
+/// ops that made `known`, its squads are replaced with `known`'s, minus anyone who has since
+/// left the game. Returns whether `published` changed.
+pub fn merge_squad_board(published: &mut Game, known: &Game) -> bool {
+    if published.squad_revision >= known.squad_revision {
+        return false;
+    }
+    published.squads = known.squads.clone();
+    published.squad_assignments = known.squad_assignments.clone();
+    published.squad_revision = known.squad_revision;
+    let game = published.clone();
+    published.squad_assignments.retain(|id, _| in_game(&game, id));
+    true
+}
+
+/// Rating assumed for anyone without one, guests included.
+pub const DEFAULT_RATING: f64 = 1000.0;
+
+/// How many rating points of imbalance one pairing repeated from last game is worth.
+const REPEAT_PAIR_PENALTY: f64 = 25.0;
+
+/// Everyone confirmed for the game, by squad board id: player ids, and `guest:<id>` for guests.
+pub fn confirmed_participants(game: &Game) -> Vec<String> {
+    let mut people: Vec<String> = game
+        .players
+        .iter()
+        .filter(|(pid, _)| is_playing(game, pid))
+        .map(|(pid, _)| pid.clone())
+        .collect();
+    people.extend(game.guests.iter().map(Guest::board_id));
+    people
+}
+
+/// Pairs of players (not guests) who shared a squad, as (smaller id, larger id).
+fn squad_pairs(assignments: &HashMap<PlayerID, String>) -> HashSet<(String, String)> {
+    let mut pairs = HashSet::new();
+    for (a, sa) in assignments {
+        for (b, sb) in assignments {
+            if a < b && sa == sb && !a.starts_with("guest:") && !b.starts_with("guest:") {
+                pairs.insert((a.clone(), b.clone()));
+            }
+        }
+    }
+    pairs
+}
+
+/// Gap between the highest and lowest average rating, over squads with anyone in them.
+fn rating_spread(sums: &[f64], squads: &[Vec<String>]) -> f64 {
+    let means = sums.iter().zip(squads).filter(|(_, s)| !s.is_empty()).map(|(sum, s)| sum / s.len() as f64);
+    let (lo, hi) = means.fold((f64::MAX, f64::MIN), |(lo, hi), m| (lo.min(m), hi.max(m)));
+    if lo > hi { 0.0 } else { hi - lo }
+}
+
+/// Most rounds of swaps [`auto_squads`] tries. It stops sooner once a round changes nothing.
+const MAX_SWAP_ROUNDS: usize = 50;
+
+/// Replace the squad assignments with a balanced split of everyone confirmed.
+/// Squads come from the game, or the team's defaults if the game has none. Squad sizes differ by
+/// at most one, average ratings are kept close, and pairs who played together in `previous` are
+/// split up where that doesn't cost much balance. The same seed always gives the same result.
+pub fn auto_squads(team: &Team, game: &mut Game, previous: Option<&Game>, seed: u64) -> Result<(), ServiceError> {
+    if game.squads.is_empty() {
+        game.squads = team.default_squads.clone();
+    }
+    if game.squads.is_empty() {
+        return Err(ServiceError::NoSquads);
+    }
+    let mut people = confirmed_participants(game);
+    if people.is_empty() {
+        return Err(ServiceError::NobodyPlaying);
+    }
+
+    let rating = |p: &str| team.ratings.get(p).copied().unwrap_or(DEFAULT_RATING);
+    let last_pairs = previous.map(|g| squad_pairs(&g.squad_assignments)).unwrap_or_default();
+
+    // Shuffle from a fixed order so the seed alone decides ties, then snake-draft by rating.
+    people.sort();
+    random::Rng::new(seed).shuffle(&mut people);
+    people.sort_by(|a, b| rating(b).total_cmp(&rating(a)));
+
+    let mut squad_ids: Vec<String> = game.squads.keys().cloned().collect();
+    squad_ids.sort();
+    let n = squad_ids.len();
+    let mut squads: Vec<Vec<String>> = vec![Vec::new(); n];
+    for (i, p) in people.into_iter().enumerate() {
+        let pos = i % n;
+        let idx = if (i / n).is_multiple_of(2) { pos } else { n - 1 - pos };
+        squads[idx].push(p);
+    }
+
+    // Swap pairs across squads while that lowers the cost: the rating spread, plus a penalty per
+    // repeated pairing. Swaps keep squad sizes intact. Rating sums and the repeat count are kept
+    // up to date, so trying a swap only looks at the two squads in it.
+    let last: HashSet<(&str, &str)> = last_pairs.iter().map(|(a, b)| (a.as_str(), b.as_str())).collect();
+    // How many in `squad`, other than `p` and `skip`, played with `p` last time.
+    let partners = |squad: &[String], p: &str, skip: &str| {
+        squad
+            .iter()
+            .filter(|q| q.as_str() != p && q.as_str() != skip && last.contains(&(p.min(q), p.max(q))))
+            .count() as f64
+    };
+    let mut sums: Vec<f64> = squads.iter().map(|s| s.iter().map(|p| rating(p)).sum()).collect();
+    let mut repeats: f64 = squads.iter().map(|s| s.iter().map(|p| partners(s, p, p)).sum::<f64>() / 2.0).sum();
+    let mut cost = rating_spread(&sums, &squads) + REPEAT_PAIR_PENALTY * repeats;
+    for _ in 0..MAX_SWAP_ROUNDS {
+        let mut improved = false;
+        for a in 0..n {
+            for b in (a + 1)..n {
+                for i in 0..squads[a].len() {
+                    for j in 0..squads[b].len() {
+                        let (x, y) = (squads[a][i].as_str(), squads[b][j].as_str());
+                        let delta = rating(y) - rating(x);
+                        let swapped_repeats = repeats + partners(&squads[a], y, x) - partners(&squads[a], x, x)
+                            + partners(&squads[b], x, y)
+                            - partners(&squads[b], y, y);
+                        sums[a] += delta;
+                        sums[b] -= delta;
+                        let c = rating_spread(&sums, &squads) + REPEAT_PAIR_PENALTY * swapped_repeats;
+                        if c + 1e-9 < cost {
+                            cost = c;
+                            repeats = swapped_repeats;
+                            improved = true;
+                            let (left, right) = squads.split_at_mut(b);
+                            std::mem::swap(&mut left[a][i], &mut right[0][j]);
+                        } else {
+                            sums[a] -= delta;
+                            sums[b] += delta;
+                        }
+                    }
+                }
             }
         }
+        if !improved {
+            break;
+        }
+    }
+
+    game.squad_assignments = squad_ids
+        .into_iter()
+        .zip(squads)
+        .flat_map(|(sid, members)| members.into_iter().map(move |p| (p, sid.clone())))
+        .collect();
+    game.squad_revision += 1;
+    Ok(())
+}
+
+/// Any seed will do.
+impl Validate for AutoSquadsRequest {}
+
+/// Apply settings to a team. Check them with [`Validate`] first.
+pub fn apply_settings(team: &mut Team, settings: &SettingsRequest) {
+    if let Some(n) = &settings.name {
+        team.name = n.trim().to_string();
+    }
+    if let Some(loc) = &settings.location {
+        team.location = non_empty(loc);
+    }
+    if let Some(t) = &settings.time {
+        team.time = non_empty(t);
+    }
+    if let Some(w) = settings.weekly_schedule {
+        team.weekly_schedule = w;
+    }
+    if let Some(tz) = &settings.timezone {
+        team.timezone = non_empty(tz);
+    }
+    if let Some(open) = settings.results_open {
+        team.results_open = open;
+    }
+    if let Some(l) = settings.guest_limit {
+        team.guest_limit = l;
+    }
+    if let Some(h) = settings.rsvp_deadline_hours {
+        team.rsvp_deadline_hours = h;
+    }
+    if let Some(h) = settings.mvp_voting_hours {
+        team.mvp_voting_hours = h;
+    }
+}
+
+/// Longest team name, in characters.
+const MAX_TEAM_NAME_CHARS: usize = 60;
+
+impl Validate for NewTeamRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_text(&mut errors, "name", &self.name, MAX_TEAM_NAME_CHARS, "team name");
+        errors
+    }
+}
+
+impl Validate for SettingsRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if let Some(name) = &self.name {
+            check_text(&mut errors, "name", name, MAX_TEAM_NAME_CHARS, "team name");
+        }
+        if let Some(Some(w)) = self.weekly_schedule
+            && !(1..=7).contains(&w)
+        {
+            errors.push(FieldError::new("weekly_schedule", "must be 1 (Monday) to 7 (Sunday)"));
+        }
+        if let Some(Some(tz)) = &self.timezone
+            && !tz.is_empty()
+            && TimeZone::get(tz).is_err()
+        {
+            errors.push(FieldError::new("timezone", "unknown timezone"));
+        }
+        if let Some(Some(h)) = self.rsvp_deadline_hours
+            && u64::from(h) > MAX_RSVP_DEADLINE_HOURS
+        {
+            errors.push(FieldError::new("rsvp_deadline_hours", "must be at most 168"));
+        }
+        if let Some(Some(h)) = self.mvp_voting_hours
+            && !(1..=MAX_MVP_VOTING_HOURS).contains(&u64::from(h))
+        {
+            errors.push(FieldError::new("mvp_voting_hours", "must be between 1 and 168"));
+        }
+        errors
     }
-    if let Some(loc) = body.get("location") {
-        team.location = loc
-            .as_str()
-            .and_then(|s| if s.is_empty() { None } else { Some(s.to_string()) });
+}
+
+/// An optional text setting: blank or null clears it.
+fn non_empty(value: &Option<String>) -> Option<String> {
+    value.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string)
+}
+
+/// Check a text field that has to be filled in: `what` names it in the message.
+fn check_text(errors: &mut Vec<FieldError>, field: &str, value: &str, max: usize, what: &str) {
+    let value = value.trim();
+    if value.is_empty() {
+        errors.push(FieldError::new(field, format!("{} can't be empty", what)));
+    } else if value.chars().count() > max {
+        errors.push(FieldError::new(field, format!("{} is too long", what)));
     }
-    if let Some(t) = body.get("time") {
-        team.time = t
-            .as_str()
-            .and_then(|s| if s.is_empty() { None } else { Some(s.to_string()) });
+}
+
+/// Check a comma-separated list of names: at least one, each no longer than a roster name.
+fn check_names(errors: &mut Vec<FieldError>, field: &str, names: &str, what: &str) {
+    let names: Vec<&str> = names.split(',').map(str::trim).filter(|n| !n.is_empty()).collect();
+    if names.is_empty() {
+        errors.push(FieldError::new(field, format!("{} can't be empty", what)));
+    } else if names.iter().any(|n| n.chars().count() > MAX_NAME_CHARS) {
+        errors.push(FieldError::new(field, format!("{} is too long", what)));
     }
-    if let Some(w) = body.get("weekly_schedule") {
-        team.weekly_schedule = w
-            .as_i64()
-            .and_then(|n| if (1..=7).contains(&n) { Some(n as i8) } else { None });
+}
+
+/// Check squad names, by squad id.
+fn check_squad_names(errors: &mut Vec<FieldError>, field: &str, squads: &HashMap<SquadID, String>) {
+    for (sid, name) in squads {
+        if sid.is_empty() {
+            errors.push(FieldError::new(field, "squad id can't be empty"));
+        } else {
+            check_text(errors, &format!("{}.{}", field, sid), name, MAX_SQUAD_NAME_CHARS, "squad name");
+        }
     }
 }
 
 /// Add players from a comma-separated string. Returns Err if empty.
-pub fn add_players(team: &mut Team, names: &str) -> Result<(), &'static str> {
+pub fn add_players(team: &mut Team, names: &str) -> Result<(), ServiceError> {
     if names.is_empty() {
-        return Err("player names can't be empty");
+        return Err(ServiceError::EmptyPlayerNames);
     }
     names.trim().split(',').for_each(|n| {
         let n = n.trim();
         if !n.is_empty() {
             let pid = random::hex_string();
-            team.players.insert(pid, n.to_string());
+            team.players.insert(pid, Player::from(n));
         }
     });
     Ok(())
 }
 
-/// Remove a player from the team roster.
-pub fn delete_player(team: &mut Team, player_id: &str) {
-    team.players.remove(player_id);
+impl Validate for AddPlayersRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_names(&mut errors, "names", &self.names, "player name");
+        errors
+    }
 }
 
-/// Reset the game: clear next_game, return the old game key if any.
-pub fn reset_game(team: &mut Team) -> Option<String> {
-    team.next_game.take()
+/// Whether two names are the same person, ignoring case and surrounding space.
+pub fn same_name(a: &str, b: &str) -> bool {
+    a.trim().to_lowercase() == b.trim().to_lowercase()
 }
 
-/// Toggle is_game_off on a game.
-pub fn toggle_game_off(game: &mut Game) {
-    game.is_game_off = !game.is_game_off;
+/// Add a regular guest to the roster under a new player id. Err if the name is empty or a player
+/// already has it; use [`convert_guest`] on each game to carry their history over.
+pub fn promote_guest(team: &mut Team, name: &str) -> Result<PlayerID, ServiceError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ServiceError::EmptyGuestName);
+    }
+    if team.players.values().any(|p| same_name(&p.name, name)) {
+        return Err(ServiceError::NameTaken);
+    }
+    let pid = random::hex_string();
+    team.players.insert(pid.clone(), Player::from(name));
+    Ok(pid)
+}
+
+impl Validate for PromoteGuestRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_text(&mut errors, "name", &self.name, MAX_NAME_CHARS, "guest name");
+        errors
+    }
+}
+
+/// Turn a guest with this name into the given roster player for one game: they're marked as
+/// playing, and their squad, results, check-in and MVP votes move to the player id.
+/// Returns whether the game had such a guest.
+pub fn convert_guest(game: &mut Game, name: &str, player_id: &str) -> bool {
+    let idx = match game.guests.iter().position(|g| same_name(&g.name, name)) {
+        Some(idx) => idx,
+        None => return false,
+    };
+    let guest = game.guests.remove(idx);
+    let board_id = guest.board_id();
+
+    game.players.insert(
+        player_id.to_string(),
+        Some(Rsvp {
+            status: RsvpStatus::In,
+            note: None,
+        }),
+    );
+    if let Some(squad) = game.squad_assignments.remove(&board_id) {
+        game.squad_assignments.insert(player_id.to_string(), squad);
+    }
+    if let Some(stats) = game.player_stats.remove(&board_id) {
+        game.player_stats.insert(player_id.to_string(), stats);
+    }
+    if let Some(attended) = game.attendance.remove(&board_id) {
+        game.attendance.insert(player_id.to_string(), attended);
+    }
+    for candidate in game.mvp_votes.values_mut().filter(|c| **c == board_id) {
+        *candidate = player_id.to_string();
+    }
+    if let Some(winners) = &mut game.mvp {
+        for w in winners.iter_mut().filter(|w| **w == board_id) {
+            *w = player_id.to_string();
+        }
+    }
+    true
+}
+
+/// Fold the roster player `from` into `into`, for when someone was added twice. `into` keeps
+/// their own name, rating and profile, with gaps filled in from `from`. Notification settings go
+/// with the email address they belong to.
+/// Games are merged separately with [`merge_player_in_game`].
+pub fn merge_players(team: &mut Team, from: &str, into: &str) -> Result<(), ServiceError> {
+    if from == into {
+        return Err(ServiceError::MergeIntoSelf);
+    }
+    if !team.players.contains_key(into) {
+        return Err(ServiceError::PlayerNotFound);
+    }
+    let old = team.players.remove(from).ok_or(ServiceError::PlayerNotFound)?;
+    let player = team.players.get_mut(into).unwrap();
+    player.active |= old.active;
+    player.position = player.position.take().or(old.position);
+    player.shirt_number = player.shirt_number.or(old.shirt_number);
+    player.phone = player.phone.take().or(old.phone);
+    if player.email.is_none() {
+        player.email = old.email;
+        player.notifications = old.notifications;
+    }
+    player.notes = player.notes.take().or(old.notes);
+
+    if let Some(r) = team.ratings.remove(from) {
+        team.ratings.entry(into.to_string()).or_insert(r);
+    }
+    if let Some(old_subs) = team.push_subscriptions.remove(from) {
+        let subs = team.push_subscriptions.entry(into.to_string()).or_default();
+        for sub in old_subs {
+            if !subs.iter().any(|s| s.endpoint == sub.endpoint) {
+                subs.push(sub);
+            }
+        }
+        if subs.len() > MAX_PUSH_SUBSCRIPTIONS {
+            subs.drain(..subs.len() - MAX_PUSH_SUBSCRIPTIONS);
+        }
+    }
+    Ok(())
+}
+
+impl Validate for MergePlayerRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.into.is_empty() {
+            errors.push(FieldError::new("into", "player id can't be empty"));
+        }
+        errors
+    }
+}
+
+/// How strongly an RSVP says someone's coming, so merges keep the answer that plays.
+fn rsvp_rank(rsvp: &Option<Rsvp>) -> u8 {
+    match rsvp.as_ref().map(|r| r.status) {
+        Some(RsvpStatus::In) => 4,
+        Some(RsvpStatus::Late) => 3,
+        Some(RsvpStatus::Maybe) => 2,
+        Some(RsvpStatus::Out) => 1,
+        None => 0,
+    }
+}
+
+/// Move everything `from` did in one game over to `into`. When both answered, the answer that
+/// plays wins, and the squad goes with it; stats add up, having turned up wins, and `into` keeps
+/// their own vote. Returns whether the game mentioned `from` at all.
+pub fn merge_player_in_game(game: &mut Game, from: &str, into: &str) -> bool {
+    let mut changed = false;
+    let mut from_wins = false;
+    if let Some(rsvp) = game.players.remove(from) {
+        changed = true;
+        match game.players.get_mut(into) {
+            Some(existing) if rsvp_rank(existing) >= rsvp_rank(&rsvp) => {}
+            Some(existing) => {
+                *existing = rsvp;
+                from_wins = true;
+            }
+            None => {
+                game.players.insert(into.to_string(), rsvp);
+                from_wins = true;
+            }
+        }
+    }
+    if let Some(squad) = game.squad_assignments.remove(from) {
+        changed = true;
+        if from_wins {
+            game.squad_assignments.insert(into.to_string(), squad);
+        } else {
+            game.squad_assignments.entry(into.to_string()).or_insert(squad);
+        }
+    }
+    if let Some(stats) = game.player_stats.remove(from) {
+        changed = true;
+        let total = game.player_stats.entry(into.to_string()).or_default();
+        total.goals += stats.goals;
+        total.assists += stats.assists;
+    }
+    if let Some(delta) = game.rating_changes.remove(from) {
+        changed = true;
+        *game.rating_changes.entry(into.to_string()).or_insert(0.0) += delta;
+    }
+    if let Some(attended) = game.attendance.remove(from) {
+        changed = true;
+        *game.attendance.entry(into.to_string()).or_insert(attended) |= attended;
+    }
+    if let Some(vote) = game.mvp_votes.remove(from) {
+        changed = true;
+        game.mvp_votes.entry(into.to_string()).or_insert(vote);
+    }
+    for candidate in game.mvp_votes.values_mut().filter(|c| *c == from) {
+        changed = true;
+        *candidate = into.to_string();
+    }
+    // Nobody gets to vote for themselves, merged or not.
+    game.mvp_votes.retain(|voter, candidate| voter != candidate);
+    if let Some(winners) = &mut game.mvp
+        && winners.iter().any(|w| w == from)
+    {
+        changed = true;
+        winners.retain(|w| w != from && w != into);
+        winners.push(into.to_string());
+        winners.sort();
+    }
+    for guest in game.guests.iter_mut().filter(|g| g.invited_by.as_deref() == Some(from)) {
+        changed = true;
+        guest.invited_by = Some(into.to_string());
+    }
+    changed
+}
+
+/// Most join requests that can wait for an admin at once.
+const MAX_PENDING_JOIN_REQUESTS: usize = 20;
+
+/// Longest player, guest or join request name, in characters.
+const MAX_NAME_CHARS: usize = 40;
+
+/// Ask to join the roster. Err if the name is empty or too long, already taken or pending, or too
+/// many requests are already waiting.
+pub fn request_to_join(team: &mut Team, name: &str, now: Timestamp) -> Result<JoinRequest, ServiceError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ServiceError::EmptyName);
+    }
+    if name.chars().count() > MAX_NAME_CHARS {
+        return Err(ServiceError::NameTooLong);
+    }
+    if team.players.values().any(|p| same_name(&p.name, name)) {
+        return Err(ServiceError::NameTaken);
+    }
+    if team.join_requests.iter().any(|r| same_name(&r.name, name)) {
+        return Err(ServiceError::RequestWaiting);
+    }
+    if team.join_requests.len() >= MAX_PENDING_JOIN_REQUESTS {
+        return Err(ServiceError::TooManyRequests);
+    }
+    let request = JoinRequest {
+        id: random::hex_string(),
+        name: name.to_string(),
+        requested_at: now,
+    };
+    team.join_requests.push(request.clone());
+    Ok(request)
 }
 
-/// Set default squads on a team from a JSON object.
-pub fn set_default_squads(team: &mut Team, squads: &serde_json::Map<String, serde_json::Value>) {
-    team.default_squads = squads
+impl Validate for JoinTeamRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_text(&mut errors, "name", &self.name, MAX_NAME_CHARS, "name");
+        errors
+    }
+}
+
+/// Let a newcomer onto the roster. Returns their new player id.
+pub fn approve_join_request(team: &mut Team, request_id: &str) -> Result<PlayerID, ServiceError> {
+    let idx = team
+        .join_requests
         .iter()
-        .map(|(k, v)| (k.clone(), v.as_str().unwrap_or("").to_string()))
-        .collect();
+        .position(|r| r.id == request_id)
+        .ok_or(ServiceError::JoinRequestNotFound)?;
+    let request = team.join_requests.remove(idx);
+    let pid = random::hex_string();
+    team.players.insert(pid.clone(), Player::from(request.name.as_str()));
+    Ok(pid)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Turn a newcomer away.
+pub fn reject_join_request(team: &mut Team, request_id: &str) -> Result<(), ServiceError> {
+    let before = team.join_requests.len();
+    team.join_requests.retain(|r| r.id != request_id);
+    if team.join_requests.len() == before {
+        return Err(ServiceError::JoinRequestNotFound);
+    }
+    Ok(())
+}
 
-    fn make_team(name: &str) -> Team {
-        Team {
-            name: name.to_string(),
-            secret: "secret123".to_string(),
-            next_game: None,
-            players: HashMap::new(),
-            location: None,
-            time: None,
-            weekly_schedule: None,
-            default_squads: HashMap::new(),
+/// Update a roster player: rename them, (de)activate them, or change their profile. Check the
+/// update with [`Validate`] first.
+pub fn update_player(team: &mut Team, player_id: &str, update: &PlayerUpdate) -> Result<(), ServiceError> {
+    let player = team.players.get_mut(player_id).ok_or(ServiceError::PlayerNotFound)?;
+    apply_player_update(player, update);
+    Ok(())
+}
+
+/// Add a player with a profile. The update needs a name no one on the roster has yet.
+pub fn create_player(team: &mut Team, profile: &PlayerUpdate) -> Result<PlayerID, ServiceError> {
+    let name = profile.name.as_deref().map(str::trim).unwrap_or("");
+    if name.is_empty() {
+        return Err(ServiceError::EmptyPlayerName);
+    }
+    if team.players.values().any(|p| same_name(&p.name, name)) {
+        return Err(ServiceError::NameTaken);
+    }
+    let mut player = Player::from(name);
+    apply_player_update(&mut player, profile);
+    let pid = random::hex_string();
+    team.players.insert(pid.clone(), player);
+    Ok(pid)
+}
+
+/// The field handling behind [`update_player`], also used for imported rows.
+pub fn apply_player_update(player: &mut Player, update: &PlayerUpdate) {
+    if let Some(name) = &update.name {
+        player.name = name.trim().to_string();
+    }
+    if let Some(active) = update.active {
+        player.active = active;
+    }
+    if let Some(position) = &update.position {
+        player.position = non_empty(position);
+    }
+    if let Some(n) = update.shirt_number {
+        player.shirt_number = n.and_then(|n| u8::try_from(n).ok());
+    }
+    if let Some(phone) = &update.phone {
+        player.phone = non_empty(phone);
+    }
+    if let Some(email) = &update.email {
+        player.email = non_empty(email);
+    }
+    if let Some(notes) = &update.notes {
+        player.notes = non_empty(notes);
+    }
+}
+
+impl Validate for PlayerUpdate {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if let Some(name) = &self.name {
+            check_text(&mut errors, "name", name, MAX_NAME_CHARS, "player name");
         }
+        let too_long = |value: &Option<Option<String>>, max: usize| {
+            value.as_ref().and_then(|v| v.as_ref()).is_some_and(|v| v.trim().chars().count() > max)
+        };
+        if too_long(&self.position, MAX_POSITION_CHARS) {
+            errors.push(FieldError::new("position", "position is too long"));
+        }
+        if let Some(Some(n)) = self.shirt_number
+            && n > MAX_SHIRT_NUMBER
+        {
+            errors.push(FieldError::new("shirt_number", "shirt number must be between 0 and 99"));
+        }
+        if too_long(&self.phone, MAX_PHONE_CHARS) {
+            errors.push(FieldError::new("phone", "phone number is too long"));
+        }
+        if too_long(&self.email, MAX_EMAIL_CHARS) {
+            errors.push(FieldError::new("email", "email is too long"));
+        } else if non_empty(self.email.as_ref().unwrap_or(&None)).is_some_and(|e| !e.contains('@')) {
+            errors.push(FieldError::new("email", "invalid email"));
+        }
+        if too_long(&self.notes, MAX_PLAYER_NOTES_CHARS) {
+            errors.push(FieldError::new("notes", "notes are too long"));
+        }
+        errors
+    }
+}
+
+/// Longest profile fields, in characters.
+const MAX_POSITION_CHARS: usize = 30;
+const MAX_PHONE_CHARS: usize = 30;
+const MAX_EMAIL_CHARS: usize = 254;
+const MAX_PLAYER_NOTES_CHARS: usize = 500;
+
+const MAX_SHIRT_NUMBER: u16 = 99;
+
+/// Take an inactive player out of the current game if they hadn't answered yet.
+/// Returns whether the game changed.
+pub fn drop_unanswered(game: &mut Game, player_id: &str) -> bool {
+    if game.players.get(player_id) == Some(&None) {
+        game.players.remove(player_id);
+        true
+    } else {
+        false
+    }
+}
+
+/// Highest rating an admin can give.
+const MAX_RATING: f64 = 5000.0;
+
+impl Validate for RatingsRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors: Vec<FieldError> = self
+            .ratings
+            .iter()
+            .filter(|(_, r)| r.is_some_and(|r| !(0.0..=MAX_RATING).contains(&r)))
+            .map(|(pid, _)| FieldError::new(format!("ratings.{}", pid), "rating must be between 0 and 5000"))
+            .collect();
+        errors.sort_by(|a, b| a.field.cmp(&b.field));
+        errors
+    }
+}
+
+/// Set admin-assigned ratings. A null rating clears it; players not on the roster are ignored.
+pub fn set_ratings(team: &mut Team, ratings: &HashMap<PlayerID, Option<f64>>) {
+    for (pid, r) in ratings {
+        if !team.players.contains_key(pid) {
+            continue;
+        }
+        match r {
+            Some(r) => {
+                team.ratings.insert(pid.clone(), *r);
+            }
+            None => {
+                team.ratings.remove(pid);
+            }
+        }
+    }
+}
+
+/// How far a single game can move a rating.
+const RATING_K: f64 = 32.0;
+
+/// Highest score, goal or assist count a game can have.
+const MAX_SCORE: u32 = 99;
+
+/// Check scores by squad id.
+fn check_scores(errors: &mut Vec<FieldError>, scores: &HashMap<SquadID, u32>) {
+    let mut bad: Vec<&SquadID> = scores.iter().filter(|(_, s)| **s > MAX_SCORE).map(|(sid, _)| sid).collect();
+    bad.sort();
+    for sid in bad {
+        errors.push(FieldError::new(format!("scores.{}", sid), "score must be at most 99"));
+    }
+}
+
+impl Validate for ScoresRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_scores(&mut errors, &self.scores);
+        errors
+    }
+}
+
+/// Record the final score of each squad, replacing any earlier scores. An empty map clears them.
+pub fn record_scores(game: &mut Game, scores: &HashMap<SquadID, u32>) -> Result<(), ServiceError> {
+    if scores.keys().any(|sid| !game.squads.contains_key(sid)) {
+        return Err(ServiceError::SquadNotFound);
+    }
+    game.squad_scores = scores.clone();
+    Ok(())
+}
+
+/// Record a game's results: `scores` by squad, and `stats` with goals and assists by squad board
+/// id. Each part that's present replaces what was recorded before. Only people in a squad can
+/// have stats, and a squad's players can't have more goals or assists than it scored.
+/// Nothing changes on error.
+pub fn record_results(game: &mut Game, results: &ResultsRequest) -> Result<(), ServiceError> {
+    let mut updated = game.clone();
+    if let Some(scores) = &results.scores {
+        record_scores(&mut updated, scores)?;
+    }
+    if let Some(stats) = &results.stats {
+        if stats.keys().any(|p| !updated.squad_assignments.contains_key(p)) {
+            return Err(ServiceError::NotInSquad);
+        }
+        updated.player_stats = stats
+            .iter()
+            .filter(|(_, st)| **st != PlayerStats::default())
+            .map(|(p, st)| (p.clone(), st.clone()))
+            .collect();
+    }
+
+    for (sid, score) in &updated.squad_scores {
+        let squad_stats: Vec<&PlayerStats> = updated
+            .player_stats
+            .iter()
+            .filter(|(p, _)| updated.squad_assignments.get(*p) == Some(sid))
+            .map(|(_, st)| st)
+            .collect();
+        if squad_stats.iter().map(|st| st.goals).sum::<u32>() > *score {
+            return Err(ServiceError::TooManyGoals);
+        }
+        if squad_stats.iter().map(|st| st.assists).sum::<u32>() > *score {
+            return Err(ServiceError::TooManyAssists);
+        }
+    }
+    *game = updated;
+    Ok(())
+}
+
+impl Validate for ResultsRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if let Some(scores) = &self.scores {
+            check_scores(&mut errors, scores);
+        }
+        let mut stats: Vec<_> = self.stats.iter().flatten().collect();
+        stats.sort_by_key(|(id, _)| *id);
+        for (id, st) in stats {
+            if st.goals > MAX_SCORE {
+                errors.push(FieldError::new(format!("stats.{}.goals", id), "goals must be at most 99"));
+            }
+            if st.assists > MAX_SCORE {
+                errors.push(FieldError::new(format!("stats.{}.assists", id), "assists must be at most 99"));
+            }
+        }
+        errors
+    }
+}
+
+/// How long MVP voting stays open after kick-off, unless the team sets its own period.
+pub const DEFAULT_MVP_VOTING_HOURS: u32 = 24;
+
+/// The longest MVP voting period a team can set: a week.
+const MAX_MVP_VOTING_HOURS: u64 = 168;
+
+/// When MVP voting opens and closes: from kick-off, for the team's voting period.
+pub fn mvp_voting_window(team: &Team, game: &Game) -> Option<(Timestamp, Timestamp)> {
+    let opens = kickoff(team, game)?;
+    let hours = team.mvp_voting_hours.unwrap_or(DEFAULT_MVP_VOTING_HOURS);
+    let closes = opens.checked_add(i64::from(hours).hours()).ok()?;
+    Some((opens, closes))
+}
+
+/// Cast a player's MVP vote. Only players who played can vote, once each, and only for someone
+/// else who played, while voting is open.
+pub fn cast_mvp_vote(
+    team: &Team,
+    game: &mut Game,
+    voter: &str,
+    candidate: &str,
+    now: Timestamp,
+) -> Result<(), ServiceError> {
+    if game.is_game_off || game.mvp.is_some() {
+        return Err(ServiceError::VotingClosed);
+    }
+    match mvp_voting_window(team, game) {
+        Some((opens, closes)) if now >= opens && now < closes => {}
+        Some((opens, _)) if now < opens => return Err(ServiceError::VotingNotOpen),
+        Some(_) => return Err(ServiceError::VotingClosed),
+        None => return Err(ServiceError::NoKickoff),
+    }
+    if !is_playing(game, voter) {
+        return Err(ServiceError::NotAVoter);
+    }
+    if voter == candidate {
+        return Err(ServiceError::SelfVote);
+    }
+    if !confirmed_participants(game).iter().any(|p| p == candidate) {
+        return Err(ServiceError::CandidateDidntPlay);
+    }
+    if game.mvp_votes.contains_key(voter) {
+        return Err(ServiceError::AlreadyVoted);
+    }
+    game.mvp_votes.insert(voter.to_string(), candidate.to_string());
+    Ok(())
+}
+
+impl Validate for MvpVoteRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.candidate.is_empty() {
+            errors.push(FieldError::new("candidate", "candidate can't be empty"));
+        }
+        errors
+    }
+}
+
+/// Everyone tied for the most MVP votes, sorted.
+pub fn mvp_winners(game: &Game) -> Vec<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for candidate in game.mvp_votes.values() {
+        *counts.entry(candidate.as_str()).or_default() += 1;
+    }
+    let most = counts.values().copied().max().unwrap_or(0);
+    let mut winners: Vec<String> = counts
+        .into_iter()
+        .filter(|(_, n)| *n == most)
+        .map(|(c, _)| c.to_string())
+        .collect();
+    winners.sort();
+    winners
+}
+
+/// A game as anyone can see it: MVP ballots are replaced with a tally per candidate, and the
+/// rating changes it made are left out.
+pub fn public_game(mut game: Game) -> Game {
+    game.rating_changes.clear();
+    game.mvp_tally = HashMap::new();
+    for candidate in std::mem::take(&mut game.mvp_votes).into_values() {
+        *game.mvp_tally.entry(candidate).or_default() += 1;
+    }
+    game
+}
+
+/// Store the MVP result once voting has closed. Returns whether the game changed.
+pub fn close_mvp_voting(team: &Team, game: &mut Game, now: Timestamp) -> bool {
+    if game.mvp.is_some() || game.is_game_off {
+        return false;
+    }
+    match mvp_voting_window(team, game) {
+        Some((_, closes)) if now >= closes => {
+            game.mvp = Some(mvp_winners(game));
+            true
+        }
+        _ => false,
+    }
+}
+
+/// How a squad's score compares with the best of the other scored squads, if there are any.
+fn squad_outcome(game: &Game, squad: &str) -> Option<std::cmp::Ordering> {
+    let own = game.squad_scores.get(squad)?;
+    let best_other = game
+        .squad_scores
+        .iter()
+        .filter(|(sid, _)| *sid != squad)
+        .map(|(_, score)| *score)
+        .max()?;
+    Some(own.cmp(&best_other))
+}
+
+/// Season totals for roster players over the given games, best scorers first.
+/// With a season, only games dated in that year count. Games that were called off never count.
+pub fn leaderboard(team: &Team, games: &[Game], season: Option<i16>) -> Vec<LeaderboardEntry> {
+    let mut entries: HashMap<&str, LeaderboardEntry> = HashMap::new();
+    for game in games {
+        if game.is_game_off {
+            continue;
+        }
+        if let Some(year) = season
+            && game.date.map(|d| d.year()) != Some(year)
+        {
+            continue;
+        }
+        for (pid, player) in &team.players {
+            let played = is_playing(game, pid);
+            let stats = game.player_stats.get(pid);
+            if !played && stats.is_none() {
+                continue;
+            }
+            let e = entries.entry(pid).or_insert_with(|| LeaderboardEntry {
+                player_id: pid.clone(),
+                name: player.name.clone(),
+                played: 0,
+                goals: 0,
+                assists: 0,
+                wins: 0,
+                draws: 0,
+                losses: 0,
+            });
+            if played {
+                e.played += 1;
+            }
+            if let Some(st) = stats {
+                e.goals += st.goals;
+                e.assists += st.assists;
+            }
+            match game.squad_assignments.get(pid).and_then(|sid| squad_outcome(game, sid)) {
+                Some(std::cmp::Ordering::Greater) => e.wins += 1,
+                Some(std::cmp::Ordering::Equal) => e.draws += 1,
+                Some(std::cmp::Ordering::Less) => e.losses += 1,
+                None => {}
+            }
+        }
+    }
+    let mut entries: Vec<LeaderboardEntry> = entries.into_values().collect();
+    entries.sort_by(|a, b| {
+        (b.goals, b.assists, b.wins)
+            .cmp(&(a.goals, a.assists, a.wins))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    entries
+}
+
+/// Record who turned up, by squad board id: true if they came, false if not, null to clear.
+/// Anyone in the game can be checked in, whatever their RSVP. Nothing changes on error.
+pub fn check_in(game: &mut Game, attendance: &HashMap<String, Option<bool>>) -> Result<(), ServiceError> {
+    let mut updated = game.attendance.clone();
+    for (id, attended) in attendance {
+        if !in_game(game, id) {
+            return Err(ServiceError::PlayerNotFound);
+        }
+        match attended {
+            Some(b) => {
+                updated.insert(id.clone(), *b);
+            }
+            None => {
+                updated.remove(id);
+            }
+        }
+    }
+    game.attendance = updated;
+    Ok(())
+}
+
+/// Who the ids are is checked against the game, in [`check_in`].
+impl Validate for AttendanceRequest {}
+
+/// No-show rates for roster players, worst first. Only games with a check-in count, and anyone
+/// who said they'd play but wasn't checked in as attending is a no-show.
+pub fn no_show_rates(team: &Team, games: &[Game]) -> Vec<NoShowEntry> {
+    let mut entries: HashMap<&str, NoShowEntry> = HashMap::new();
+    for game in games.iter().filter(|g| !g.is_game_off && !g.attendance.is_empty()) {
+        for (pid, player) in &team.players {
+            if !is_playing(game, pid) {
+                continue;
+            }
+            let e = entries.entry(pid).or_insert_with(|| NoShowEntry {
+                player_id: pid.clone(),
+                name: player.name.clone(),
+                said_playing: 0,
+                no_shows: 0,
+                rate: 0.0,
+            });
+            e.said_playing += 1;
+            if game.attendance.get(pid) != Some(&true) {
+                e.no_shows += 1;
+            }
+        }
+    }
+    let mut entries: Vec<NoShowEntry> = entries
+        .into_values()
+        .map(|e| NoShowEntry {
+            rate: e.no_shows as f64 / e.said_playing as f64,
+            ..e
+        })
+        .collect();
+    entries.sort_by(|a, b| b.rate.total_cmp(&a.rate).then_with(|| a.name.cmp(&b.name)));
+    entries
+}
+
+/// Elo rating changes for the roster players in a game with recorded squad scores.
+/// Each pair of scored squads is one match between the squads' average ratings (guests count at
+/// the default rating), and a player's change is their squad's result averaged over its opponents.
+pub fn rating_changes(team: &Team, game: &Game) -> HashMap<PlayerID, f64> {
+    let rating = |p: &str| team.ratings.get(p).copied().unwrap_or(DEFAULT_RATING);
+
+    let mut members: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (p, sid) in &game.squad_assignments {
+        if game.squad_scores.contains_key(sid) {
+            members.entry(sid.as_str()).or_default().push(p.as_str());
+        }
+    }
+    if members.len() < 2 {
+        return HashMap::new();
+    }
+    let strength: HashMap<&str, f64> = members
+        .iter()
+        .map(|(sid, ps)| (*sid, ps.iter().map(|p| rating(p)).sum::<f64>() / ps.len() as f64))
+        .collect();
+
+    let mut changes = HashMap::new();
+    for (sid, ps) in &members {
+        let mut delta = 0.0;
+        for other in members.keys().filter(|o| *o != sid) {
+            let expected = 1.0 / (1.0 + 10f64.powf((strength[other] - strength[sid]) / 400.0));
+            let actual = match game.squad_scores[*sid].cmp(&game.squad_scores[*other]) {
+                std::cmp::Ordering::Greater => 1.0,
+                std::cmp::Ordering::Equal => 0.5,
+                std::cmp::Ordering::Less => 0.0,
+            };
+            delta += RATING_K * (actual - expected);
+        }
+        delta /= (members.len() - 1) as f64;
+        for p in ps.iter().filter(|p| team.players.contains_key(**p)) {
+            changes.insert(p.to_string(), delta);
+        }
+    }
+    changes
+}
+
+/// Update ratings from a game's scores, and note the changes on the game. Changes it applied
+/// before are undone first, so correcting a score doesn't count the game twice; a rating cleared
+/// since then is undone from the default. Players no longer on the roster are left out.
+pub fn apply_game_ratings(team: &mut Team, game: &mut Game) {
+    for (pid, d) in std::mem::take(&mut game.rating_changes) {
+        if team.players.contains_key(&pid) {
+            *team.ratings.entry(pid).or_insert(DEFAULT_RATING) -= d;
+        }
+    }
+    game.rating_changes = rating_changes(team, game);
+    for (pid, d) in &game.rating_changes {
+        *team.ratings.entry(pid.clone()).or_insert(DEFAULT_RATING) += d;
+    }
+}
+
+/// Remove a player from the team roster.
+pub fn delete_player(team: &mut Team, player_id: &str) {
+    team.players.remove(player_id);
+    team.ratings.remove(player_id);
+    team.push_subscriptions.remove(player_id);
+}
+
+/// Version of the [`TeamArchive`] format written by [`team_archive`].
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Most games an archive can bring in, about ten years of weekly games. Each one is a KV write,
+/// and a request only gets so many.
+pub const MAX_ARCHIVE_GAMES: usize = 520;
+
+/// Bundle a team with its games for export.
+pub fn team_archive(team: Team, games: HashMap<String, Game>, now: Timestamp) -> TeamArchive {
+    TeamArchive {
+        version: ARCHIVE_VERSION,
+        exported_at: now,
+        team,
+        games,
+    }
+}
+
+/// Rekey an archive for import as a new team: a fresh secret, and fresh keys for every game so it
+/// can sit alongside the original. Games the team doesn't refer to are dropped, and so are push
+/// subscriptions, which only work with the deployment that made them.
+pub fn restore_archive(archive: TeamArchive) -> Result<TeamArchive, ServiceError> {
+    if archive.version == 0 || archive.version > ARCHIVE_VERSION {
+        return Err(ServiceError::UnsupportedArchive);
+    }
+    let TeamArchive {
+        mut team,
+        mut games,
+        exported_at,
+        ..
+    } = archive;
+    if team.name.trim().is_empty() {
+        return Err(ServiceError::EmptyTeamName);
+    }
+    if games.len() > MAX_ARCHIVE_GAMES {
+        return Err(ServiceError::TooManyGames);
+    }
+
+    let mut new_keys: HashMap<String, String> = HashMap::new();
+    let mut restored = HashMap::new();
+    for old_key in team.past_games.iter().chain(team.next_game.iter()) {
+        if let Some(game) = games.remove(old_key) {
+            let key = random::hex_string();
+            new_keys.insert(old_key.clone(), key.clone());
+            restored.insert(key, game);
+        }
+    }
+    team.past_games = team.past_games.iter().filter_map(|k| new_keys.get(k).cloned()).collect();
+    team.next_game = team.next_game.as_ref().and_then(|k| new_keys.get(k).cloned());
+    team.secret = random::hex_string();
+    team.push_subscriptions.clear();
+    Ok(TeamArchive {
+        version: ARCHIVE_VERSION,
+        exported_at,
+        team,
+        games: restored,
+    })
+}
+
+/// Move the current game into the team's history, ahead of a new one replacing it.
+pub fn archive_game(team: &mut Team) {
+    if let Some(key) = team.next_game.take() {
+        team.past_games.push(key);
+    }
+}
+
+/// Reset the game: clear next_game, return the old game key if any.
+pub fn reset_game(team: &mut Team) -> Option<String> {
+    team.next_game.take()
+}
+
+/// Toggle is_game_off on a game.
+pub fn toggle_game_off(game: &mut Game) {
+    game.is_game_off = !game.is_game_off;
+}
+
+/// Replace a team's default squads.
+pub fn set_default_squads(team: &mut Team, squads: &HashMap<SquadID, String>) {
+    team.default_squads = squads.clone();
+}
+
+impl Validate for DefaultSquadsRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_squad_names(&mut errors, "squads", &self.squads);
+        errors
+    }
+}
+
+/// Set a player's email address and notifications. Check the request with [`Validate`] first.
+/// Returns Err if the player isn't on the roster.
+pub fn set_contact(team: &mut Team, player_id: &str, contact: &ContactRequest) -> Result<(), ServiceError> {
+    let player = team.players.get_mut(player_id).ok_or(ServiceError::PlayerNotFound)?;
+    player.email = Some(contact.email.trim().to_string());
+    player.notifications = contact.notifications;
+    Ok(())
+}
+
+impl Validate for ContactRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        let email = self.email.trim();
+        if email.chars().count() > MAX_EMAIL_CHARS {
+            errors.push(FieldError::new("email", "email is too long"));
+        } else if !email.contains('@') || email.starts_with('@') || email.ends_with('@') {
+            errors.push(FieldError::new("email", "invalid email"));
+        }
+        errors
+    }
+}
+
+/// Opt a player out of every notification. Their email address stays on their profile.
+pub fn remove_contact(team: &mut Team, player_id: &str) {
+    if let Some(player) = team.players.get_mut(player_id) {
+        player.notifications = Notifications::default();
+    }
+}
+
+/// Active players who want the notification `wants` picks out, with the address to send it to.
+fn notified<'a>(
+    team: &'a Team,
+    wants: impl Fn(&Notifications) -> bool + 'a,
+) -> impl Iterator<Item = (&'a PlayerID, &'a Player, &'a str)> + 'a {
+    team.players
+        .iter()
+        .filter(move |(_, p)| p.active && wants(&p.notifications))
+        .filter_map(|(pid, p)| Some((pid, p, p.email.as_deref()?)))
+}
+
+/// Hour of the day before the game, in the team's timezone, from which the reminder goes out.
+const REMINDER_LOCAL_HOUR: i8 = 18;
+
+/// Whether the reminder for this game should go out now: the evening before the game, in the
+/// team's timezone.
+pub fn reminder_due(team: &Team, game: &Game, now: Timestamp) -> bool {
+    if game.reminder_sent || game.is_game_off {
+        return false;
+    }
+    let local = now.to_zoned(team_timezone(team));
+    match game.date {
+        Some(d) => (d - local.date()).get_days() == 1 && local.hour() >= REMINDER_LOCAL_HOUR,
+        None => false,
+    }
+}
+
+/// Reminder emails for everyone opted in who hasn't said they're out.
+pub fn reminder_emails(team: &Team, game: &Game, link: &str) -> Vec<Email> {
+    notified(team, |n| n.reminders)
+        .filter(|(pid, _, _)| !has_declined(game, pid))
+        .map(|(_, p, email)| mail::reminder_email(email, &p.name, team, game, link))
+        .collect()
+}
+
+/// Game-off notices for everyone opted in.
+pub fn game_off_emails(team: &Team, game: &Game, link: &str) -> Vec<Email> {
+    notified(team, |n| n.game_off)
+        .map(|(_, p, email)| mail::game_off_email(email, &p.name, team, game, link))
+        .collect()
+}
+
+/// Confirmation for a player whose RSVP an organiser changed, if they opted in.
+pub fn rsvp_changed_email(team: &Team, game: &Game, player_id: &str, status: RsvpStatus, link: &str) -> Option<Email> {
+    let player = team.players.get(player_id).filter(|p| p.notifications.rsvp_changes)?;
+    let email = player.email.as_deref()?;
+    Some(mail::rsvp_changed_email(email, &player.name, team, game, status, link))
+}
+
+/// How long before kick-off the "starting soon" push goes out.
+const KICKOFF_PUSH_LEAD_MINUTES: i64 = 120;
+
+/// Most browsers a single player can subscribe.
+const MAX_PUSH_SUBSCRIPTIONS: usize = 5;
+
+/// When the game kicks off, if both the date and time are known.
+/// `team.time` is read as "HH:MM" in the team's timezone, or UTC if none is set.
+pub fn kickoff(team: &Team, game: &Game) -> Option<Timestamp> {
+    let date = game.date?;
+    let time: Time = team.time.as_deref()?.trim().parse().ok()?;
+    date.to_datetime(time).to_zoned(team_timezone(team)).ok().map(|z| z.timestamp())
+}
+
+/// The team's timezone, or UTC if none is set.
+pub fn team_timezone(team: &Team) -> TimeZone {
+    team.timezone.as_deref().and_then(|tz| TimeZone::get(tz).ok()).unwrap_or(TimeZone::UTC)
+}
+
+/// Whether the "starting soon" push for this game should go out now.
+pub fn kickoff_push_due(team: &Team, game: &Game, now: Timestamp) -> bool {
+    if game.kickoff_push_sent || game.is_game_off {
+        return false;
+    }
+    match kickoff(team, game) {
+        Some(k) => now < k && k.as_second() - now.as_second() <= KICKOFF_PUSH_LEAD_MINUTES * 60,
+        None => false,
+    }
+}
+
+/// Longest push endpoint URL, and push key, in characters.
+const MAX_PUSH_ENDPOINT_CHARS: usize = 1000;
+const MAX_PUSH_KEY_CHARS: usize = 200;
+
+fn check_endpoint(errors: &mut Vec<FieldError>, endpoint: &str) {
+    if !endpoint.starts_with("https://") {
+        errors.push(FieldError::new("endpoint", "endpoint must be an https URL"));
+    } else if endpoint.chars().count() > MAX_PUSH_ENDPOINT_CHARS {
+        errors.push(FieldError::new("endpoint", "endpoint is too long"));
+    }
+}
+
+impl Validate for PushSubscription {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_endpoint(&mut errors, &self.endpoint);
+        check_text(&mut errors, "keys.p256dh", &self.keys.p256dh, MAX_PUSH_KEY_CHARS, "key");
+        check_text(&mut errors, "keys.auth", &self.keys.auth, MAX_PUSH_KEY_CHARS, "key");
+        errors
+    }
+}
+
+impl Validate for RemovePushSubscriptionRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_endpoint(&mut errors, &self.endpoint);
+        errors
+    }
+}
+
+/// Store a browser push subscription for a player, replacing any with the same endpoint. Check
+/// the subscription with [`Validate`] first.
+pub fn add_push_subscription(team: &mut Team, player_id: &str, sub: PushSubscription) -> Result<(), ServiceError> {
+    if !team.players.contains_key(player_id) {
+        return Err(ServiceError::PlayerNotFound);
+    }
+    let subs = team.push_subscriptions.entry(player_id.to_string()).or_default();
+    subs.retain(|s| s.endpoint != sub.endpoint);
+    if subs.len() >= MAX_PUSH_SUBSCRIPTIONS {
+        subs.remove(0);
+    }
+    subs.push(sub);
+    Ok(())
+}
+
+/// Forget a player's push subscription by endpoint.
+pub fn remove_push_subscription(team: &mut Team, player_id: &str, endpoint: &str) {
+    if let Some(subs) = team.push_subscriptions.get_mut(player_id) {
+        subs.retain(|s| s.endpoint != endpoint);
+        if subs.is_empty() {
+            team.push_subscriptions.remove(player_id);
+        }
+    }
+}
+
+/// Every subscription to notify. With a game, players who said they're out are skipped.
+pub fn push_targets(team: &Team, game: Option<&Game>) -> Vec<(PlayerID, PushSubscription)> {
+    team.push_subscriptions
+        .iter()
+        .filter(|(pid, _)| team.players.get(*pid).is_some_and(|p| p.active))
+        .filter(|(pid, _)| game.is_none_or(|g| !has_declined(g, pid)))
+        .flat_map(|(pid, subs)| subs.iter().map(|s| (pid.clone(), s.clone())))
+        .collect()
+}
+
+/// Push shown when a new game opens for sign-up.
+pub fn new_game_push(team: &Team, game: &Game, link: &str) -> PushMessage {
+    PushMessage {
+        title: team.name.clone(),
+        body: format!("New game {}. Are you in?", mail::when(team, game)),
+        url: link.to_string(),
+    }
+}
+
+/// Push shown when the game is called off.
+pub fn game_off_push(team: &Team, game: &Game, link: &str) -> PushMessage {
+    PushMessage {
+        title: team.name.clone(),
+        body: format!("The game {} is off.", mail::when(team, game)),
+        url: link.to_string(),
+    }
+}
+
+/// Push shown shortly before kick-off.
+pub fn kickoff_push(team: &Team, game: &Game, link: &str) -> PushMessage {
+    let playing = rsvp_counts(game).playing;
+    PushMessage {
+        title: team.name.clone(),
+        body: format!("Kick-off soon, {} playing.", playing),
+        url: link.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_team(name: &str) -> Team {
+        Team {
+            schema_version: crate::migrate::TEAM_SCHEMA_VERSION,
+            name: name.to_string(),
+            secret: "secret123".to_string(),
+            next_game: None,
+            players: HashMap::new(),
+            location: None,
+            time: None,
+            weekly_schedule: None,
+            default_squads: HashMap::new(),
+            push_subscriptions: HashMap::new(),
+            timezone: None,
+            ratings: HashMap::new(),
+            past_games: Vec::new(),
+            results_open: false,
+            mvp_voting_hours: None,
+            rsvp_deadline_hours: None,
+            guest_limit: None,
+            join_requests: Vec::new(),
+        }
+    }
+
+    fn rsvp(status: RsvpStatus) -> Option<Rsvp> {
+        Some(Rsvp { status, note: None })
+    }
+
+    /// A request body the way a handler would get it, which must be valid.
+    fn body<T: serde::de::DeserializeOwned + Validate>(json: serde_json::Value) -> T {
+        crate::types::parse_request(&json.to_string()).unwrap()
+    }
+
+    fn body_errors<T: serde::de::DeserializeOwned + Validate + std::fmt::Debug>(json: serde_json::Value) -> Vec<FieldError> {
+        crate::types::parse_request::<T>(&json.to_string()).unwrap_err()
+    }
+
+    fn guest(id: &str) -> Guest {
+        Guest { id: id.into(), name: format!("Guest {}", id), invited_by: None }
+    }
+
+    fn make_game() -> Game {
+        Game {
+            schema_version: crate::migrate::GAME_SCHEMA_VERSION,
+            description: String::new(),
+            players: HashMap::new(),
+            guests: Vec::new(),
+            comments: Vec::new(),
+            date: None,
+            squads: HashMap::new(),
+            squad_assignments: HashMap::new(),
+            is_game_off: false,
+            reminder_sent: false,
+            kickoff_push_sent: false,
+            squad_revision: 0,
+            rating_changes: HashMap::new(),
+            squad_scores: HashMap::new(),
+            player_stats: HashMap::new(),
+            mvp_votes: HashMap::new(),
+            mvp_tally: HashMap::new(),
+            mvp: None,
+            attendance: HashMap::new(),
+        }
+    }
+
+    fn contact(email: &str) -> ContactRequest {
+        ContactRequest {
+            email: email.to_string(),
+            notifications: ALL_NOTIFICATIONS,
+        }
+    }
+
+    const ALL_NOTIFICATIONS: Notifications = Notifications {
+        reminders: true,
+        game_off: true,
+        rsvp_changes: true,
+    };
+
+    /// A player with an email address who wants every notification.
+    fn subscribed(name: &str, email: &str) -> Player {
+        Player {
+            email: Some(email.to_string()),
+            notifications: ALL_NOTIFICATIONS,
+            ..Player::from(name)
+        }
+    }
+
+    // --- team_response ---
+
+    #[test]
+    fn team_response_no_game() {
+        let mut team = make_team("FC Test");
+        team.location = Some("Field A".into());
+        let resp = team_response(&team, "abc123", None);
+        assert_eq!(resp.team_name, "FC Test");
+        assert_eq!(resp.team_key, "abc123");
+        assert_eq!(resp.location, Some("Field A".into()));
+        assert!(resp.game.is_none());
+    }
+
+    #[test]
+    fn team_response_with_game() {
+        let team = make_team("FC Test");
+        let game = make_game();
+        let resp = team_response(&team, "abc123", Some(game));
+        assert!(resp.game.is_some());
+    }
+
+    #[test]
+    fn team_response_includes_roster() {
+        let mut team = make_team("FC Test");
+        team.players.insert("p1".into(), "Alice".into());
+        let resp = team_response(&team, "k", None);
+        assert_eq!(resp.players.get("p1").unwrap(), "Alice");
+    }
+
+    // --- populate_unregistered_players ---
+
+    #[test]
+    fn populate_adds_missing_players() {
+        let mut team = make_team("T");
+        team.players.insert("p1".into(), "Alice".into());
+        team.players.insert("p2".into(), "Bob".into());
+
+        let mut game = make_game();
+        game.players.insert("p1".into(), rsvp(RsvpStatus::In));
+
+        let changed = populate_unregistered_players(&team, &mut game);
+        assert!(changed);
+        assert_eq!(game.players.len(), 2);
+        assert_eq!(game.players.get("p2"), Some(&None));
+    }
+
+    #[test]
+    fn populate_noop_when_all_present() {
+        let mut team = make_team("T");
+        team.players.insert("p1".into(), "Alice".into());
+
+        let mut game = make_game();
+        game.players.insert("p1".into(), rsvp(RsvpStatus::In));
+
+        let changed = populate_unregistered_players(&team, &mut game);
+        assert!(!changed);
+    }
+
+    #[test]
+    fn populate_handles_empty_team() {
+        let team = make_team("T");
+        let mut game = make_game();
+        let changed = populate_unregistered_players(&team, &mut game);
+        assert!(!changed);
+    }
+
+    // --- set_player_status ---
+
+    #[test]
+    fn set_play_new_player() {
+        let mut game = make_game();
+        set_player_status(&mut game, "p1", RsvpStatus::In, None).unwrap();
+        assert_eq!(game.players.get("p1"), Some(&rsvp(RsvpStatus::In)));
+    }
+
+    #[test]
+    fn set_not_play_overwrites() {
+        let mut game = make_game();
+        game.players.insert("p1".into(), rsvp(RsvpStatus::In));
+        set_player_status(&mut game, "p1", RsvpStatus::Out, None).unwrap();
+        assert_eq!(game.players.get("p1"), Some(&rsvp(RsvpStatus::Out)));
+    }
+
+    #[test]
+    fn set_player_status_notes() {
+        let mut game = make_game();
+        set_player_status(&mut game, "p1", RsvpStatus::Late, Some(" 15 min late ")).unwrap();
+        assert_eq!(game.players["p1"].as_ref().unwrap().note.as_deref(), Some("15 min late"));
+        // Without a note the old one stays; an empty one clears it.
+        set_player_status(&mut game, "p1", RsvpStatus::In, None).unwrap();
+        assert_eq!(game.players["p1"].as_ref().unwrap().note.as_deref(), Some("15 min late"));
+        set_player_status(&mut game, "p1", RsvpStatus::Maybe, Some("")).unwrap();
+        assert_eq!(game.players["p1"], rsvp(RsvpStatus::Maybe));
+        let long = "x".repeat(101);
+        assert_eq!(set_player_status(&mut game, "p1", RsvpStatus::In, Some(&long)), Err(ServiceError::NoteTooLong));
+        assert_eq!(game.players["p1"], rsvp(RsvpStatus::Maybe));
+    }
+
+    #[test]
+    fn rsvp_deadline_locks_changes() {
+        let mut team = make_team("T");
+        team.time = Some("19:00".into());
+        team.timezone = Some("Europe/London".into());
+        let mut game = make_game();
+        game.date = Some(jiff::civil::date(2026, 7, 1));
+        let before = "2026-07-01T11:59:00Z".parse().unwrap();
+        let after = "2026-07-01T12:00:00Z".parse().unwrap();
+
+        assert!(rsvp_deadline(&team, &game).is_none());
+        assert!(check_rsvp_open(&team, &game, after).is_ok());
+
+        // 19:00 BST is 18:00 UTC, so six hours before is 12:00 UTC.
+        team.rsvp_deadline_hours = Some(6);
+        assert_eq!(rsvp_deadline(&team, &game), Some(after));
+        assert!(check_rsvp_open(&team, &game, before).is_ok());
+        assert_eq!(
+            check_rsvp_open(&team, &game, after),
+            Err(ServiceError::RsvpClosed)
+        );
+
+        game.date = None;
+        assert!(check_rsvp_open(&team, &game, after).is_ok());
+    }
+
+    #[test]
+    fn rsvp_counts_tally() {
+        let mut game = make_game();
+        game.players.insert("a".into(), rsvp(RsvpStatus::In));
+        game.players.insert("b".into(), rsvp(RsvpStatus::Late));
+        game.players.insert("c".into(), rsvp(RsvpStatus::Maybe));
+        game.players.insert("d".into(), rsvp(RsvpStatus::Out));
+        game.players.insert("e".into(), None);
+        game.guests.push(guest("0"));
+        let counts = rsvp_counts(&game);
+        assert_eq!((counts.r#in, counts.late, counts.maybe, counts.out, counts.no_answer), (1, 1, 1, 1, 1));
+        assert_eq!(counts.playing, 3);
+        assert_eq!(confirmed_participants(&game).len(), 3);
+    }
+
+    // --- add_comment ---
+
+    #[test]
+    fn add_comment_ok() {
+        let mut game = make_game();
+        assert!(add_comment(&mut game, "Hello", None).is_ok());
+        assert_eq!(game.comments.len(), 1);
+        assert_eq!(game.comments[0], Comment { text: "Hello".into(), author: None, legacy: false });
+    }
+
+    #[test]
+    fn add_comment_with_author() {
+        let mut game = make_game();
+        assert!(add_comment(&mut game, "Hello", Some("Alice")).is_ok());
+        assert_eq!(game.comments[0], Comment { text: "Hello".into(), author: Some("Alice".into()), legacy: false });
+    }
+
+    #[test]
+    fn add_comment_empty_rejected() {
+        let mut game = make_game();
+        assert!(add_comment(&mut game, "", None).is_err());
+        assert!(game.comments.is_empty());
+    }
+
+    #[test]
+    fn add_comment_accumulates() {
+        let mut game = make_game();
+        add_comment(&mut game, "First", None).unwrap();
+        add_comment(&mut game, "Second", Some("Bob")).unwrap();
+        assert_eq!(game.comments.len(), 2);
+    }
+
+    // --- add_guests ---
+
+    fn guest_names(game: &Game) -> Vec<&str> {
+        game.guests.iter().map(|g| g.name.as_str()).collect()
+    }
+
+    #[test]
+    fn add_single_guest() {
+        let mut game = make_game();
+        assert!(add_guests(&make_team("T"), &mut game, "Charlie", None, None).is_ok());
+        assert_eq!(guest_names(&game), vec!["Charlie"]);
+        assert!(!game.guests[0].id.is_empty());
+    }
+
+    #[test]
+    fn add_comma_separated_guests() {
+        let mut game = make_game();
+        add_guests(&make_team("T"), &mut game, "Alice, Bob, Charlie", None, None).unwrap();
+        assert_eq!(guest_names(&game), vec!["Alice", "Bob", "Charlie"]);
+        assert_ne!(game.guests[0].id, game.guests[1].id);
+    }
+
+    #[test]
+    fn add_guests_empty_rejected() {
+        let mut game = make_game();
+        assert!(add_guests(&make_team("T"), &mut game, "", None, None).is_err());
+        assert!(add_guests(&make_team("T"), &mut game, " , ", None, None).is_err());
+    }
+
+    #[test]
+    fn add_guests_limit_per_player() {
+        let mut team = make_team("T");
+        team.players.insert("p1".into(), "Alice".into());
+        team.players.insert("p2".into(), "Bob".into());
+        let mut game = make_game();
+        add_guests(&team, &mut game, "A, B", Some("p1"), Some(2)).unwrap();
+        assert_eq!(game.guests[0].invited_by.as_deref(), Some("p1"));
+        assert_eq!(add_guests(&team, &mut game, "C", Some("p1"), Some(2)), Err(ServiceError::GuestLimitReached));
+        assert_eq!(add_guests(&team, &mut game, "C", Some("ghost"), None), Err(ServiceError::PlayerNotFound));
+        add_guests(&team, &mut game, "C, D", Some("p2"), Some(2)).unwrap();
+        // Guests nobody brought share one allowance.
+        add_guests(&team, &mut game, "E, F", None, Some(2)).unwrap();
+        assert_eq!(add_guests(&team, &mut game, "G", None, Some(2)), Err(ServiceError::GuestLimitReached));
+        // Admins add without a limit.
+        add_guests(&team, &mut game, "G", Some("p1"), None).unwrap();
+        assert_eq!(game.guests.len(), 7);
+    }
+
+    #[test]
+    fn delete_guest_by_id() {
+        let mut game = make_game();
+        game.guests = vec![guest("a"), guest("b"), guest("c")];
+        game.squad_assignments.insert("guest:b".into(), "s1".into());
+        game.squad_assignments.insert("guest:c".into(), "s1".into());
+        delete_guest(&mut game, "b");
+        assert_eq!(guest_names(&game), vec!["Guest a", "Guest c"]);
+        assert!(!game.squad_assignments.contains_key("guest:b"));
+        assert!(game.squad_assignments.contains_key("guest:c"));
+    }
+
+    #[test]
+    fn delete_unknown_guest_noop() {
+        let mut game = make_game();
+        game.guests = vec![guest("a")];
+        delete_guest(&mut game, "zzz");
+        assert_eq!(game.guests.len(), 1);
+    }
+
+    // --- promote guests ---
+
+    #[test]
+    fn promote_guest_adds_player() {
+        let mut team = make_team("T");
+        team.players.insert("p1".into(), "Alice".into());
+        assert_eq!(promote_guest(&mut team, " alice "), Err(ServiceError::NameTaken));
+        assert_eq!(promote_guest(&mut team, " "), Err(ServiceError::EmptyGuestName));
+        let pid = promote_guest(&mut team, " Dave ").unwrap();
+        assert_eq!(team.players[&pid].name, "Dave");
+    }
+
+    #[test]
+    fn convert_guest_carries_history() {
+        let mut game = game_with_squads();
+        game.guests = vec![guest("a"), Guest { id: "d".into(), name: "Dave".into(), invited_by: None }];
+        game.squad_assignments.insert("guest:d".into(), "s1".into());
+        game.player_stats.insert("guest:d".into(), PlayerStats { goals: 2, assists: 0 });
+        game.attendance.insert("guest:d".into(), true);
+        game.mvp_votes.insert("p1".into(), "guest:d".into());
+        game.mvp = Some(vec!["guest:d".into()]);
+
+        assert!(convert_guest(&mut game, "dave", "p9"));
+        assert_eq!(guest_names(&game), vec!["Guest a"]);
+        assert_eq!(game.players.get("p9"), Some(&rsvp(RsvpStatus::In)));
+        assert_eq!(game.squad_assignments.get("p9"), Some(&"s1".to_string()));
+        assert!(!game.squad_assignments.contains_key("guest:d"));
+        assert_eq!(game.player_stats["p9"].goals, 2);
+        assert_eq!(game.attendance.get("p9"), Some(&true));
+        assert_eq!(game.mvp_votes.get("p1"), Some(&"p9".to_string()));
+        assert_eq!(game.mvp, Some(vec!["p9".to_string()]));
+
+        assert!(!convert_guest(&mut game, "Dave", "p9"));
+    }
+
+    // --- has_game ---
+
+    #[test]
+    fn has_game_current_and_past_only() {
+        let mut team = make_team("T");
+        team.next_game = Some("g2".into());
+        team.past_games = vec!["g1".into()];
+        assert!(has_game(&team, "g1"));
+        assert!(has_game(&team, "g2"));
+        assert!(!has_game(&team, "g3"));
+    }
+
+    // --- request bodies ---
+
+    #[test]
+    fn parse_request_names_the_bad_field() {
+        let errors = crate::types::parse_request::<ResultsRequest>(r#"{"scores": {"s1": -1}}"#).unwrap_err();
+        assert_eq!(errors, vec![FieldError::new("scores.s1", "invalid value: integer `-1`, expected u32")]);
+
+        let errors = body_errors::<RsvpRequest>(serde_json::json!({"status": "sometimes", "note": 5}));
+        assert_eq!(
+            errors,
+            vec![
+                FieldError::new("note", "invalid type: integer `5`, expected a string"),
+                FieldError::new("status", "unknown variant `sometimes`, expected one of `in`, `out`, `maybe`, `late`"),
+            ]
+        );
+
+        let errors = body_errors::<ResultsRequest>(serde_json::json!({"scores": {"s1": -1, "s2": "x"}, "stats": 1}));
+        assert_eq!(errors.len(), 3);
+
+        let errors = crate::types::parse_request::<MvpVoteRequest>("{}").unwrap_err();
+        assert_eq!(errors, vec![FieldError::new("", "missing field `candidate`")]);
+
+        let errors = crate::types::parse_request::<NewTeamRequest>("{not json").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "");
+    }
+
+    #[test]
+    fn text_fields_are_checked() {
+        assert_eq!(
+            body_errors::<CommentRequest>(serde_json::json!({"comment": " ", "author": "x".repeat(41)})),
+            vec![FieldError::new("comment", "comment can't be empty"), FieldError::new("author", "author is too long")]
+        );
+        assert_eq!(
+            body_errors::<AddGuestRequest>(serde_json::json!({"guest_name": " , "})),
+            vec![FieldError::new("guest_name", "guest name can't be empty")]
+        );
+        assert_eq!(
+            body_errors::<AddPlayersRequest>(serde_json::json!({"names": format!("Ann, {}", "x".repeat(41))})),
+            vec![FieldError::new("names", "player name is too long")]
+        );
+        assert_eq!(
+            body_errors::<NewTeamRequest>(serde_json::json!({"name": "x".repeat(61)})),
+            vec![FieldError::new("name", "team name is too long")]
+        );
+        assert_eq!(
+            body_errors::<SquadsRequest>(serde_json::json!({"squads": {"s1": ""}})),
+            vec![FieldError::new("squads.s1", "squad name can't be empty")]
+        );
+        assert_eq!(
+            body_errors::<ResultsRequest>(serde_json::json!({"scores": {"s1": 100}, "stats": {"p1": {"goals": 100}}})),
+            vec![
+                FieldError::new("scores.s1", "score must be at most 99"),
+                FieldError::new("stats.p1.goals", "goals must be at most 99"),
+            ]
+        );
+        let ok: CommentRequest = body(serde_json::json!({"comment": "see you there", "author": "Alice"}));
+        assert!(ok.validate().is_empty());
+    }
+
+    #[test]
+    fn parse_request_empty_body_uses_defaults() {
+        let req: AutoSquadsRequest = crate::types::parse_request("").unwrap();
+        assert_eq!(req.seed, None);
+        let req: SettingsRequest = crate::types::parse_request(r#"{"location": null}"#).unwrap();
+        assert_eq!(req.location, Some(None));
+        assert_eq!(req.time, None);
+    }
+
+    // --- update players ---
+
+    #[test]
+    fn update_player_renames_and_deactivates() {
+        let mut team = make_team("T");
+        team.players.insert("p1".into(), "Jonh".into());
+        update_player(&mut team, "p1", &body(serde_json::json!({"name": " John "}))).unwrap();
+        assert_eq!(team.players["p1"], Player::from("John"));
+        update_player(&mut team, "p1", &body(serde_json::json!({"active": false}))).unwrap();
+        assert!(!team.players["p1"].active);
+
+        assert_eq!(update_player(&mut team, "nope", &PlayerUpdate::default()), Err(ServiceError::PlayerNotFound));
+        assert_eq!(
+            body_errors::<PlayerUpdate>(serde_json::json!({"name": "", "active": true})),
+            vec![FieldError::new("name", "player name can't be empty")]
+        );
+        assert_eq!(
+            body_errors::<PlayerUpdate>(serde_json::json!({"active": "yes"})),
+            vec![FieldError::new("active", "invalid type: string \"yes\", expected a boolean")]
+        );
+    }
+
+    #[test]
+    fn update_player_profile() {
+        let mut team = make_team("T");
+        team.players.insert("p1".into(), "Alice".into());
+        let update = body(serde_json::json!({
+            "position": " Keeper ",
+            "shirt_number": 1,
+            "phone": "+44 7700 900000",
+            "email": "alice@example.com",
+            "notes": "Bad knee"
+        }));
+        update_player(&mut team, "p1", &update).unwrap();
+        let p = &team.players["p1"];
+        assert_eq!(p.position.as_deref(), Some("Keeper"));
+        assert_eq!(p.shirt_number, Some(1));
+        assert_eq!(p.email.as_deref(), Some("alice@example.com"));
+
+        update_player(&mut team, "p1", &body(serde_json::json!({"position": "", "shirt_number": null}))).unwrap();
+        assert_eq!(team.players["p1"].position, None);
+        assert_eq!(team.players["p1"].shirt_number, None);
+
+        assert_eq!(
+            body_errors::<PlayerUpdate>(serde_json::json!({"shirt_number": 100, "email": "alice"})),
+            vec![
+                FieldError::new("shirt_number", "shirt number must be between 0 and 99"),
+                FieldError::new("email", "invalid email"),
+            ]
+        );
+    }
+
+    #[test]
+    fn create_player_with_profile() {
+        let mut team = make_team("T");
+        team.players.insert("p1".into(), "Alice".into());
+        let pid = create_player(&mut team, &body(serde_json::json!({"name": " Bob ", "shirt_number": 9}))).unwrap();
+        assert_eq!(team.players[&pid], Player { shirt_number: Some(9), ..Player::from("Bob") });
+
+        assert_eq!(
+            create_player(&mut team, &body(serde_json::json!({"name": "alice"}))),
+            Err(ServiceError::NameTaken)
+        );
+        assert_eq!(create_player(&mut team, &PlayerUpdate::default()), Err(ServiceError::EmptyPlayerName));
+        assert_eq!(team.players.len(), 2);
+    }
+
+    #[test]
+    fn contact_details_only_for_admins() {
+        let mut team = make_team("T");
+        team.players.insert(
+            "p1".into(),
+            Player {
+                position: Some("Keeper".into()),
+                shirt_number: Some(1),
+                phone: Some("123".into()),
+                email: Some("alice@example.com".into()),
+                ..Player::from("Alice")
+            },
+        );
+        team.players.insert("p2".into(), "Bob".into());
+
+        let public = team_response(&team, "k", None);
+        assert_eq!(public.player_details, None);
+        assert_eq!(public.profiles.len(), 1);
+        assert_eq!(public.profiles["p1"].shirt_number, Some(1));
+        let json = serde_json::to_string(&public).unwrap();
+        assert!(!json.contains("alice@example.com"));
+        assert!(!json.contains("123"));
+
+        let admin = admin_team_response(&team, "k", None);
+        assert_eq!(admin.player_details.unwrap()["p1"].phone.as_deref(), Some("123"));
+    }
+
+    #[test]
+    fn inactive_players_kept_out_of_games() {
+        let mut team = make_team("T");
+        team.players.insert("p1".into(), "Alice".into());
+        team.players.insert("p2".into(), Player { active: false, ..Player::from("Bob") });
+        let mut game = make_game();
+        populate_unregistered_players(&team, &mut game);
+        assert!(game.players.contains_key("p1"));
+        assert!(!game.players.contains_key("p2"));
+
+        let resp = team_response(&team, "k", None);
+        assert_eq!(resp.players.get("p2"), Some(&"Bob".to_string()));
+        assert_eq!(resp.inactive_players, vec!["p2".to_string()]);
+    }
+
+    #[test]
+    fn drop_unanswered_keeps_answers() {
+        let mut game = make_game();
+        game.players.insert("p1".into(), None);
+        game.players.insert("p2".into(), rsvp(RsvpStatus::In));
+        assert!(drop_unanswered(&mut game, "p1"));
+        assert!(!drop_unanswered(&mut game, "p2"));
+        assert!(game.players.contains_key("p2"));
+    }
+
+    // --- archives ---
+
+    #[test]
+    fn restore_archive_rekeys_games() {
+        let mut team = make_team("T");
+        team.players.insert("p1".into(), "Alice".into());
+        team.past_games = vec!["old".into(), "gone".into()];
+        team.next_game = Some("cur".into());
+        team.push_subscriptions.insert("p1".into(), vec![subscription("https://push.example/1")]);
+        let mut old = make_game();
+        old.description = "old".into();
+        old.rating_changes.insert("p1".into(), 5.0);
+        let mut cur = make_game();
+        cur.description = "cur".into();
+        let games = HashMap::from([
+            ("old".to_string(), old),
+            ("cur".to_string(), cur),
+            ("stray".to_string(), make_game()),
+        ]);
+
+        let archive = team_archive(team, games, at("2026-01-01T00:00:00Z"));
+        let json = serde_json::to_string(&archive).unwrap();
+        let TeamArchive { team, games, .. } = restore_archive(serde_json::from_str(&json).unwrap()).unwrap();
+
+        assert_eq!(games.len(), 2);
+        assert_eq!(team.past_games.len(), 1);
+        let old_key = &team.past_games[0];
+        assert_ne!(old_key, "old");
+        assert_eq!(games[old_key].description, "old");
+        assert_eq!(games[team.next_game.as_ref().unwrap()].description, "cur");
+        assert_eq!(games[old_key].rating_changes["p1"], 5.0);
+        assert_ne!(team.secret, "secret123");
+        assert!(team.push_subscriptions.is_empty());
+        assert_eq!(team.players["p1"].name, "Alice");
+    }
+
+    #[test]
+    fn restore_archive_limits_games() {
+        let games = (0..=MAX_ARCHIVE_GAMES).map(|i| (i.to_string(), make_game())).collect();
+        let archive = team_archive(make_team("T"), games, at("2026-01-01T00:00:00Z"));
+        assert_eq!(restore_archive(archive).err(), Some(ServiceError::TooManyGames));
+    }
+
+    #[test]
+    fn restore_archive_checks_version() {
+        let mut archive = team_archive(make_team("T"), HashMap::new(), at("2026-01-01T00:00:00Z"));
+        archive.version = ARCHIVE_VERSION + 1;
+        assert_eq!(restore_archive(archive).err(), Some(ServiceError::UnsupportedArchive));
+    }
+
+    // --- merge players ---
+
+    #[test]
+    fn merge_players_folds_roster_entry() {
+        let mut team = make_team("T");
+        team.players.insert("jon".into(), Player { shirt_number: Some(7), ..Player::from("Jon") });
+        team.players.insert("jonathan".into(), Player { active: false, ..Player::from("Jonathan") });
+        team.ratings.insert("jon".into(), 1100.0);
+        team.players.get_mut("jon").unwrap().email = Some("jon@example.com".into());
+        team.players.get_mut("jon").unwrap().notifications = ALL_NOTIFICATIONS;
+
+        merge_players(&mut team, "jon", "jonathan").unwrap();
+        assert!(!team.players.contains_key("jon"));
+        let p = &team.players["jonathan"];
+        assert_eq!(p.name, "Jonathan");
+        assert!(p.active);
+        assert_eq!(p.shirt_number, Some(7));
+        assert_eq!(team.ratings["jonathan"], 1100.0);
+        assert_eq!(p.email.as_deref(), Some("jon@example.com"));
+        assert_eq!(p.notifications, ALL_NOTIFICATIONS);
+
+        assert_eq!(merge_players(&mut team, "jonathan", "jonathan"), Err(ServiceError::MergeIntoSelf));
+        assert_eq!(merge_players(&mut team, "jon", "jonathan"), Err(ServiceError::PlayerNotFound));
+        assert_eq!(merge_players(&mut team, "jonathan", "nope"), Err(ServiceError::PlayerNotFound));
+        assert!(team.players.contains_key("jonathan"));
+    }
+
+    #[test]
+    fn merge_in_game_playing_wins() {
+        let mut game = make_game();
+        game.players.insert("jon".into(), rsvp(RsvpStatus::In));
+        game.players.insert("jonathan".into(), rsvp(RsvpStatus::Out));
+        assert!(merge_player_in_game(&mut game, "jon", "jonathan"));
+        assert_eq!(game.players.len(), 1);
+        assert_eq!(game.players["jonathan"], rsvp(RsvpStatus::In));
+
+        let mut game = make_game();
+        game.players.insert("jon".into(), None);
+        game.players.insert("jonathan".into(), rsvp(RsvpStatus::Late));
+        merge_player_in_game(&mut game, "jon", "jonathan");
+        assert_eq!(game.players["jonathan"], rsvp(RsvpStatus::Late));
+
+        let mut game = make_game();
+        game.players.insert("other".into(), None);
+        assert!(!merge_player_in_game(&mut game, "jon", "jonathan"));
+    }
+
+    #[test]
+    fn merge_in_game_squad_follows_rsvp() {
+        let mut game = make_game();
+        game.players.insert("jon".into(), rsvp(RsvpStatus::In));
+        game.players.insert("jonathan".into(), rsvp(RsvpStatus::Out));
+        game.squad_assignments.insert("jon".into(), "blue".into());
+        game.squad_assignments.insert("jonathan".into(), "red".into());
+        merge_player_in_game(&mut game, "jon", "jonathan");
+        assert_eq!(game.squad_assignments.get("jonathan"), Some(&"blue".to_string()));
+
+        let mut game = make_game();
+        game.players.insert("jon".into(), rsvp(RsvpStatus::Maybe));
+        game.players.insert("jonathan".into(), rsvp(RsvpStatus::In));
+        game.squad_assignments.insert("jon".into(), "blue".into());
+        game.squad_assignments.insert("jonathan".into(), "red".into());
+        merge_player_in_game(&mut game, "jon", "jonathan");
+        assert_eq!(game.squad_assignments.get("jonathan"), Some(&"red".to_string()));
+        assert_eq!(game.squad_assignments.len(), 1);
+    }
+
+    #[test]
+    fn merge_in_game_moves_history() {
+        let mut game = make_game();
+        game.players.insert("jon".into(), rsvp(RsvpStatus::In));
+        game.players.insert("jonathan".into(), None);
+        game.squad_assignments.insert("jon".into(), "blue".into());
+        game.player_stats.insert("jon".into(), PlayerStats { goals: 2, assists: 0 });
+        game.player_stats.insert("jonathan".into(), PlayerStats { goals: 1, assists: 1 });
+        game.attendance.insert("jon".into(), true);
+        game.attendance.insert("jonathan".into(), false);
+        game.rating_changes.insert("jon".into(), 12.0);
+        game.mvp_votes.insert("p3".into(), "jon".into());
+        game.mvp_votes.insert("jonathan".into(), "jon".into());
+        game.mvp = Some(vec!["jon".into()]);
+        game.guests.push(Guest { invited_by: Some("jon".into()), ..guest("0") });
+
+        merge_player_in_game(&mut game, "jon", "jonathan");
+        assert_eq!(game.squad_assignments["jonathan"], "blue");
+        assert_eq!(game.player_stats["jonathan"], PlayerStats { goals: 3, assists: 1 });
+        assert!(game.attendance["jonathan"]);
+        assert_eq!(game.rating_changes["jonathan"], 12.0);
+        assert_eq!(game.mvp_votes.get("p3").map(String::as_str), Some("jonathan"));
+        assert!(!game.mvp_votes.contains_key("jonathan"));
+        assert_eq!(game.mvp, Some(vec!["jonathan".to_string()]));
+        assert_eq!(game.guests[0].invited_by.as_deref(), Some("jonathan"));
+        assert!(!game.players.contains_key("jon"));
+    }
+
+    // --- join requests ---
+
+    #[test]
+    fn join_request_approve_and_reject() {
+        let mut team = make_team("T");
+        let now: Timestamp = "2026-03-01T10:00:00Z".parse().unwrap();
+        let eve = request_to_join(&mut team, " Eve ", now).unwrap();
+        assert_eq!(eve.name, "Eve");
+        let mal = request_to_join(&mut team, "Mal", now).unwrap();
+
+        let pid = approve_join_request(&mut team, &eve.id).unwrap();
+        assert_eq!(team.players[&pid].name, "Eve");
+        reject_join_request(&mut team, &mal.id).unwrap();
+        assert!(team.join_requests.is_empty());
+        assert!(!team.players.values().any(|p| p.name == "Mal"));
+
+        assert_eq!(approve_join_request(&mut team, &eve.id), Err(ServiceError::JoinRequestNotFound));
+        assert_eq!(reject_join_request(&mut team, "nope"), Err(ServiceError::JoinRequestNotFound));
+    }
+
+    #[test]
+    fn join_request_limits() {
+        let mut team = make_team("T");
+        team.players.insert("p1".into(), "Alice".into());
+        let now: Timestamp = "2026-03-01T10:00:00Z".parse().unwrap();
+        assert_eq!(request_to_join(&mut team, "  ", now), Err(ServiceError::EmptyName));
+        assert_eq!(request_to_join(&mut team, &"x".repeat(41), now), Err(ServiceError::NameTooLong));
+        assert_eq!(
+            request_to_join(&mut team, "ALICE", now),
+            Err(ServiceError::NameTaken)
+        );
+        request_to_join(&mut team, "Bob", now).unwrap();
+        assert_eq!(request_to_join(&mut team, "bob", now), Err(ServiceError::RequestWaiting));
+        for i in 1..20 {
+            request_to_join(&mut team, &format!("N{}", i), now).unwrap();
+        }
+        assert_eq!(
+            request_to_join(&mut team, "One more", now),
+            Err(ServiceError::TooManyRequests)
+        );
+    }
+
+    // --- save_squad_assignments ---
+
+    #[test]
+    fn save_squads_replaces_all() {
+        let mut game = make_game();
+        game.squad_assignments.insert("p1".into(), "old".into());
+
+        let mut new = HashMap::new();
+        new.insert("p2".into(), "squad1".into());
+        save_squad_assignments(&mut game, new);
+
+        assert!(!game.squad_assignments.contains_key("p1"));
+        assert_eq!(game.squad_assignments.get("p2"), Some(&"squad1".into()));
+    }
+
+    // --- apply_squad_op ---
+
+    fn game_with_squads() -> Game {
+        let mut game = make_game();
+        game.players.insert("p1".into(), rsvp(RsvpStatus::In));
+        game.guests.push(guest("0"));
+        game.squads.insert("s1".into(), "Blue".into());
+        game.squads.insert("s2".into(), "Red".into());
+        game
+    }
+
+    #[test]
+    fn squad_op_assign_and_unassign() {
+        let mut game = game_with_squads();
+        let op = SquadOp::Assign { player: "p1".into(), squad: "s1".into() };
+        apply_squad_op(&mut game, &op).unwrap();
+        let op = SquadOp::Assign { player: "guest:0".into(), squad: "s2".into() };
+        apply_squad_op(&mut game, &op).unwrap();
+        assert_eq!(game.squad_assignments.get("p1"), Some(&"s1".into()));
+
+        apply_squad_op(&mut game, &SquadOp::Unassign { player: "p1".into() }).unwrap();
+        assert!(!game.squad_assignments.contains_key("p1"));
+        assert_eq!(game.squad_assignments.len(), 1);
+    }
+
+    #[test]
+    fn squad_op_assign_unknown_squad_rejected() {
+        let mut game = game_with_squads();
+        let op = SquadOp::Assign { player: "p1".into(), squad: "nope".into() };
+        assert!(apply_squad_op(&mut game, &op).is_err());
+        assert!(game.squad_assignments.is_empty());
+    }
+
+    #[test]
+    fn squad_op_assign_unknown_player_rejected() {
+        let mut game = game_with_squads();
+        let op = SquadOp::Assign { player: "p9".into(), squad: "s1".into() };
+        assert_eq!(apply_squad_op(&mut game, &op), Err(ServiceError::PlayerNotFound));
+        let op = SquadOp::Assign { player: "guest:9".into(), squad: "s1".into() };
+        assert_eq!(apply_squad_op(&mut game, &op), Err(ServiceError::PlayerNotFound));
+        assert!(game.squad_assignments.is_empty());
+        assert_eq!(game.squad_revision, 0);
+    }
+
+    #[test]
+    fn merge_squad_board_keeps_missed_ops() {
+        let read = game_with_squads();
+        let mut known = read.clone();
+        let op = SquadOp::Assign { player: "guest:0".into(), squad: "s1".into() };
+        apply_squad_op(&mut known, &op).unwrap();
+        let op = SquadOp::Assign { player: "p1".into(), squad: "s2".into() };
+        apply_squad_op(&mut known, &op).unwrap();
+
+        // A handler that read the game before the ops removes the guest and publishes.
+        let mut published = read.clone();
+        delete_guest(&mut published, "0");
+        assert!(merge_squad_board(&mut published, &known));
+        assert_eq!(published.squad_assignments, HashMap::from([("p1".into(), "s2".into())]));
+        assert_eq!(published.squad_revision, 2);
+        assert!(published.guests.is_empty());
+
+        // A save made after the ops wins.
+        let mut saved = known.clone();
+        save_squad_assignments(&mut saved, HashMap::new());
+        saved.squad_revision += 1;
+        assert!(!merge_squad_board(&mut saved, &known));
+        assert!(saved.squad_assignments.is_empty());
+    }
+
+    #[test]
+    fn squad_op_add_and_rename() {
+        let mut game = game_with_squads();
+        let op = SquadOp::AddSquad { squad: "s3".into(), name: " Green ".into() };
+        apply_squad_op(&mut game, &op).unwrap();
+        assert_eq!(game.squads.get("s3"), Some(&"Green".into()));
+        assert!(apply_squad_op(&mut game, &op).is_err());
+
+        let op = SquadOp::RenameSquad { squad: "s3".into(), name: "Lime".into() };
+        apply_squad_op(&mut game, &op).unwrap();
+        assert_eq!(game.squads.get("s3"), Some(&"Lime".into()));
+        let op = SquadOp::RenameSquad { squad: "s9".into(), name: "X".into() };
+        assert!(apply_squad_op(&mut game, &op).is_err());
+        let op = SquadOp::RenameSquad { squad: "s3".into(), name: " ".into() };
+        assert!(apply_squad_op(&mut game, &op).is_err());
+    }
+
+    #[test]
+    fn squad_op_remove_unassigns_members() {
+        let mut game = game_with_squads();
+        game.squad_assignments.insert("p1".into(), "s1".into());
+        game.squad_assignments.insert("p2".into(), "s2".into());
+        apply_squad_op(&mut game, &SquadOp::RemoveSquad { squad: "s1".into() }).unwrap();
+        assert!(!game.squads.contains_key("s1"));
+        assert!(!game.squad_assignments.contains_key("p1"));
+        assert!(game.squad_assignments.contains_key("p2"));
+        assert!(apply_squad_op(&mut game, &SquadOp::RemoveSquad { squad: "s1".into() }).is_err());
+    }
+
+    #[test]
+    fn squad_op_json_shape() {
+        let op: SquadOp = serde_json::from_str(r#"{"op":"assign","player":"p1","squad":"s1"}"#).unwrap();
+        assert_eq!(op, SquadOp::Assign { player: "p1".into(), squad: "s1".into() });
+        let op: SquadOp = serde_json::from_str(r#"{"op":"remove_squad","squad":"s1"}"#).unwrap();
+        assert_eq!(op, SquadOp::RemoveSquad { squad: "s1".into() });
     }
 
-    fn make_game() -> Game {
-        Game {
-            description: String::new(),
-            players: HashMap::new(),
-            guests: Vec::new(),
-            comments: Vec::new(),
-            date: None,
-            squads: HashMap::new(),
-            squad_assignments: HashMap::new(),
-            is_game_off: false,
+    // --- auto_squads ---
+
+    fn game_with_players(n: usize) -> Game {
+        let mut game = make_game();
+        game.squads.insert("a".into(), "Blue".into());
+        game.squads.insert("b".into(), "Red".into());
+        for i in 0..n {
+            game.players.insert(format!("p{}", i), rsvp(RsvpStatus::In));
         }
+        game
     }
 
-    // --- team_response ---
+    fn squad_of(game: &Game, pid: &str) -> String {
+        game.squad_assignments.get(pid).unwrap().clone()
+    }
 
     #[test]
-    fn team_response_no_game() {
-        let mut team = make_team("FC Test");
-        team.location = Some("Field A".into());
-        let resp = team_response(&team, "abc123", None);
-        assert_eq!(resp.team_name, "FC Test");
-        assert_eq!(resp.team_key, "abc123");
-        assert_eq!(resp.location, Some("Field A".into()));
-        assert!(resp.game.is_none());
+    fn auto_squads_assigns_everyone_confirmed() {
+        let team = make_team("T");
+        let mut game = game_with_players(5);
+        game.players.insert("out".into(), rsvp(RsvpStatus::Out));
+        game.players.insert("unsure".into(), None);
+        game.guests = vec![guest("0"), guest("1")];
+        auto_squads(&team, &mut game, None, 1).unwrap();
+
+        assert_eq!(game.squad_assignments.len(), 7);
+        assert!(game.squad_assignments.contains_key("guest:1"));
+        assert!(!game.squad_assignments.contains_key("out"));
+        assert!(!game.squad_assignments.contains_key("unsure"));
+        let blue = game.squad_assignments.values().filter(|s| *s == "a").count();
+        assert!(blue == 3 || blue == 4);
     }
 
     #[test]
-    fn team_response_with_game() {
-        let team = make_team("FC Test");
-        let game = make_game();
-        let resp = team_response(&team, "abc123", Some(game));
-        assert!(resp.game.is_some());
+    fn auto_squads_is_deterministic_per_seed() {
+        let team = make_team("T");
+        let mut g1 = game_with_players(10);
+        let mut g2 = g1.clone();
+        auto_squads(&team, &mut g1, None, 99).unwrap();
+        auto_squads(&team, &mut g2, None, 99).unwrap();
+        assert_eq!(g1.squad_assignments, g2.squad_assignments);
     }
 
     #[test]
-    fn team_response_includes_roster() {
-        let mut team = make_team("FC Test");
-        team.players.insert("p1".into(), "Alice".into());
-        let resp = team_response(&team, "k", None);
-        assert_eq!(resp.players.get("p1").unwrap(), "Alice");
+    fn auto_squads_balances_ratings() {
+        let mut team = make_team("T");
+        for (pid, r) in [("p0", 1400.0), ("p1", 1300.0), ("p2", 1000.0), ("p3", 900.0)] {
+            team.ratings.insert(pid.into(), r);
+        }
+        let mut game = game_with_players(4);
+        auto_squads(&team, &mut game, None, 5).unwrap();
+        // The best balance is 1400+900 vs 1300+1000.
+        assert_eq!(squad_of(&game, "p0"), squad_of(&game, "p3"));
+        assert_eq!(squad_of(&game, "p1"), squad_of(&game, "p2"));
     }
 
-    // --- populate_unregistered_players ---
+    #[test]
+    fn auto_squads_handles_a_big_roster() {
+        let mut team = make_team("T");
+        let mut previous = game_with_players(120);
+        for i in 0..120 {
+            team.ratings.insert(format!("p{}", i), 800.0 + (i * 7 % 50) as f64 * 10.0);
+            previous.squad_assignments.insert(format!("p{}", i), if i < 60 { "a" } else { "b" }.into());
+        }
+        let mut game = game_with_players(120);
+        auto_squads(&team, &mut game, Some(&previous), 1).unwrap();
+        let in_a: Vec<usize> = (0..120).filter(|i| squad_of(&game, &format!("p{}", i)) == "a").collect();
+        assert_eq!(in_a.len(), 60);
+        // Last week's squads are mixed up rather than kept together.
+        let kept = in_a.iter().filter(|i| **i < 60).count();
+        assert!((20..=40).contains(&kept), "{} kept together", kept);
+    }
 
     #[test]
-    fn populate_adds_missing_players() {
+    fn auto_squads_splits_last_weeks_pairs() {
+        let team = make_team("T");
+        let mut previous = game_with_players(4);
+        for (pid, sid) in [("p0", "a"), ("p1", "a"), ("p2", "b"), ("p3", "b")] {
+            previous.squad_assignments.insert(pid.into(), sid.into());
+        }
+        for seed in 0..10 {
+            let mut game = game_with_players(4);
+            auto_squads(&team, &mut game, Some(&previous), seed).unwrap();
+            assert_ne!(squad_of(&game, "p0"), squad_of(&game, "p1"));
+            assert_ne!(squad_of(&game, "p2"), squad_of(&game, "p3"));
+        }
+    }
+
+    #[test]
+    fn auto_squads_falls_back_to_default_squads() {
         let mut team = make_team("T");
-        team.players.insert("p1".into(), "Alice".into());
-        team.players.insert("p2".into(), "Bob".into());
+        team.default_squads.insert("d1".into(), "Bibs".into());
+        team.default_squads.insert("d2".into(), "Skins".into());
+        let mut game = make_game();
+        game.players.insert("p1".into(), rsvp(RsvpStatus::In));
+        auto_squads(&team, &mut game, None, 0).unwrap();
+        assert_eq!(game.squads.len(), 2);
+        assert!(game.squads.contains_key(&squad_of(&game, "p1")));
+    }
 
+    #[test]
+    fn auto_squads_errors() {
+        let team = make_team("T");
         let mut game = make_game();
-        game.players.insert("p1".into(), Some(true));
+        game.players.insert("p1".into(), rsvp(RsvpStatus::In));
+        assert!(auto_squads(&team, &mut game, None, 0).is_err());
+        let mut game = game_with_players(0);
+        assert!(auto_squads(&team, &mut game, None, 0).is_err());
+    }
 
-        let changed = populate_unregistered_players(&team, &mut game);
-        assert!(changed);
-        assert_eq!(game.players.len(), 2);
-        assert_eq!(game.players.get("p2"), Some(&None));
+    // --- archive_game ---
+
+    #[test]
+    fn archive_game_moves_key_to_history() {
+        let mut team = make_team("T");
+        archive_game(&mut team);
+        assert!(team.past_games.is_empty());
+        team.next_game = Some("g1".into());
+        archive_game(&mut team);
+        assert!(team.next_game.is_none());
+        assert_eq!(team.past_games, vec!["g1".to_string()]);
     }
 
+    // --- set_ratings ---
+
     #[test]
-    fn populate_noop_when_all_present() {
+    fn set_ratings_updates_and_clears() {
         let mut team = make_team("T");
         team.players.insert("p1".into(), "Alice".into());
+        team.players.insert("p2".into(), "Bob".into());
+        team.ratings.insert("p2".into(), 1200.0);
+        let body: RatingsRequest = body(serde_json::json!({"ratings": {"p1": 1100, "p2": null, "ghost": 900}}));
+        set_ratings(&mut team, &body.ratings);
+        assert_eq!(team.ratings.get("p1"), Some(&1100.0));
+        assert!(!team.ratings.contains_key("p2"));
+        assert!(!team.ratings.contains_key("ghost"));
+    }
 
-        let mut game = make_game();
-        game.players.insert("p1".into(), Some(true));
+    // --- ratings from results ---
 
-        let changed = populate_unregistered_players(&team, &mut game);
-        assert!(!changed);
+    fn scored_game(blue: u32, red: u32) -> (Team, Game) {
+        let mut team = make_team("T");
+        let mut game = game_with_squads();
+        for (pid, sid) in [("p1", "s1"), ("p2", "s1"), ("p3", "s2"), ("p4", "s2")] {
+            team.players.insert(pid.into(), pid.into());
+            game.squad_assignments.insert(pid.into(), sid.into());
+        }
+        game.squad_assignments.insert("guest:0".into(), "s2".into());
+        game.squad_scores.insert("s1".into(), blue);
+        game.squad_scores.insert("s2".into(), red);
+        (team, game)
     }
 
     #[test]
-    fn populate_handles_empty_team() {
-        let team = make_team("T");
-        let mut game = make_game();
-        let changed = populate_unregistered_players(&team, &mut game);
-        assert!(!changed);
+    fn record_scores_validates() {
+        let mut game = game_with_squads();
+        record_scores(&mut game, &HashMap::from([("s1".into(), 3), ("s2".into(), 1)])).unwrap();
+        assert_eq!(game.squad_scores.get("s1"), Some(&3));
+        assert_eq!(record_scores(&mut game, &HashMap::from([("nope".into(), 1)])), Err(ServiceError::SquadNotFound));
+        assert_eq!(
+            body_errors::<ScoresRequest>(serde_json::json!({"scores": {"s1": -1}})),
+            vec![FieldError::new("scores.s1", "invalid value: integer `-1`, expected u32")]
+        );
+        assert_eq!(game.squad_scores.len(), 2);
     }
 
-    // --- set_player_status ---
+    #[test]
+    fn rating_changes_even_squads() {
+        let (team, game) = scored_game(3, 1);
+        let changes = rating_changes(&team, &game);
+        assert_eq!(changes.len(), 4);
+        assert!((changes["p1"] - 16.0).abs() < 1e-9);
+        assert!((changes["p3"] + 16.0).abs() < 1e-9);
+        assert!(!changes.contains_key("guest:0"));
+    }
 
     #[test]
-    fn set_play_new_player() {
-        let mut game = make_game();
-        set_player_status(&mut game, "p1", true);
-        assert_eq!(game.players.get("p1"), Some(&Some(true)));
+    fn rating_changes_draw_favours_underdog() {
+        let (mut team, game) = scored_game(2, 2);
+        team.ratings.insert("p1".into(), 1400.0);
+        let changes = rating_changes(&team, &game);
+        assert!(changes["p1"] < 0.0);
+        assert!(changes["p3"] > 0.0);
     }
 
     #[test]
-    fn set_not_play_overwrites() {
-        let mut game = make_game();
-        game.players.insert("p1".into(), Some(true));
-        set_player_status(&mut game, "p1", false);
-        assert_eq!(game.players.get("p1"), Some(&Some(false)));
+    fn rating_changes_need_two_scored_squads() {
+        let (team, mut game) = scored_game(1, 0);
+        game.squad_scores.remove("s2");
+        assert!(rating_changes(&team, &game).is_empty());
     }
 
-    // --- add_comment ---
+    #[test]
+    fn apply_game_ratings_replaces_earlier_result() {
+        let (mut team, mut game) = scored_game(3, 1);
+        apply_game_ratings(&mut team, &mut game);
+        assert!((team.ratings["p1"] - 1016.0).abs() < 1e-9);
+        game.squad_scores.insert("s2".into(), 5);
+        apply_game_ratings(&mut team, &mut game);
+        assert!((team.ratings["p1"] - 984.0).abs() < 1e-9);
+        assert!((team.ratings["p3"] - 1016.0).abs() < 1e-9);
+        assert!((game.rating_changes["p1"] + 16.0).abs() < 1e-9);
+    }
 
     #[test]
-    fn add_comment_ok() {
-        let mut game = make_game();
-        assert!(add_comment(&mut game, "Hello", None).is_ok());
-        assert_eq!(game.comments.len(), 1);
-        match &game.comments[0] {
-            Comment::Full { text, author } => {
-                assert_eq!(text, "Hello");
-                assert!(author.is_none());
-            }
-            _ => panic!("expected Full variant"),
-        }
+    fn apply_game_ratings_undoes_changes_to_a_cleared_rating() {
+        let (mut team, mut game) = scored_game(3, 1);
+        apply_game_ratings(&mut team, &mut game);
+        set_ratings(&mut team, &HashMap::from([("p1".to_string(), None)]));
+        team.players.remove("p2");
+        team.ratings.remove("p2");
+
+        game.squad_scores.clear();
+        apply_game_ratings(&mut team, &mut game);
+        // p1's +16 comes off the default they were cleared back to; p2 isn't on the roster.
+        assert!((team.ratings["p1"] - 984.0).abs() < 1e-9);
+        assert!(!team.ratings.contains_key("p2"));
+        assert!((team.ratings["p3"] - 1000.0).abs() < 1e-9);
+        assert!(game.rating_changes.is_empty());
     }
 
     #[test]
-    fn add_comment_with_author() {
-        let mut game = make_game();
-        assert!(add_comment(&mut game, "Hello", Some("Alice")).is_ok());
-        match &game.comments[0] {
-            Comment::Full { text, author } => {
-                assert_eq!(text, "Hello");
-                assert_eq!(author.as_deref(), Some("Alice"));
-            }
-            _ => panic!("expected Full variant"),
-        }
+    fn record_results_scores_and_stats() {
+        let (_, mut game) = scored_game(0, 0);
+        let results = body(serde_json::json!({
+            "scores": {"s1": 2, "s2": 1},
+            "stats": {"p1": {"goals": 2}, "p2": {"assists": 1}, "guest:0": {"goals": 1}, "p3": {}}
+        }));
+        record_results(&mut game, &results).unwrap();
+        assert_eq!(game.squad_scores.get("s1"), Some(&2));
+        assert_eq!(game.player_stats.get("p1"), Some(&PlayerStats { goals: 2, assists: 0 }));
+        assert!(!game.player_stats.contains_key("p3"));
+
+        // Scores alone leave the stats in place.
+        record_results(&mut game, &body(serde_json::json!({"scores": {"s1": 3, "s2": 1}}))).unwrap();
+        assert_eq!(game.player_stats.len(), 3);
     }
 
     #[test]
-    fn add_comment_empty_rejected() {
-        let mut game = make_game();
-        assert!(add_comment(&mut game, "", None).is_err());
-        assert!(game.comments.is_empty());
+    fn record_results_rejects_invalid() {
+        let (_, mut game) = scored_game(1, 0);
+        let cases = [
+            (serde_json::json!({"stats": {"nobody": {"goals": 1}}}), ServiceError::NotInSquad),
+            (serde_json::json!({"stats": {"p1": {"goals": 1}, "p2": {"goals": 1}}}), ServiceError::TooManyGoals),
+            (serde_json::json!({"stats": {"p3": {"assists": 1}}}), ServiceError::TooManyAssists),
+            (serde_json::json!({"scores": {"s1": 0}, "stats": {"p1": {"goals": 1}}}), ServiceError::TooManyGoals),
+        ];
+        for (json, err) in cases {
+            assert_eq!(record_results(&mut game, &body(json)), Err(err));
+        }
+        assert_eq!(game.squad_scores.get("s1"), Some(&1));
+        assert!(game.player_stats.is_empty());
     }
 
     #[test]
-    fn add_comment_accumulates() {
-        let mut game = make_game();
-        add_comment(&mut game, "First", None).unwrap();
-        add_comment(&mut game, "Second", Some("Bob")).unwrap();
-        assert_eq!(game.comments.len(), 2);
+    fn leaderboard_totals_and_order() {
+        let (team, mut g1) = scored_game(2, 1);
+        for pid in ["p1", "p2", "p3", "p4"] {
+            g1.players.insert(pid.into(), rsvp(RsvpStatus::In));
+        }
+        g1.player_stats.insert("p3".into(), PlayerStats { goals: 1, assists: 0 });
+        g1.date = Some(jiff::civil::date(2025, 5, 1));
+        let mut g2 = g1.clone();
+        g2.squad_scores.insert("s2".into(), 2);
+        g2.player_stats.insert("p1".into(), PlayerStats { goals: 2, assists: 0 });
+        g2.date = Some(jiff::civil::date(2026, 5, 1));
+        let mut off = g2.clone();
+        off.is_game_off = true;
+
+        let board = leaderboard(&team, &[g1.clone(), g2.clone(), off], None);
+        assert_eq!(board.len(), 4);
+        // Level on goals and assists, so wins decide.
+        assert_eq!(board[0].player_id, "p1");
+        assert_eq!((board[0].played, board[0].goals, board[0].wins, board[0].draws, board[0].losses), (2, 2, 1, 1, 0));
+        assert_eq!(board[1].player_id, "p3");
+        assert_eq!((board[1].goals, board[1].wins, board[1].draws, board[1].losses), (2, 0, 1, 1));
+
+        let board = leaderboard(&team, &[g1, g2], Some(2026));
+        assert_eq!(board[0].player_id, "p1");
+        assert_eq!(board[0].played, 1);
     }
 
-    // --- add_guests ---
+    // --- check-in ---
 
     #[test]
-    fn add_single_guest() {
-        let mut game = make_game();
-        assert!(add_guests(&mut game, "Charlie").is_ok());
-        assert_eq!(game.guests, vec!["Charlie"]);
+    fn check_in_records_and_clears() {
+        let (_, mut game) = voting_game();
+        let marks = HashMap::from([("p1".into(), Some(true)), ("p4".into(), Some(true)), ("guest:0".into(), Some(false))]);
+        check_in(&mut game, &marks).unwrap();
+        assert_eq!(game.attendance.get("p4"), Some(&true));
+        assert_eq!(game.attendance.get("guest:0"), Some(&false));
+        check_in(&mut game, &HashMap::from([("guest:0".into(), None)])).unwrap();
+        assert_eq!(game.attendance.len(), 2);
     }
 
     #[test]
-    fn add_comma_separated_guests() {
-        let mut game = make_game();
-        add_guests(&mut game, "Alice, Bob, Charlie").unwrap();
-        assert_eq!(game.guests, vec!["Alice", "Bob", "Charlie"]);
+    fn check_in_rejects_unknown() {
+        let (_, mut game) = voting_game();
+        for json in [
+            serde_json::json!({"attendance": {"p1": true, "nobody": true}}),
+            serde_json::json!({"attendance": {"guest:1": true}}),
+            serde_json::json!({"attendance": {"guest:x": true}}),
+        ] {
+            let marks: AttendanceRequest = body(json);
+            assert_eq!(check_in(&mut game, &marks.attendance), Err(ServiceError::PlayerNotFound));
+        }
+        assert_eq!(
+            body_errors::<AttendanceRequest>(serde_json::json!({"attendance": {"p1": "yes"}})),
+            vec![FieldError::new("attendance.p1", "invalid type: string \"yes\", expected a boolean")]
+        );
+        assert!(game.attendance.is_empty());
     }
 
     #[test]
-    fn add_guests_empty_rejected() {
+    fn no_show_rates_over_checked_in_games() {
+        let (team, mut g1) = voting_game();
+        g1.attendance.insert("p1".into(), true);
+        g1.attendance.insert("p2".into(), false);
+        let mut g2 = g1.clone();
+        g2.attendance.insert("p2".into(), true);
+        let mut unchecked = g1.clone();
+        unchecked.attendance.clear();
+
+        let rates = no_show_rates(&team, &[g1, g2, unchecked]);
+        assert_eq!(rates.len(), 3);
+        // p3 was never checked in.
+        assert_eq!((rates[0].player_id.as_str(), rates[0].no_shows, rates[0].said_playing), ("p3", 2, 2));
+        assert_eq!((rates[1].player_id.as_str(), rates[1].rate), ("p2", 0.5));
+        assert_eq!((rates[2].player_id.as_str(), rates[2].rate), ("p1", 0.0));
+    }
+
+    // --- MVP voting ---
+
+    fn voting_game() -> (Team, Game) {
+        let mut team = make_team("T");
+        team.time = Some("19:00".into());
         let mut game = make_game();
-        assert!(add_guests(&mut game, "").is_err());
+        game.date = Some(jiff::civil::date(2026, 3, 11));
+        for pid in ["p1", "p2", "p3"] {
+            team.players.insert(pid.into(), pid.into());
+            game.players.insert(pid.into(), rsvp(RsvpStatus::In));
+        }
+        team.players.insert("p4".into(), "p4".into());
+        game.players.insert("p4".into(), rsvp(RsvpStatus::Out));
+        game.guests.push(guest("0"));
+        (team, game)
     }
 
-    // --- delete_guest ---
+    fn at(s: &str) -> Timestamp {
+        s.parse().unwrap()
+    }
 
     #[test]
-    fn delete_guest_by_index() {
-        let mut game = make_game();
-        game.guests = vec!["A".into(), "B".into(), "C".into()];
-        delete_guest(&mut game, 1);
-        assert_eq!(game.guests, vec!["A", "C"]);
+    fn mvp_vote_rules() {
+        let (team, mut game) = voting_game();
+        let now = at("2026-03-11T20:00:00Z");
+        assert_eq!(cast_mvp_vote(&team, &mut game, "p4", "p1", now), Err(ServiceError::NotAVoter));
+        assert_eq!(cast_mvp_vote(&team, &mut game, "p1", "p1", now), Err(ServiceError::SelfVote));
+        assert_eq!(cast_mvp_vote(&team, &mut game, "p1", "p4", now), Err(ServiceError::CandidateDidntPlay));
+        cast_mvp_vote(&team, &mut game, "p1", "guest:0", now).unwrap();
+        assert_eq!(cast_mvp_vote(&team, &mut game, "p1", "p2", now), Err(ServiceError::AlreadyVoted));
+        assert_eq!(game.mvp_votes.get("p1"), Some(&"guest:0".to_string()));
     }
 
     #[test]
-    fn delete_guest_out_of_bounds_noop() {
-        let mut game = make_game();
-        game.guests = vec!["A".into()];
-        delete_guest(&mut game, 5);
-        assert_eq!(game.guests.len(), 1);
+    fn mvp_vote_window() {
+        let (mut team, mut game) = voting_game();
+        team.mvp_voting_hours = Some(2);
+        assert_eq!(
+            cast_mvp_vote(&team, &mut game, "p1", "p2", at("2026-03-11T18:59:00Z")),
+            Err(ServiceError::VotingNotOpen)
+        );
+        assert_eq!(
+            cast_mvp_vote(&team, &mut game, "p1", "p2", at("2026-03-11T21:00:00Z")),
+            Err(ServiceError::VotingClosed)
+        );
+        cast_mvp_vote(&team, &mut game, "p1", "p2", at("2026-03-11T20:59:00Z")).unwrap();
+
+        game.date = None;
+        assert_eq!(
+            cast_mvp_vote(&team, &mut game, "p2", "p1", at("2026-03-11T20:00:00Z")),
+            Err(ServiceError::NoKickoff)
+        );
     }
 
-    // --- save_squad_assignments ---
+    #[test]
+    fn close_mvp_voting_stores_winners() {
+        let (team, mut game) = voting_game();
+        let during = at("2026-03-11T20:00:00Z");
+        cast_mvp_vote(&team, &mut game, "p1", "p3", during).unwrap();
+        cast_mvp_vote(&team, &mut game, "p2", "p3", during).unwrap();
+        cast_mvp_vote(&team, &mut game, "p3", "p1", during).unwrap();
+
+        assert!(!close_mvp_voting(&team, &mut game, during));
+        assert!(game.mvp.is_none());
+        let after = at("2026-03-12T19:00:00Z");
+        assert!(close_mvp_voting(&team, &mut game, after));
+        assert_eq!(game.mvp, Some(vec!["p3".to_string()]));
+        assert!(!close_mvp_voting(&team, &mut game, after));
+        assert_eq!(cast_mvp_vote(&team, &mut game, "p1", "p2", during), Err(ServiceError::VotingClosed));
+    }
 
     #[test]
-    fn save_squads_replaces_all() {
-        let mut game = make_game();
-        game.squad_assignments.insert("p1".into(), "old".into());
+    fn mvp_winners_ties_and_no_votes() {
+        let (_, mut game) = voting_game();
+        assert!(mvp_winners(&game).is_empty());
+        game.mvp_votes.insert("p1".into(), "p2".into());
+        game.mvp_votes.insert("p2".into(), "p1".into());
+        assert_eq!(mvp_winners(&game), vec!["p1".to_string(), "p2".to_string()]);
+    }
 
-        let mut new = HashMap::new();
-        new.insert("p2".into(), "squad1".into());
-        save_squad_assignments(&mut game, new);
+    #[test]
+    fn responses_tally_mvp_votes_without_ballots() {
+        let (team, mut game) = voting_game();
+        game.mvp_votes.insert("p1".into(), "p3".into());
+        game.mvp_votes.insert("p2".into(), "p3".into());
+        let shown = team_response(&team, "k", Some(game)).game.unwrap();
+        assert!(shown.mvp_votes.is_empty());
+        assert_eq!(shown.mvp_tally.get("p3"), Some(&2));
+        let json = serde_json::to_value(&shown).unwrap();
+        assert!(json.get("mvp_votes").is_none());
+    }
 
-        assert!(!game.squad_assignments.contains_key("p1"));
-        assert_eq!(game.squad_assignments.get("p2"), Some(&"squad1".into()));
+    #[test]
+    fn admin_team_response_includes_ratings() {
+        let mut team = make_team("T");
+        team.ratings.insert("p1".into(), 1100.0);
+        assert!(team_response(&team, "k", None).ratings.is_none());
+        let mut game = make_game();
+        game.rating_changes.insert("p1".into(), 16.0);
+        assert!(team_response(&team, "k", Some(game)).game.unwrap().rating_changes.is_empty());
+        assert_eq!(admin_team_response(&team, "k", None).ratings.unwrap().get("p1"), Some(&1100.0));
     }
 
     // --- apply_settings ---
@@ -395,23 +3038,27 @@ mod tests {
     #[test]
     fn apply_settings_all_fields() {
         let mut team = make_team("T");
-        let body = serde_json::json!({
+        let json = serde_json::json!({
             "location": "Stadium",
             "time": "19:00",
-            "weekly_schedule": 3
+            "weekly_schedule": 3,
+            "results_open": true,
+            "guest_limit": 2
         });
-        apply_settings(&mut team, &body);
+        apply_settings(&mut team, &body(json));
         assert_eq!(team.location, Some("Stadium".into()));
         assert_eq!(team.time, Some("19:00".into()));
         assert_eq!(team.weekly_schedule, Some(3));
+        assert!(team.results_open);
+        assert_eq!(team.guest_limit, Some(2));
     }
 
     #[test]
     fn apply_settings_empty_clears() {
         let mut team = make_team("T");
         team.location = Some("Old".into());
-        let body = serde_json::json!({"location": ""});
-        apply_settings(&mut team, &body);
+        let json = serde_json::json!({"location": ""});
+        apply_settings(&mut team, &body(json));
         assert_eq!(team.location, None);
     }
 
@@ -420,24 +3067,53 @@ mod tests {
         let mut team = make_team("T");
         team.location = Some("Keep".into());
         team.time = Some("18:00".into());
-        let body = serde_json::json!({"time": "20:00"});
-        apply_settings(&mut team, &body);
+        let json = serde_json::json!({"time": "20:00"});
+        apply_settings(&mut team, &body(json));
         assert_eq!(team.location, Some("Keep".into())); // untouched
         assert_eq!(team.time, Some("20:00".into()));
     }
 
+    #[test]
+    fn settings_reject_invalid_weekly_schedule() {
+        for day in [0, 8] {
+            assert_eq!(
+                body_errors::<SettingsRequest>(serde_json::json!({"weekly_schedule": day})),
+                vec![FieldError::new("weekly_schedule", "must be 1 (Monday) to 7 (Sunday)")]
+            );
+        }
+        let mut team = make_team("T");
+        team.weekly_schedule = Some(3);
+        apply_settings(&mut team, &body(serde_json::json!({"weekly_schedule": null})));
+        assert_eq!(team.weekly_schedule, None);
+    }
+
+    /// v1 clears a schedule it can't use, rather than refusing the request.
     #[test]
     fn apply_settings_invalid_weekly_schedule() {
         let mut team = make_team("T");
-        let body = serde_json::json!({"weekly_schedule": 0});
+        team.weekly_schedule = Some(3);
+        let body = crate::types::parse_lenient_request(r#"{"weekly_schedule": 0}"#).unwrap();
         apply_settings(&mut team, &body);
         assert_eq!(team.weekly_schedule, None);
 
-        let body = serde_json::json!({"weekly_schedule": 8});
+        team.weekly_schedule = Some(3);
+        let body = crate::types::parse_lenient_request(r#"{"weekly_schedule": 8}"#).unwrap();
         apply_settings(&mut team, &body);
         assert_eq!(team.weekly_schedule, None);
     }
 
+    #[test]
+    fn settings_report_every_invalid_field() {
+        let errors = body_errors::<SettingsRequest>(serde_json::json!({
+            "name": " ",
+            "timezone": "Mars/Olympus",
+            "rsvp_deadline_hours": 169,
+            "mvp_voting_hours": 0
+        }));
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["name", "timezone", "rsvp_deadline_hours", "mvp_voting_hours"]);
+    }
+
     // --- add_players ---
 
     #[test]
@@ -445,7 +3121,7 @@ mod tests {
         let mut team = make_team("T");
         assert!(add_players(&mut team, "Alice").is_ok());
         assert_eq!(team.players.len(), 1);
-        assert!(team.players.values().any(|n| n == "Alice"));
+        assert!(team.players.values().any(|p| p.name == "Alice"));
     }
 
     #[test]
@@ -523,8 +3199,8 @@ mod tests {
     #[test]
     fn set_default_squads_from_json() {
         let mut team = make_team("T");
-        let squads = serde_json::json!({"1": "Blue", "2": "Red"});
-        set_default_squads(&mut team, squads.as_object().unwrap());
+        let req: DefaultSquadsRequest = body(serde_json::json!({"squads": {"1": "Blue", "2": "Red"}}));
+        set_default_squads(&mut team, &req.squads);
         assert_eq!(team.default_squads.len(), 2);
         assert_eq!(team.default_squads.get("1"), Some(&"Blue".into()));
     }
@@ -593,4 +3269,265 @@ mod tests {
         game.date = Some(jiff::Zoned::now().date().checked_sub(5.days()).unwrap());
         assert!(should_reset_game(&team, &game));
     }
+
+    // --- contacts ---
+
+    #[test]
+    fn set_contact_ok_trims_email() {
+        let mut team = make_team("T");
+        team.players.insert("p1".into(), "Alice".into());
+        set_contact(&mut team, "p1", &contact(" alice@example.com ")).unwrap();
+        assert_eq!(team.players["p1"], subscribed("Alice", "alice@example.com"));
+
+        remove_contact(&mut team, "p1");
+        assert_eq!(team.players["p1"].notifications, Notifications::default());
+        assert_eq!(team.players["p1"].email.as_deref(), Some("alice@example.com"));
+    }
+
+    #[test]
+    fn set_contact_rejects_unknown_player() {
+        let mut team = make_team("T");
+        assert!(set_contact(&mut team, "p1", &contact("a@example.com")).is_err());
+    }
+
+    #[test]
+    fn contact_rejects_bad_email() {
+        for email in ["alice", "alice@", "@example.com"] {
+            assert_eq!(
+                body_errors::<ContactRequest>(serde_json::json!({"email": email})),
+                vec![FieldError::new("email", "invalid email")]
+            );
+        }
+        assert!(contact("a@example.com").validate().is_empty());
+    }
+
+    // --- reminders ---
+
+    #[test]
+    fn reminder_due_evening_before_in_team_timezone() {
+        let mut team = make_team("T");
+        let mut game = make_game();
+        game.date = Some(jiff::civil::date(2026, 3, 11));
+        assert!(!reminder_due(&team, &game, at("2026-03-10T17:59:00Z")));
+        assert!(reminder_due(&team, &game, at("2026-03-10T18:00:00Z")));
+        assert!(reminder_due(&team, &game, at("2026-03-10T23:00:00Z")));
+        assert!(!reminder_due(&team, &game, at("2026-03-09T20:00:00Z")));
+        assert!(!reminder_due(&team, &game, at("2026-03-11T00:00:00Z")));
+
+        // 18:00 in Sydney is 07:00 UTC the same day.
+        team.timezone = Some("Australia/Sydney".into());
+        assert!(reminder_due(&team, &game, at("2026-03-10T07:00:00Z")));
+        assert!(!reminder_due(&team, &game, at("2026-03-10T06:59:00Z")));
+        assert!(!reminder_due(&team, &game, at("2026-03-10T18:00:00Z")));
+    }
+
+    #[test]
+    fn reminder_not_due_when_sent_or_off() {
+        let team = make_team("T");
+        let mut game = make_game();
+        game.date = Some(jiff::civil::date(2026, 3, 11));
+        let now = at("2026-03-10T19:00:00Z");
+        game.reminder_sent = true;
+        assert!(!reminder_due(&team, &game, now));
+        game.reminder_sent = false;
+        game.is_game_off = true;
+        assert!(!reminder_due(&team, &game, now));
+    }
+
+    #[test]
+    fn reminder_emails_skip_opted_out_and_not_playing() {
+        let mut team = make_team("T");
+        team.players.insert("p1".into(), subscribed("Alice", "alice@example.com"));
+        let mut bob = subscribed("Bob", "bob@example.com");
+        bob.notifications.reminders = false;
+        team.players.insert("p2".into(), bob);
+        team.players.insert("p3".into(), subscribed("Carol", "carol@example.com"));
+        team.players.insert("p4".into(), Player { email: None, ..subscribed("Dan", "") });
+        let mut game = make_game();
+        game.players.insert("p3".into(), rsvp(RsvpStatus::Out));
+
+        let emails = reminder_emails(&team, &game, "link");
+        assert_eq!(emails.len(), 1);
+        assert_eq!(emails[0].to, "alice@example.com");
+    }
+
+    #[test]
+    fn game_off_emails_only_opted_in() {
+        let mut team = make_team("T");
+        team.players.insert("p1".into(), subscribed("Alice", "alice@example.com"));
+        let mut bob = subscribed("Bob", "bob@example.com");
+        bob.notifications.game_off = false;
+        team.players.insert("p2".into(), bob);
+        let emails = game_off_emails(&team, &make_game(), "link");
+        assert_eq!(emails.len(), 1);
+        assert_eq!(emails[0].to, "alice@example.com");
+    }
+
+    #[test]
+    fn rsvp_changed_email_respects_opt_in() {
+        let mut team = make_team("T");
+        team.players.insert("p1".into(), "Alice".into());
+        assert!(rsvp_changed_email(&team, &make_game(), "p1", RsvpStatus::In, "link").is_none());
+        team.players.insert("p1".into(), subscribed("Alice", "alice@example.com"));
+        let e = rsvp_changed_email(&team, &make_game(), "p1", RsvpStatus::In, "link").unwrap();
+        assert!(e.text.contains("marked you as playing"));
+        team.players.get_mut("p1").unwrap().notifications.rsvp_changes = false;
+        assert!(rsvp_changed_email(&team, &make_game(), "p1", RsvpStatus::In, "link").is_none());
+    }
+
+    #[test]
+    fn game_off_emails_are_delivered_via_transport() {
+        use crate::mail::testing::{RecordingTransport, block_on};
+        let mut team = make_team("T");
+        team.players.insert("p1".into(), subscribed("Alice", "alice@example.com"));
+        let transport = RecordingTransport::default();
+        let failed = block_on(mail::send_all(&transport, &game_off_emails(&team, &make_game(), "link")));
+        assert!(failed.is_empty());
+        assert_eq!(transport.sent.borrow()[0].to, "alice@example.com");
+    }
+
+    // --- kickoff ---
+
+    #[test]
+    fn kickoff_needs_date_and_time() {
+        let mut team = make_team("T");
+        let mut game = make_game();
+        assert!(kickoff(&team, &game).is_none());
+        game.date = Some(jiff::civil::date(2026, 3, 11));
+        assert!(kickoff(&team, &game).is_none());
+        team.time = Some("not a time".into());
+        assert!(kickoff(&team, &game).is_none());
+        team.time = Some("19:00".into());
+        assert_eq!(kickoff(&team, &game), Some("2026-03-11T19:00:00Z".parse().unwrap()));
+    }
+
+    #[test]
+    fn kickoff_uses_team_timezone() {
+        let mut team = make_team("T");
+        team.time = Some("19:00".into());
+        team.timezone = Some("Europe/London".into());
+        let mut game = make_game();
+        game.date = Some(jiff::civil::date(2026, 7, 1));
+        assert_eq!(kickoff(&team, &game), Some("2026-07-01T18:00:00Z".parse().unwrap()));
+    }
+
+    #[test]
+    fn apply_settings_timezone() {
+        let mut team = make_team("T");
+        apply_settings(&mut team, &body(serde_json::json!({"timezone": "Europe/Berlin"})));
+        assert_eq!(team.timezone.as_deref(), Some("Europe/Berlin"));
+        apply_settings(&mut team, &body(serde_json::json!({"timezone": ""})));
+        assert_eq!(team.timezone, None);
+    }
+
+    #[test]
+    fn kickoff_push_due_window() {
+        let mut team = make_team("T");
+        team.time = Some("19:00".into());
+        let mut game = make_game();
+        game.date = Some(jiff::civil::date(2026, 3, 11));
+        let at = |s: &str| -> Timestamp { s.parse().unwrap() };
+
+        assert!(!kickoff_push_due(&team, &game, at("2026-03-11T16:30:00Z")));
+        assert!(kickoff_push_due(&team, &game, at("2026-03-11T17:00:00Z")));
+        assert!(kickoff_push_due(&team, &game, at("2026-03-11T18:59:00Z")));
+        assert!(!kickoff_push_due(&team, &game, at("2026-03-11T19:00:00Z")));
+
+        game.kickoff_push_sent = true;
+        assert!(!kickoff_push_due(&team, &game, at("2026-03-11T18:00:00Z")));
+        game.kickoff_push_sent = false;
+        game.is_game_off = true;
+        assert!(!kickoff_push_due(&team, &game, at("2026-03-11T18:00:00Z")));
+    }
+
+    // --- push subscriptions ---
+
+    fn subscription(endpoint: &str) -> PushSubscription {
+        PushSubscription {
+            endpoint: endpoint.to_string(),
+            keys: crate::types::PushKeys {
+                p256dh: "key".to_string(),
+                auth: "auth".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn add_push_subscription_dedupes_by_endpoint() {
+        let mut team = make_team("T");
+        team.players.insert("p1".into(), "Alice".into());
+        add_push_subscription(&mut team, "p1", subscription("https://push.example/a")).unwrap();
+        add_push_subscription(&mut team, "p1", subscription("https://push.example/a")).unwrap();
+        add_push_subscription(&mut team, "p1", subscription("https://push.example/b")).unwrap();
+        assert_eq!(team.push_subscriptions.get("p1").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn add_push_subscription_caps_per_player() {
+        let mut team = make_team("T");
+        team.players.insert("p1".into(), "Alice".into());
+        for i in 0..=MAX_PUSH_SUBSCRIPTIONS {
+            add_push_subscription(&mut team, "p1", subscription(&format!("https://push.example/{}", i))).unwrap();
+        }
+        let subs = team.push_subscriptions.get("p1").unwrap();
+        assert_eq!(subs.len(), MAX_PUSH_SUBSCRIPTIONS);
+        assert_eq!(subs[0].endpoint, "https://push.example/1");
+    }
+
+    #[test]
+    fn add_push_subscription_validates() {
+        let mut team = make_team("T");
+        assert!(add_push_subscription(&mut team, "p1", subscription("https://push.example/a")).is_err());
+        assert!(subscription("https://push.example/a").validate().is_empty());
+        assert_eq!(
+            body_errors::<PushSubscription>(serde_json::json!({
+                "endpoint": "http://push.example/a",
+                "keys": {"p256dh": "", "auth": "auth"},
+            })),
+            vec![
+                FieldError::new("endpoint", "endpoint must be an https URL"),
+                FieldError::new("keys.p256dh", "key can't be empty"),
+            ]
+        );
+        let long = format!("https://push.example/{}", "a".repeat(1000));
+        assert_eq!(
+            body_errors::<RemovePushSubscriptionRequest>(serde_json::json!({"endpoint": long})),
+            vec![FieldError::new("endpoint", "endpoint is too long")]
+        );
+    }
+
+    #[test]
+    fn remove_push_subscription_drops_empty_entry() {
+        let mut team = make_team("T");
+        team.players.insert("p1".into(), "Alice".into());
+        add_push_subscription(&mut team, "p1", subscription("https://push.example/a")).unwrap();
+        remove_push_subscription(&mut team, "p1", "https://push.example/a");
+        assert!(team.push_subscriptions.is_empty());
+    }
+
+    #[test]
+    fn push_targets_skip_players_who_are_out() {
+        let mut team = make_team("T");
+        team.players.insert("p1".into(), "Alice".into());
+        team.players.insert("p2".into(), "Bob".into());
+        add_push_subscription(&mut team, "p1", subscription("https://push.example/a")).unwrap();
+        add_push_subscription(&mut team, "p2", subscription("https://push.example/b")).unwrap();
+        let mut game = make_game();
+        game.players.insert("p2".into(), rsvp(RsvpStatus::Out));
+
+        assert_eq!(push_targets(&team, None).len(), 2);
+        let targets = push_targets(&team, Some(&game));
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].0, "p1");
+    }
+
+    #[test]
+    fn kickoff_push_counts_players_and_guests() {
+        let team = make_team("T");
+        let mut game = make_game();
+        game.players.insert("p1".into(), rsvp(RsvpStatus::In));
+        game.players.insert("p2".into(), rsvp(RsvpStatus::Out));
+        game.guests = vec![guest("0")];
+        assert_eq!(kickoff_push(&team, &game, "l").body, "Kick-off soon, 2 playing.");
+    }
 }
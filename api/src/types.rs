@@ -1,17 +1,24 @@
 use std::collections::HashMap;
 
+use jiff::Timestamp;
 use jiff::civil::Date;
 use serde::{Deserialize, Serialize};
+use serde_path_to_error::Segment;
+use utoipa::ToSchema;
 
 pub type PlayerID = String;
 pub type SquadID = String;
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Stored under the team key. Read through [`crate::migrate`], which upgrades older documents.
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
 pub struct Team {
+    /// See [`crate::migrate::TEAM_SCHEMA_VERSION`].
+    #[serde(default)]
+    pub schema_version: u32,
     pub name: String,
     pub secret: String,
     pub next_game: Option<String>,
-    pub players: HashMap<PlayerID, String>,
+    pub players: HashMap<PlayerID, Player>,
     #[serde(default)]
     pub location: Option<String>,
     #[serde(default)]
@@ -20,16 +27,130 @@ pub struct Team {
     pub weekly_schedule: Option<i8>,
     #[serde(default)]
     pub default_squads: HashMap<SquadID, String>,
+    #[serde(default)]
+    pub push_subscriptions: HashMap<PlayerID, Vec<PushSubscription>>,
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub ratings: HashMap<PlayerID, f64>,
+    /// Keys of earlier games, oldest first.
+    #[serde(default)]
+    pub past_games: Vec<String>,
+    /// Whether players, not just admins, can record game results.
+    #[serde(default)]
+    pub results_open: bool,
+    /// How many hours MVP voting stays open after kick-off. None uses the default.
+    #[serde(default)]
+    pub mvp_voting_hours: Option<u32>,
+    /// How many hours before kick-off RSVPs lock. None means they never do.
+    #[serde(default)]
+    pub rsvp_deadline_hours: Option<u32>,
+    /// Most guests one player can bring to a game. None means no limit.
+    #[serde(default)]
+    pub guest_limit: Option<u32>,
+    /// Newcomers waiting for an admin to let them onto the roster, oldest first.
+    #[serde(default)]
+    pub join_requests: Vec<JoinRequest>,
+}
+
+/// Someone asking to join the roster through the team link.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct JoinRequest {
+    pub id: String,
+    pub name: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub requested_at: Timestamp,
+}
+
+/// Someone on the roster. Inactive players, say injured ones, aren't added to new games but keep
+/// their history.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct Player {
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub active: bool,
+    #[serde(default)]
+    pub position: Option<String>,
+    #[serde(default)]
+    pub shirt_number: Option<u8>,
+    /// Only admins see phone, email, notes and notifications.
+    #[serde(default)]
+    pub phone: Option<String>,
+    /// Also where their notification emails go.
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub notifications: Notifications,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl From<&str> for Player {
+    fn from(name: &str) -> Self {
+        Player {
+            name: name.to_string(),
+            active: true,
+            position: None,
+            shirt_number: None,
+            phone: None,
+            email: None,
+            notes: None,
+            notifications: Notifications::default(),
+        }
+    }
+}
+
+/// The parts of a player's profile anyone with the team link can see.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, ToSchema)]
+pub struct PublicProfile {
+    pub position: Option<String>,
+    pub shirt_number: Option<u8>,
+}
+
+/// Which emails a player opted into. They only go out if the player has an email address.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, ToSchema)]
+pub struct Notifications {
+    #[serde(default)]
+    pub reminders: bool,
+    #[serde(default)]
+    pub game_off: bool,
+    #[serde(default)]
+    pub rsvp_changes: bool,
+}
+
+/// A browser push subscription, as returned by `PushSubscription.toJSON()`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct PushSubscription {
+    pub endpoint: String,
+    pub keys: PushKeys,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct PushKeys {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+/// Stored under the game key. Read through [`crate::migrate`], which upgrades older documents.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct Game {
+    /// See [`crate::migrate::GAME_SCHEMA_VERSION`].
+    #[serde(default)]
+    pub schema_version: u32,
     pub description: String,
-    pub players: HashMap<PlayerID, Option<bool>>,
-    pub guests: Vec<String>,
+    /// Each roster player's RSVP, or null if they haven't answered yet.
+    // The generated schema can't say a map's values may be null, so that's left to the description.
+    #[schema(value_type = HashMap<PlayerID, Rsvp>)]
+    pub players: HashMap<PlayerID, Option<Rsvp>>,
+    pub guests: Vec<Guest>,
     #[serde(default)]
     pub comments: Vec<Comment>,
     #[serde(default)]
+    #[schema(value_type = Option<String>, format = Date)]
     pub date: Option<Date>,
     #[serde(default)]
     pub squads: HashMap<SquadID, String>,
@@ -37,21 +158,127 @@ pub struct Game {
     pub squad_assignments: HashMap<PlayerID, SquadID>,
     #[serde(default)]
     pub is_game_off: bool,
+    #[serde(default)]
+    pub reminder_sent: bool,
+    #[serde(default)]
+    pub kickoff_push_sent: bool,
+    #[serde(default)]
+    pub squad_scores: HashMap<SquadID, u32>,
+    /// Goals and assists, by squad board id (player id or `guest:<id>`).
+    #[serde(default)]
+    pub player_stats: HashMap<String, PlayerStats>,
+    /// Rating changes this game's result applied, by player, so recording it again undoes them
+    /// first. Left out of responses; exports keep them.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub rating_changes: HashMap<PlayerID, f64>,
+    /// Bumped by every change to the squad board, so the team's room can tell when a handler
+    /// publishes a game it read before some squad board ops.
+    #[serde(default)]
+    pub squad_revision: u64,
+    /// MVP votes, voter to candidate. Candidates are squad board ids, so guests can win too.
+    /// Ballots are private: responses carry [`Game::mvp_tally`] instead.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub mvp_votes: HashMap<PlayerID, String>,
+    /// MVP votes per candidate. Only filled in responses, never stored.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub mvp_tally: HashMap<String, u32>,
+    /// Whoever got the most MVP votes, set once voting closes. Empty if nobody voted.
+    #[serde(default)]
+    pub mvp: Option<Vec<String>>,
+    /// Who actually turned up, from the admin's check-in, by squad board id.
+    #[serde(default)]
+    pub attendance: HashMap<String, bool>,
 }
 
-/// A comment is either a legacy plain string or a struct with optional author.
-/// Old data stored as strings will deserialize via the Legacy variant.
-/// New comments are always saved as Full.
-#[derive(Serialize, Deserialize, Debug, Clone)]
-#[serde(untagged)]
-pub enum Comment {
-    Full { text: String, #[serde(default, skip_serializing_if = "Option::is_none")] author: Option<String> },
-    Legacy(String),
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, ToSchema)]
+pub struct PlayerStats {
+    #[serde(default)]
+    pub goals: u32,
+    #[serde(default)]
+    pub assists: u32,
+}
+
+/// Someone brought along for one game.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct Guest {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub invited_by: Option<PlayerID>,
+}
+
+impl Guest {
+    /// How the guest is referred to on the squad board, in results and in check-in.
+    pub fn board_id(&self) -> String {
+        format!("guest:{}", self.id)
+    }
+}
+
+/// A player's answer for a game.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RsvpStatus {
+    In,
+    Out,
+    Maybe,
+    Late,
+}
+
+/// An RSVP with an optional short note, like "arriving 15 min late".
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct Rsvp {
+    pub status: RsvpStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl Rsvp {
+    /// Whether they're coming, even if late.
+    pub fn is_playing(&self) -> bool {
+        matches!(self.status, RsvpStatus::In | RsvpStatus::Late)
+    }
+}
+
+/// A comment on a game, with an optional author.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct Comment {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Written before comments had authors, as a bare string. v1 still sends these that way.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub legacy: bool,
+}
+
+/// A single change to the squad board, sent over the squad board WebSocket.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum SquadOp {
+    Assign { player: String, squad: SquadID },
+    Unassign { player: String },
+    AddSquad { squad: SquadID, name: String },
+    RenameSquad { squad: SquadID, name: String },
+    RemoveSquad { squad: SquadID },
+}
+
+/// What the squad board WebSocket sends back.
+/// `State` carries the authoritative board, along with the op that produced it if any.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SquadBoardEvent {
+    State {
+        op: Option<SquadOp>,
+        squads: HashMap<SquadID, String>,
+        assignments: HashMap<PlayerID, SquadID>,
+    },
+    Error {
+        message: String,
+    },
 }
 
 // API response types
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct TeamPageResponse {
     pub team_name: String,
     pub team_key: String,
@@ -59,12 +286,507 @@ pub struct TeamPageResponse {
     pub time: Option<String>,
     pub weekly_schedule: Option<i8>,
     pub default_squads: HashMap<SquadID, String>,
+    /// Every roster player's name, inactive ones included so history still reads.
     pub players: HashMap<PlayerID, String>,
+    /// Players who won't be added to new games.
+    #[serde(default)]
+    pub inactive_players: Vec<PlayerID>,
+    /// Positions and shirt numbers, for players who have either.
+    #[serde(default)]
+    pub profiles: HashMap<PlayerID, PublicProfile>,
     pub game: Option<Game>,
+    /// How the current game's RSVPs add up.
+    #[serde(default)]
+    pub rsvp_counts: Option<RsvpCounts>,
+    #[serde(default)]
+    pub rsvp_deadline_hours: Option<u32>,
+    #[serde(default)]
+    pub guest_limit: Option<u32>,
+    /// When RSVPs for the current game lock, if there's a deadline and a kick-off time.
+    #[serde(default)]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub rsvp_deadline: Option<Timestamp>,
+    #[serde(default)]
+    pub results_open: bool,
+    #[serde(default)]
+    pub mvp_voting_hours: Option<u32>,
+    /// When MVP voting for the current game opens and closes, if the game has a kick-off time.
+    #[serde(default)]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub mvp_voting_opens: Option<Timestamp>,
+    #[serde(default)]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub mvp_voting_closes: Option<Timestamp>,
+    /// Only included in admin responses.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ratings: Option<HashMap<PlayerID, f64>>,
+    /// Only included in admin responses.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub join_requests: Option<Vec<JoinRequest>>,
+    /// Full player profiles, contact details included. Only included in admin responses.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_details: Option<HashMap<PlayerID, Player>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, ToSchema)]
+pub struct RsvpCounts {
+    pub r#in: usize,
+    pub late: usize,
+    pub maybe: usize,
+    pub out: usize,
+    pub no_answer: usize,
+    pub guests: usize,
+    /// In, late and guests together.
+    pub playing: usize,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct NewTeamResponse {
     pub team_key: String,
     pub team_secret: String,
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct AutoSquadsResponse {
+    pub seed: u64,
+    pub squads: HashMap<SquadID, String>,
+    pub assignments: HashMap<PlayerID, SquadID>,
+}
+
+/// One roster player's totals over a season.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct LeaderboardEntry {
+    pub player_id: PlayerID,
+    pub name: String,
+    pub played: u32,
+    pub goals: u32,
+    pub assists: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct LeaderboardResponse {
+    pub season: Option<i16>,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+/// How often a roster player said they'd play but didn't turn up, over checked-in games.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct NoShowEntry {
+    pub player_id: PlayerID,
+    pub name: String,
+    pub said_playing: u32,
+    pub no_shows: u32,
+    pub rate: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct PromoteGuestResponse {
+    pub player_id: PlayerID,
+    /// How many games the guest's history was carried over from.
+    pub games: usize,
+}
+
+/// A team's game keys, for fetching games one at a time.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct GameKeysResponse {
+    pub current: Option<String>,
+    /// Oldest first.
+    pub past: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct PushPublicKeyResponse {
+    /// The VAPID public key, for `PushManager.subscribe()`.
+    pub public_key: String,
+}
+
+/// What a roster CSV import would do, or did.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, ToSchema)]
+pub struct RosterImportResponse {
+    /// Whether the players were added. False for dry runs and imports with errors.
+    pub imported: bool,
+    pub added: Vec<Player>,
+    /// Rows skipped because the name is already on the roster or earlier in the file.
+    pub duplicates: Vec<ImportDuplicate>,
+    pub errors: Vec<ImportError>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct ImportDuplicate {
+    /// Line in the CSV, counting the header as line 1.
+    pub line: u64,
+    pub name: String,
+    /// The roster player with the same name, if it wasn't just repeated within the file.
+    pub player_id: Option<PlayerID>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct ImportError {
+    pub line: u64,
+    pub message: String,
+}
+
+/// Everything about a team in one document, for backups and moving between deployments.
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct TeamArchive {
+    /// Format version, bumped whenever an older reader couldn't make sense of the document.
+    pub version: u32,
+    #[schema(value_type = String, format = DateTime)]
+    pub exported_at: Timestamp,
+    pub team: Team,
+    /// The current game and every past one, by the key the team refers to it with.
+    pub games: HashMap<String, Game>,
+}
+
+/// A stored document exactly as it is in KV, for admins to inspect when it can't be read.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct RawDocument {
+    /// "team" or "game".
+    pub kind: String,
+    pub key: String,
+    /// None if the document is missing.
+    pub document: Option<String>,
+    /// Why the document can't be read, if it can't.
+    pub error: Option<String>,
+}
+
+// ============================================================
+// Request bodies
+// ============================================================
+
+/// A problem with one field of a request body. `field` is a dotted path, like `scores.blue`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        FieldError {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Checks on a request body beyond what deserializing it already guarantees.
+/// The rules live with the logic they protect, mostly in `service`.
+pub trait Validate {
+    fn validate(&self) -> Vec<FieldError> {
+        Vec::new()
+    }
+}
+
+/// Most field errors reported for one body.
+const MAX_FIELD_ERRORS: usize = 20;
+
+/// Deserialize and validate a request body. An empty body reads as `{}`, so endpoints whose
+/// fields are all optional can be called without one.
+///
+/// Every bad field is reported, not just the first: each one is taken out and the body read
+/// again. A list is reported once, at its first bad element, and a missing field is reported on
+/// its object, which is then taken out as a whole.
+pub fn parse_request<T: serde::de::DeserializeOwned + Validate>(text: &str) -> Result<T, Vec<FieldError>> {
+    let text = if text.trim().is_empty() { "{}" } else { text };
+    let mut value: serde_json::Value =
+        serde_json::from_str(text).map_err(|e| vec![FieldError::new("", without_position(&e.to_string()))])?;
+    let mut errors = Vec::new();
+    // Paths of what's been taken out, so taking out a required field doesn't report it missing too.
+    let mut removed: Vec<String> = Vec::new();
+    loop {
+        let e = match serde_path_to_error::deserialize::<_, T>(&value) {
+            Ok(body) if errors.is_empty() => {
+                return match body.validate() {
+                    errors if errors.is_empty() => Ok(body),
+                    errors => Err(errors),
+                };
+            }
+            Ok(_) => return Err(errors),
+            Err(e) => e,
+        };
+        // The whole body is "."; no one field is to blame.
+        let field = match e.path().to_string() {
+            p if p == "." => String::new(),
+            p => p,
+        };
+        let message = e.inner().to_string();
+        let missing = message.strip_prefix("missing field `").and_then(|m| m.strip_suffix('`'));
+        let taken_out = missing.is_some_and(|key| removed.iter().any(|r| *r == join_field(&field, key)));
+        if !taken_out {
+            errors.push(FieldError::new(&field, without_position(&message)));
+        }
+        let segments: Vec<Segment> = e.path().iter().cloned().collect();
+        match take_out(&mut value, &segments) {
+            Some(path) if errors.len() < MAX_FIELD_ERRORS => removed.push(path),
+            _ => return Err(errors),
+        }
+    }
+}
+
+/// Deserialize and validate a v1 request body, as loosely as v1 read them: a value of the wrong
+/// type, or one that fails validation, reads as null, so a setting it was meant for is cleared.
+/// Where null won't do either, it's ignored as if it weren't there. Only a body that still can't
+/// be read without it is an error, reported as [`parse_request`] would.
+pub fn parse_lenient_request<T: serde::de::DeserializeOwned + Validate>(text: &str) -> Result<T, Vec<FieldError>> {
+    let strict = match parse_request::<T>(text) {
+        Ok(body) => return Ok(body),
+        Err(errors) => errors,
+    };
+    let text = if text.trim().is_empty() { "{}" } else { text };
+    let Ok(mut value) = serde_json::from_str::<serde_json::Value>(text) else { return Err(strict) };
+    // Each bad value can take two rounds: one to null it, and one to take it out.
+    for _ in 0..2 * MAX_FIELD_ERRORS {
+        let bad: Vec<Vec<Segment>> = match serde_path_to_error::deserialize::<_, T>(&value) {
+            Ok(body) => {
+                let errors = body.validate();
+                if errors.is_empty() {
+                    return Ok(body);
+                }
+                errors
+                    .iter()
+                    .map(|e| e.field.split('.').map(|key| Segment::Map { key: key.to_string() }).collect())
+                    .collect()
+            }
+            Err(e) => vec![e.path().iter().cloned().collect()],
+        };
+        for path in bad {
+            if !null_out(&mut value, &path) && take_out(&mut value, &path).is_none() {
+                return Err(strict);
+            }
+        }
+    }
+    Err(strict)
+}
+
+/// serde_json adds where in the text it went wrong, which means little to API clients.
+fn without_position(message: &str) -> &str {
+    message.split(" at line ").next().unwrap_or_default()
+}
+
+fn join_field(object: &str, key: &str) -> String {
+    if object.is_empty() { key.to_string() } else { format!("{}.{}", object, key) }
+}
+
+/// Remove the value at `path` from the body, or the whole list it's in, since removing one
+/// element would renumber the rest. Returns the path of what was removed, or None if it's the
+/// whole body or can't be found.
+fn take_out(value: &mut serde_json::Value, path: &[Segment]) -> Option<String> {
+    let end = path.iter().position(|s| matches!(s, Segment::Seq { .. })).unwrap_or(path.len());
+    let mut keys = Vec::new();
+    for segment in &path[..end] {
+        match segment {
+            Segment::Map { key } => keys.push(key.as_str()),
+            _ => return None,
+        }
+    }
+    let (last, parents) = keys.split_last()?;
+    let mut object = value;
+    for key in parents {
+        object = object.get_mut(*key)?;
+    }
+    object.as_object_mut()?.remove(*last)?;
+    Some(keys.join("."))
+}
+
+/// Replace the field at `path` with null. Returns false if it's already null, in a list, or can't
+/// be found.
+fn null_out(value: &mut serde_json::Value, path: &[Segment]) -> bool {
+    let mut field = Some(value);
+    for segment in path {
+        field = match segment {
+            Segment::Map { key } => field.and_then(|f| f.get_mut(key)),
+            _ => None,
+        };
+    }
+    match field {
+        Some(f) if !f.is_null() => {
+            *f = serde_json::Value::Null;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// For partial updates: a missing field is None, and a present one is Some, even if it's null.
+fn present<'de, D, T>(d: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(d).map(Some)
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct NewTeamRequest {
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct RsvpRequest {
+    pub status: RsvpStatus,
+    /// None keeps the existing note, and an empty one clears it.
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct CommentRequest {
+    pub comment: String,
+    #[serde(default)]
+    pub author: Option<String>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct AddGuestRequest {
+    /// One or more names, comma-separated.
+    pub guest_name: String,
+}
+
+#[derive(Deserialize, Debug, Default, ToSchema)]
+pub struct NewGameRequest {
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct SquadsRequest {
+    #[serde(default)]
+    pub squads: Option<HashMap<SquadID, String>>,
+    #[serde(default)]
+    pub assignments: Option<HashMap<PlayerID, SquadID>>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct RemovePushSubscriptionRequest {
+    pub endpoint: String,
+}
+
+#[derive(Deserialize, Debug, Default, ToSchema)]
+pub struct AutoSquadsRequest {
+    /// Picks the same squads again for the same seed. Random if left out.
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// Team settings to change. Fields left out stay as they are; null clears the optional ones.
+#[derive(Deserialize, Debug, Default, ToSchema)]
+pub struct SettingsRequest {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    pub location: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub time: Option<Option<String>>,
+    /// Day of the week, 1 for Monday to 7 for Sunday.
+    #[serde(default, deserialize_with = "present")]
+    pub weekly_schedule: Option<Option<i8>>,
+    #[serde(default, deserialize_with = "present")]
+    pub timezone: Option<Option<String>>,
+    #[serde(default)]
+    pub results_open: Option<bool>,
+    #[serde(default, deserialize_with = "present")]
+    pub guest_limit: Option<Option<u32>>,
+    #[serde(default, deserialize_with = "present")]
+    pub rsvp_deadline_hours: Option<Option<u32>>,
+    #[serde(default, deserialize_with = "present")]
+    pub mvp_voting_hours: Option<Option<u32>>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct AddPlayersRequest {
+    /// One or more names, comma-separated.
+    pub names: String,
+}
+
+/// Changes to a roster player. Fields left out stay as they are; null clears the profile ones.
+#[derive(Deserialize, Debug, Default, ToSchema)]
+pub struct PlayerUpdate {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub active: Option<bool>,
+    #[serde(default, deserialize_with = "present")]
+    pub position: Option<Option<String>>,
+    // Wider than `Player::shirt_number` so out-of-range numbers get a clear error from validation.
+    #[serde(default, deserialize_with = "present")]
+    pub shirt_number: Option<Option<u16>>,
+    #[serde(default, deserialize_with = "present")]
+    pub phone: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub email: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub notes: Option<Option<String>>,
+}
+
+/// A player's email address and which notifications they want there.
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct ContactRequest {
+    pub email: String,
+    #[serde(flatten)]
+    pub notifications: Notifications,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct RosterImportRequest {
+    pub csv: String,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct DefaultSquadsRequest {
+    pub squads: HashMap<SquadID, String>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct PromoteGuestRequest {
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct MergePlayerRequest {
+    /// The player who stays.
+    pub into: PlayerID,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct JoinTeamRequest {
+    pub name: String,
+}
+
+/// A game's results. Each part that's present replaces what was recorded before.
+#[derive(Deserialize, Debug, Default, ToSchema)]
+pub struct ResultsRequest {
+    #[serde(default)]
+    pub scores: Option<HashMap<SquadID, u32>>,
+    /// Goals and assists, by squad board id.
+    #[serde(default)]
+    pub stats: Option<HashMap<String, PlayerStats>>,
+}
+
+/// Squad scores on their own, as `PUT .../scores` has always taken them.
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct ScoresRequest {
+    pub scores: HashMap<SquadID, u32>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct MvpVoteRequest {
+    pub candidate: String,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct RatingsRequest {
+    /// A null rating clears it.
+    pub ratings: HashMap<PlayerID, Option<f64>>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct AttendanceRequest {
+    /// By squad board id: true if they came, false if not, null to clear.
+    pub attendance: HashMap<String, Option<bool>>,
+}
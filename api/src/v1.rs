@@ -0,0 +1,188 @@
+//! v1's shapes, for clients written before v2. v1 and v2 share handlers, so v1 responses are
+//! rewritten on the way out to look as they did:
+//!
+//! - RSVPs are `true` (in or late), `false` (out) or `null` (maybe, or no answer), not objects.
+//! - Guests are names, not objects, and are deleted by their position in the list.
+//! - Comments written before comments had authors are bare strings.
+//! - A wrong admin secret is a 404 "team not found", not a 403.
+//!
+//! The live events stream came after v1 and is only served under v2, since its games aren't
+//! rewritten.
+//!
+//! Request bodies are read as loosely as v1 read them: a value v1 couldn't use clears the setting
+//! it was for, or is ignored; see [`crate::types::parse_lenient_request`].
+
+use serde_json::Value;
+
+use crate::error::ApiError;
+use crate::types::Game;
+
+/// Rewrite a v1 response body in place, and return the status to send it with.
+pub fn rewrite(path: &str, status: u16, body: &mut Value) -> u16 {
+    if status >= 400 {
+        if body["code"] == ApiError::WrongSecret.code() {
+            let err = ApiError::TeamNotFound;
+            *body = err.to_json();
+            return err.status();
+        }
+        return status;
+    }
+    match path.rsplit('/').next().unwrap_or_default() {
+        "new_game" => game(body),
+        "comments" => comments(body),
+        "guests" => guests(body),
+        _ => {
+            if let Some(g) = body.get_mut("game") {
+                game(g);
+            }
+        }
+    }
+    status
+}
+
+/// A game, or null.
+fn game(game: &mut Value) {
+    if let Some(Value::Object(players)) = game.get_mut("players") {
+        for rsvp in players.values_mut() {
+            *rsvp = match rsvp["status"].as_str() {
+                Some("in" | "late") => Value::Bool(true),
+                Some("out") => Value::Bool(false),
+                _ => Value::Null,
+            };
+        }
+    }
+    if let Some(g) = game.get_mut("guests") {
+        guests(g);
+    }
+    if let Some(c) = game.get_mut("comments") {
+        comments(c);
+    }
+}
+
+fn guests(guests: &mut Value) {
+    if let Value::Array(guests) = guests {
+        for guest in guests.iter_mut() {
+            *guest = guest["name"].take();
+        }
+    }
+}
+
+fn comments(comments: &mut Value) {
+    if let Value::Array(comments) = comments {
+        for comment in comments.iter_mut().filter(|c| c["legacy"] == true) {
+            *comment = comment["text"].take();
+        }
+    }
+}
+
+/// The id of the guest a v1 client means by their position in the list.
+pub fn guest_id(game: &Game, idx: &str) -> Option<String> {
+    let idx: usize = idx.parse().ok()?;
+    game.guests.get(idx).map(|g| g.id.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service;
+    use crate::types::{Comment, Guest, Rsvp, RsvpStatus, Team};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn rsvp(status: RsvpStatus) -> Option<Rsvp> {
+        Some(Rsvp { status, note: Some("running late".into()) })
+    }
+
+    fn team_page() -> Value {
+        let team: Team = serde_json::from_value(json!({
+            "name": "Sunday Football",
+            "secret": "s",
+            "next_game": "g1",
+            "players": {"p1": {"name": "Alice"}},
+        }))
+        .unwrap();
+        let mut game: Game = serde_json::from_value(json!({"description": "Sunday", "players": {}, "guests": []})).unwrap();
+        game.players = HashMap::from([
+            ("p1".into(), rsvp(RsvpStatus::Late)),
+            ("p2".into(), rsvp(RsvpStatus::Out)),
+            ("p3".into(), rsvp(RsvpStatus::Maybe)),
+            ("p4".into(), None),
+        ]);
+        game.guests = vec![Guest { id: "a1".into(), name: "Gina".into(), invited_by: Some("p1".into()) }];
+        game.comments = vec![
+            Comment { text: "see you there".into(), author: None, legacy: true },
+            Comment { text: "bring bibs".into(), author: Some("Alice".into()), legacy: false },
+            Comment { text: "me too".into(), author: None, legacy: false },
+        ];
+        serde_json::to_value(service::team_response(&team, "k", Some(game))).unwrap()
+    }
+
+    #[test]
+    fn team_page_has_the_v1_game_shape() {
+        let mut body = team_page();
+        assert_eq!(rewrite("/api/teams/k", 200, &mut body), 200);
+        assert_eq!(body["players"], json!({"p1": "Alice"}));
+        let game = &body["game"];
+        assert_eq!(game["players"], json!({"p1": true, "p2": false, "p3": null, "p4": null}));
+        assert_eq!(game["guests"], json!(["Gina"]));
+        assert_eq!(
+            game["comments"],
+            json!(["see you there", {"text": "bring bibs", "author": "Alice"}, {"text": "me too"}])
+        );
+    }
+
+    #[test]
+    fn lists_have_the_v1_shape() {
+        let page = team_page();
+        let mut guests = page["game"]["guests"].clone();
+        rewrite("/api/teams/k/guests", 200, &mut guests);
+        assert_eq!(guests, json!(["Gina"]));
+        let mut comments = page["game"]["comments"].clone();
+        rewrite("/api/teams/k/comments", 200, &mut comments);
+        assert_eq!(comments[0], "see you there");
+    }
+
+    #[test]
+    fn wrong_secret_is_not_found() {
+        let mut body = ApiError::WrongSecret.to_json();
+        assert_eq!(rewrite("/api/admin/k/nope", 403, &mut body), 404);
+        assert_eq!(body, ApiError::TeamNotFound.to_json());
+
+        let mut body = ApiError::RsvpClosed.to_json();
+        assert_eq!(rewrite("/api/teams/k/guests", 403, &mut body), 403);
+        assert_eq!(body, ApiError::RsvpClosed.to_json());
+    }
+
+    #[test]
+    fn guests_are_deleted_by_position() {
+        let mut game: Game = serde_json::from_value(json!({"description": "", "players": {}, "guests": []})).unwrap();
+        game.guests = vec![
+            Guest { id: "1".into(), name: "Gina".into(), invited_by: None },
+            Guest { id: "2".into(), name: "Hal".into(), invited_by: None },
+        ];
+        assert_eq!(guest_id(&game, "1").as_deref(), Some("2"));
+        assert_eq!(guest_id(&game, "2"), None);
+        assert_eq!(guest_id(&game, "x"), None);
+    }
+
+    #[test]
+    fn loose_bodies_clear_or_ignore_bad_values() {
+        use crate::types::{SettingsRequest, parse_lenient_request, parse_request};
+        let text = r#"{"location": "Park", "weekly_schedule": "3", "time": 7, "name": ""}"#;
+        assert!(parse_request::<SettingsRequest>(text).is_err());
+        let settings: SettingsRequest = parse_lenient_request(text).unwrap();
+        assert_eq!(settings.location, Some(Some("Park".into())));
+        assert_eq!(settings.weekly_schedule, Some(None));
+        assert_eq!(settings.time, Some(None));
+        assert_eq!(settings.name, None);
+
+        let settings: SettingsRequest = parse_lenient_request(r#"{"weekly_schedule": 9}"#).unwrap();
+        assert_eq!(settings.weekly_schedule, Some(None));
+
+        let scores: crate::types::ScoresRequest = parse_lenient_request(r#"{"scores": {"s1": -1, "s2": 3}}"#).unwrap();
+        assert_eq!(scores.scores, std::collections::HashMap::from([("s2".to_string(), 3)]));
+
+        let errors = parse_lenient_request::<crate::types::CommentRequest>(r#"{"comment": 5}"#).unwrap_err();
+        assert_eq!(errors[0].field, "comment");
+    }
+}
@@ -0,0 +1,18 @@
+// Shows the team's push notifications: a new game, a game called off, and "starting soon".
+// The API sends a JSON payload of { title, body, url }.
+
+self.addEventListener("push", (event) => {
+  const msg = event.data ? event.data.json() : {};
+  event.waitUntil(
+    self.registration.showNotification(msg.title || "nextgame", {
+      body: msg.body,
+      data: { url: msg.url },
+    }),
+  );
+});
+
+self.addEventListener("notificationclick", (event) => {
+  event.notification.close();
+  const url = event.notification.data && event.notification.data.url;
+  if (url) event.waitUntil(self.clients.openWindow(url));
+});
@@ -1,4 +1,4 @@
-import type { TeamPageResponse, NewTeamResponse, Game, Comment } from "./types";
+import type { TeamPageResponse, NewTeamResponse, Game, Comment, SquadOp, SquadBoardEvent, AutoSquadsResponse, SquadID, PlayerStats, LeaderboardResponse, RsvpStatus, NoShowEntry, Guest, PlayerID, PromoteGuestResponse, JoinRequest, RosterImportResponse, RawDocument } from "./types";
 
 function apiBase(): string {
   const origin = window.location.origin;
@@ -8,9 +8,26 @@ function apiBase(): string {
   return "https://nextgame.aquarhead.workers.dev";
 }
 
+// An error response from the API. `code` is stable; see api/src/error.rs for the list.
+export class ApiError extends Error {
+  constructor(
+    public status: number,
+    public code: string | null,
+    message: string,
+    public details?: unknown,
+  ) {
+    super(message);
+  }
+}
+
+async function throwApiError(resp: Response): Promise<never> {
+  const body = await resp.json().catch(() => null);
+  throw new ApiError(resp.status, body?.code ?? null, body?.error ?? `HTTP ${resp.status}`, body?.details);
+}
+
 async function fetchJson<T>(url: string): Promise<T> {
   const resp = await fetch(url);
-  if (!resp.ok) throw new Error(`HTTP ${resp.status}`);
+  if (!resp.ok) await throwApiError(resp);
   return resp.json();
 }
 
@@ -20,7 +37,7 @@ async function postJson<T>(url: string, body: unknown): Promise<T> {
     headers: { "Content-Type": "application/json" },
     body: JSON.stringify(body),
   });
-  if (!resp.ok) throw new Error(`HTTP ${resp.status}`);
+  if (!resp.ok) await throwApiError(resp);
   return resp.json();
 }
 
@@ -30,89 +47,269 @@ async function putJsonNoContent(url: string, body: unknown): Promise<void> {
     headers: { "Content-Type": "application/json" },
     body: JSON.stringify(body),
   });
-  if (!resp.ok) throw new Error(`HTTP ${resp.status}`);
+  if (!resp.ok) await throwApiError(resp);
 }
 
-async function deleteNoContent(url: string): Promise<void> {
-  const resp = await fetch(url, { method: "DELETE" });
-  if (!resp.ok) throw new Error(`HTTP ${resp.status}`);
+async function deleteNoContent(url: string, body?: unknown): Promise<void> {
+  const resp = await fetch(url, body === undefined ? { method: "DELETE" } : {
+    method: "DELETE",
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify(body),
+  });
+  if (!resp.ok) await throwApiError(resp);
 }
 
-async function postNoContent(url: string): Promise<void> {
-  const resp = await fetch(url, { method: "POST" });
-  if (!resp.ok) throw new Error(`HTTP ${resp.status}`);
+async function postNoContent(url: string, body?: unknown): Promise<void> {
+  const resp = await fetch(url, body === undefined ? { method: "POST" } : {
+    method: "POST",
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify(body),
+  });
+  if (!resp.ok) await throwApiError(resp);
 }
 
 // --- Public API ---
 
 export async function createTeam(name: string): Promise<NewTeamResponse> {
-  return postJson(`${apiBase()}/api/teams`, { name });
+  return postJson(`${apiBase()}/api/v2/teams`, { name });
 }
 
-export async function getTeam(key: string): Promise<TeamPageResponse> {
-  return fetchJson(`${apiBase()}/api/teams/${key}`);
+// Creates a new team, under new keys, from an archive made by adminExportTeam.
+export async function importTeam(archive: unknown): Promise<NewTeamResponse> {
+  return postJson(`${apiBase()}/api/v2/teams/import`, archive);
 }
 
-export async function playerPlay(teamKey: string, playerId: string): Promise<void> {
-  return postNoContent(`${apiBase()}/api/teams/${teamKey}/players/${playerId}/play`);
+export async function getTeam(key: string): Promise<TeamPageResponse> {
+  return fetchJson(`${apiBase()}/api/v2/teams/${key}`);
 }
 
-export async function playerNotPlay(teamKey: string, playerId: string): Promise<void> {
-  return postNoContent(`${apiBase()}/api/teams/${teamKey}/players/${playerId}/not_play`);
+export async function setRsvp(teamKey: string, playerId: string, status: RsvpStatus, note?: string): Promise<void> {
+  return putJsonNoContent(`${apiBase()}/api/v2/teams/${teamKey}/players/${playerId}/rsvp`, { status, note });
 }
 
 export async function addComment(teamKey: string, comment: string, author?: string): Promise<Comment[]> {
-  return postJson(`${apiBase()}/api/teams/${teamKey}/comments`, { comment, author });
+  return postJson(`${apiBase()}/api/v2/teams/${teamKey}/comments`, { comment, author });
+}
+
+// With a guest limit set, guests nobody brought share one allowance, so pass invitedBy when known.
+export async function addGuest(teamKey: string, guestName: string, invitedBy?: PlayerID): Promise<Guest[]> {
+  const path = invitedBy ? `players/${invitedBy}/guests` : 'guests';
+  return postJson(`${apiBase()}/api/v2/teams/${teamKey}/${path}`, { guest_name: guestName });
 }
 
-export async function addGuest(teamKey: string, guestName: string): Promise<string[]> {
-  return postJson(`${apiBase()}/api/teams/${teamKey}/guests`, { guest_name: guestName });
+export async function pushPublicKey(): Promise<{ public_key: string }> {
+  return fetchJson(`${apiBase()}/api/v2/push/public_key`);
 }
 
-export async function deleteGuest(teamKey: string, idx: number): Promise<void> {
-  return deleteNoContent(`${apiBase()}/api/teams/${teamKey}/guests/${idx}`);
+// Send this team's pushes for the player to a browser subscription, from PushSubscription.toJSON().
+export async function addPushSubscription(teamKey: string, playerId: PlayerID, subscription: PushSubscriptionJSON): Promise<void> {
+  return postNoContent(`${apiBase()}/api/v2/teams/${teamKey}/players/${playerId}/push_subscriptions`, subscription);
+}
+
+export async function removePushSubscription(teamKey: string, playerId: PlayerID, endpoint: string): Promise<void> {
+  return deleteNoContent(`${apiBase()}/api/v2/teams/${teamKey}/players/${playerId}/push_subscriptions`, { endpoint });
+}
+
+export async function deleteGuest(teamKey: string, guestId: string): Promise<void> {
+  return deleteNoContent(`${apiBase()}/api/v2/teams/${teamKey}/guests/${guestId}`);
 }
 
 export async function createGame(teamKey: string, description: string): Promise<Game> {
-  return postJson(`${apiBase()}/api/teams/${teamKey}/new_game`, { description });
+  return postJson(`${apiBase()}/api/v2/teams/${teamKey}/new_game`, { description });
 }
 
 export async function saveSquads(teamKey: string, squads: Record<string, string>, assignments: Record<string, string>): Promise<void> {
-  return putJsonNoContent(`${apiBase()}/api/teams/${teamKey}/squads`, { squads, assignments });
+  return putJsonNoContent(`${apiBase()}/api/v2/teams/${teamKey}/squads`, { squads, assignments });
+}
+
+export async function autoSquads(teamKey: string, seed?: number): Promise<AutoSquadsResponse> {
+  return postJson(`${apiBase()}/api/v2/teams/${teamKey}/squads/auto`, { seed });
+}
+
+// Only works when the team lets players record results.
+export async function recordResults(teamKey: string, gameKey: string, results: GameResults): Promise<TeamPageResponse> {
+  const resp = await fetch(`${apiBase()}/api/v2/teams/${teamKey}/games/${gameKey}/results`, {
+    method: "PUT",
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify(results),
+  });
+  if (!resp.ok) await throwApiError(resp);
+  return resp.json();
+}
+
+export async function castMvpVote(teamKey: string, gameKey: string, playerId: string, candidate: string): Promise<void> {
+  return postNoContent(`${apiBase()}/api/v2/teams/${teamKey}/games/${gameKey}/players/${playerId}/mvp_vote`, { candidate });
+}
+
+export async function requestToJoin(teamKey: string, name: string): Promise<JoinRequest> {
+  return postJson(`${apiBase()}/api/v2/teams/${teamKey}/join_requests`, { name });
+}
+
+export async function getLeaderboard(teamKey: string, season?: number): Promise<LeaderboardResponse> {
+  const query = season != null ? `?season=${season}` : "";
+  return fetchJson(`${apiBase()}/api/v2/teams/${teamKey}/leaderboard${query}`);
+}
+
+// Calls onGame with the current game whenever it changes. Returns a function that stops listening.
+export function watchGame(teamKey: string, onGame: (game: Game | null) => void): () => void {
+  const source = new EventSource(`${apiBase()}/api/v2/teams/${teamKey}/events`);
+  source.addEventListener("game", (e) => onGame(JSON.parse((e as MessageEvent).data)));
+  return () => source.close();
+}
+
+export interface SquadBoardConnection {
+  // Returns false if the socket isn't open, so the caller can fall back to saveSquads.
+  send: (op: SquadOp) => boolean;
+  close: () => void;
+}
+
+// Opens the collaborative squad board socket. The server answers every op with the authoritative board.
+export function connectSquadBoard(teamKey: string, onEvent: (event: SquadBoardEvent) => void): SquadBoardConnection {
+  const url = `${apiBase().replace(/^http/, "ws")}/api/v2/teams/${teamKey}/squads/ws`;
+  const socket = new WebSocket(url);
+  socket.addEventListener("message", (e) => onEvent(JSON.parse(e.data)));
+  return {
+    send: (op) => {
+      if (socket.readyState !== WebSocket.OPEN) return false;
+      socket.send(JSON.stringify(op));
+      return true;
+    },
+    close: () => socket.close(),
+  };
 }
 
 // --- Admin API ---
 
 export async function getAdmin(teamKey: string, teamSecret: string): Promise<TeamPageResponse> {
-  return fetchJson(`${apiBase()}/api/admin/${teamKey}/${teamSecret}`);
+  return fetchJson(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}`);
+}
+
+// The whole team and every game as one JSON document, for backups.
+export async function adminExportTeam(teamKey: string, teamSecret: string): Promise<unknown> {
+  return fetchJson(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/export`);
+}
+
+export async function adminRawDocuments(teamKey: string, teamSecret: string): Promise<RawDocument[]> {
+  return fetchJson(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/raw`);
+}
+
+// Replaces a stored document with a fixed one. Fails if the fix still can't be read.
+export async function adminRepairDocument(teamKey: string, teamSecret: string, kind: "team" | "game", key: string, document: unknown): Promise<void> {
+  return putJsonNoContent(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/raw/${kind}/${key}`, document);
 }
 
 export async function updateSettings(teamKey: string, teamSecret: string, body: unknown): Promise<TeamPageResponse> {
-  const resp = await fetch(`${apiBase()}/api/admin/${teamKey}/${teamSecret}/settings`, {
+  const resp = await fetch(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/settings`, {
     method: "PUT",
     headers: { "Content-Type": "application/json" },
     body: JSON.stringify(body),
   });
-  if (!resp.ok) throw new Error(`HTTP ${resp.status}`);
+  if (!resp.ok) await throwApiError(resp);
   return resp.json();
 }
 
 export async function adminAddPlayers(teamKey: string, teamSecret: string, names: string): Promise<Record<string, string>> {
-  return postJson(`${apiBase()}/api/admin/${teamKey}/${teamSecret}/players`, { names });
+  return postJson(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/players`, { names });
+}
+
+// null or "" clears a profile field.
+export interface PlayerUpdate {
+  name?: string;
+  active?: boolean;
+  position?: string | null;
+  shirt_number?: number | null;
+  phone?: string | null;
+  email?: string | null;
+  notes?: string | null;
+}
+
+export async function adminCreatePlayer(teamKey: string, teamSecret: string, profile: PlayerUpdate & { name: string }): Promise<TeamPageResponse> {
+  return postJson(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/players/new`, profile);
+}
+
+export async function adminUpdatePlayer(teamKey: string, teamSecret: string, playerId: string, update: PlayerUpdate): Promise<TeamPageResponse> {
+  const resp = await fetch(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/players/${playerId}`, {
+    method: "PUT",
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify(update),
+  });
+  if (!resp.ok) await throwApiError(resp);
+  return resp.json();
+}
+
+// A link that downloads the roster as CSV.
+export function adminRosterExportUrl(teamKey: string, teamSecret: string): string {
+  return `${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/players/export`;
+}
+
+// With dryRun, only reports what would be added. Otherwise adds the players unless a row has errors.
+export async function adminImportRoster(teamKey: string, teamSecret: string, csv: string, dryRun: boolean): Promise<RosterImportResponse> {
+  return postJson(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/players/import`, { csv, dry_run: dryRun });
+}
+
+// Folds a duplicate player into another, keeping the other's name.
+export async function adminMergePlayer(teamKey: string, teamSecret: string, playerId: string, into: PlayerID): Promise<TeamPageResponse> {
+  return postJson(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/players/${playerId}/merge`, { into });
 }
 
 export async function adminDeletePlayer(teamKey: string, teamSecret: string, playerId: string): Promise<void> {
-  return deleteNoContent(`${apiBase()}/api/admin/${teamKey}/${teamSecret}/players/${playerId}`);
+  return deleteNoContent(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/players/${playerId}`);
 }
 
 export async function adminResetGame(teamKey: string, teamSecret: string): Promise<void> {
-  return postNoContent(`${apiBase()}/api/admin/${teamKey}/${teamSecret}/reset_game`);
+  return postNoContent(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/reset_game`);
 }
 
 export async function adminToggleGameOff(teamKey: string, teamSecret: string): Promise<void> {
-  return postNoContent(`${apiBase()}/api/admin/${teamKey}/${teamSecret}/game_off`);
+  return postNoContent(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/game_off`);
 }
 
 export async function adminSetDefaultSquads(teamKey: string, teamSecret: string, squads: Record<string, string>): Promise<void> {
-  return putJsonNoContent(`${apiBase()}/api/admin/${teamKey}/${teamSecret}/default_squads`, { squads });
+  return putJsonNoContent(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/default_squads`, { squads });
+}
+
+export interface GameResults {
+  scores?: Record<SquadID, number>;
+  stats?: Record<string, PlayerStats>;
+}
+
+export async function adminRecordResults(teamKey: string, teamSecret: string, gameKey: string, results: GameResults): Promise<TeamPageResponse> {
+  const resp = await fetch(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/games/${gameKey}/results`, {
+    method: "PUT",
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify(results),
+  });
+  if (!resp.ok) await throwApiError(resp);
+  return resp.json();
+}
+
+// true if they came, false if not, null to clear.
+export async function adminCheckIn(teamKey: string, teamSecret: string, attendance: Record<string, boolean | null>): Promise<void> {
+  return putJsonNoContent(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/attendance`, { attendance });
+}
+
+export async function adminNoShows(teamKey: string, teamSecret: string): Promise<NoShowEntry[]> {
+  return fetchJson(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/no_shows`);
+}
+
+export async function adminAddGuest(teamKey: string, teamSecret: string, name: string, invitedBy?: PlayerID): Promise<Guest[]> {
+  const path = invitedBy ? `players/${invitedBy}/guests` : 'guests';
+  return postJson(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/${path}`, { guest_name: name });
+}
+
+export async function adminDeleteGuest(teamKey: string, teamSecret: string, guestId: string): Promise<void> {
+  return deleteNoContent(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/guests/${guestId}`);
+}
+
+// Adds a regular guest to the roster, carrying over their games as a guest.
+export async function adminPromoteGuest(teamKey: string, teamSecret: string, name: string): Promise<PromoteGuestResponse> {
+  return postJson(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/guests/promote`, { name });
+}
+
+export async function adminApproveJoinRequest(teamKey: string, teamSecret: string, requestId: string): Promise<TeamPageResponse> {
+  return postJson(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/join_requests/${requestId}/approve`, {});
+}
+
+export async function adminRejectJoinRequest(teamKey: string, teamSecret: string, requestId: string): Promise<TeamPageResponse> {
+  return postJson(`${apiBase()}/api/v2/admin/${teamKey}/${teamSecret}/join_requests/${requestId}/reject`, {});
 }
@@ -6,14 +6,6 @@ interface Props {
   onAdd: (comment: string, author?: string) => void;
 }
 
-function commentText(c: Comment): string {
-  return typeof c === "string" ? c : c.text;
-}
-
-function commentAuthor(c: Comment): string | undefined {
-  return typeof c === "string" ? undefined : c.author;
-}
-
 export default function Comments(props: Props) {
   const [comment, setComment] = createSignal("");
   const [author, setAuthor] = createSignal("");
@@ -101,12 +93,12 @@ export default function Comments(props: Props) {
         <For each={props.comments}>
           {(c) => (
             <div class="glass-card bg-white/5 border-white/10 p-5 rounded-2xl">
-              <Show when={commentAuthor(c)}>
+              <Show when={c.author}>
                 <span class="text-xs font-semibold text-[var(--accent-cyan)] uppercase tracking-wider">
-                  {commentAuthor(c)}
+                  {c.author}
                 </span>
               </Show>
-              <p class="text-sm text-slate-200 leading-relaxed">{commentText(c)}</p>
+              <p class="text-sm text-slate-200 leading-relaxed">{c.text}</p>
             </div>
           )}
         </For>
@@ -1,21 +1,25 @@
 import { createSignal, Show, For, onCleanup } from "solid-js";
+import type { Guest, PlayerID } from "../types";
 
 interface Props {
-  guests: string[];
-  onAdd: (name: string) => void;
-  onDelete: (idx: number) => void;
+  guests: Guest[];
+  roster: Record<PlayerID, string>;
+  inactive: PlayerID[];
+  onAdd: (name: string, invitedBy?: PlayerID) => void;
+  onDelete: (id: string) => void;
 }
 
 export default function GuestForm(props: Props) {
   const [editing, setEditing] = createSignal(false);
   const [guestName, setGuestName] = createSignal("");
+  const [invitedBy, setInvitedBy] = createSignal("");
   let inputRef!: HTMLInputElement;
   let editingRef!: HTMLSpanElement;
 
   const submit = () => {
     const name = guestName().trim();
     if (!name) return;
-    props.onAdd(name);
+    props.onAdd(name, invitedBy() || undefined);
     setGuestName("");
     inputRef?.focus();
   };
@@ -41,11 +45,14 @@ export default function GuestForm(props: Props) {
       <h3 class="text-sm font-bold text-slate-400 uppercase tracking-widest mb-3">Guest Players</h3>
       <div class="flex flex-wrap gap-2 items-center">
         <For each={props.guests}>
-          {(name, idx) => (
+          {(guest) => (
             <span class="inline-flex items-center gap-1.5 px-3 py-1.5 rounded-full bg-cyan-500/10 border border-cyan-500/20 text-sm font-medium text-slate-100">
-              {name}
+              {guest.name}
+              <Show when={guest.invited_by && props.roster[guest.invited_by]}>
+                {(host) => <span class="text-xs text-slate-400">with {host()}</span>}
+              </Show>
               <button
-                onClick={() => props.onDelete(idx())}
+                onClick={() => props.onDelete(guest.id)}
                 class="p-0.5 bg-rose-500/15 text-rose-300 hover:bg-rose-500/30 hover:text-rose-200 rounded transition-colors cursor-pointer"
               >
                 <i class="ph ph-trash text-xs" />
@@ -76,6 +83,17 @@ export default function GuestForm(props: Props) {
               placeholder="Name"
               class="bg-transparent px-3 py-1.5 text-sm text-white placeholder-slate-400 outline-none w-28"
             />
+            <select
+              value={invitedBy()}
+              onChange={(e) => setInvitedBy(e.currentTarget.value)}
+              class="bg-transparent px-2 py-1.5 text-sm text-slate-300 outline-none cursor-pointer"
+              title="Who's bringing them"
+            >
+              <option value="">Brought by…</option>
+              <For each={Object.entries(props.roster).filter(([pid]) => !props.inactive.includes(pid)).sort((a, b) => a[1].localeCompare(b[1]))}>
+                {([pid, name]) => <option value={pid}>{name}</option>}
+              </For>
+            </select>
             <button
               onClick={submit}
               class="px-2 py-1.5 text-[var(--accent-cyan)] hover:bg-cyan-500/10 transition-colors"
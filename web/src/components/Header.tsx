@@ -16,7 +16,7 @@ function reminderUrl(teamKey: string): string {
   const base = (origin.includes("localhost") || origin.includes("127.0.0.1"))
     ? "http://localhost:8787"
     : "https://nextgame.aquarhead.workers.dev";
-  return `${base}/api/teams/${teamKey}/reminder.ics`;
+  return `${base}/api/v2/teams/${teamKey}/reminder.ics`;
 }
 
 const HINT_KEY = "nextgame_reminder_hint_dismissed";
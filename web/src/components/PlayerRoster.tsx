@@ -1,24 +1,38 @@
-import { Index, createMemo } from "solid-js";
-import type { PlayerID } from "../types";
+import { Index, Show, createMemo } from "solid-js";
+import type { PlayerID, PublicProfile, Rsvp, RsvpStatus } from "../types";
 
 interface Props {
   roster: Record<PlayerID, string>;
-  gamePlayers: Record<PlayerID, boolean | null>;
-  onPlay: (id: string) => void;
-  onNotPlay: (id: string) => void;
+  profiles: Record<PlayerID, PublicProfile>;
+  gamePlayers: Record<PlayerID, Rsvp | null>;
+  onRsvp: (id: string, status: RsvpStatus, note?: string) => void;
 }
 
+const CHOICES: { status: RsvpStatus; label: string; active: string }[] = [
+  { status: "in", label: "Yes", active: "bg-cyan-500 text-white shadow-lg" },
+  { status: "late", label: "Late", active: "bg-emerald-500/80 text-white shadow-lg" },
+  { status: "maybe", label: "Maybe", active: "bg-amber-500/80 text-white shadow-lg" },
+  { status: "out", label: "No", active: "bg-rose-500/80 text-white shadow-lg" },
+];
+
 export default function PlayerRoster(props: Props) {
   const sortedPlayers = createMemo(() => {
     return Object.entries(props.gamePlayers)
-      .map(([pid, playing]) => ({
+      .map(([pid, rsvp]) => ({
         id: pid,
         name: props.roster[pid] ?? "Unknown",
-        playing,
+        rsvp,
+        profile: props.profiles[pid],
       }))
       .sort((a, b) => a.name.localeCompare(b.name));
   });
 
+  const editNote = (id: string, rsvp: Rsvp | null) => {
+    const note = window.prompt("Add a note, e.g. arriving 15 min late", rsvp?.note ?? "");
+    if (note === null) return;
+    props.onRsvp(id, rsvp?.status ?? "in", note.trim());
+  };
+
   return (
     <div>
       <div class="overflow-x-auto">
@@ -34,28 +48,39 @@ export default function PlayerRoster(props: Props) {
               {(player) => (
                 <tr class="group transition-colors hover:bg-white/5">
                   <td class="py-5 px-2">
+                    <Show when={player().profile?.shirt_number != null}>
+                      <span class="mr-2 text-sm font-bold text-[var(--accent-cyan)]">#{player().profile?.shirt_number}</span>
+                    </Show>
                     <span class="font-semibold text-lg text-slate-100">{player().name}</span>
+                    <Show when={player().profile?.position}>
+                      <span class="ml-2 text-xs text-slate-400 uppercase tracking-wider">{player().profile?.position}</span>
+                    </Show>
+                    <Show when={player().rsvp?.note}>
+                      <p class="text-sm text-slate-400 italic">{player().rsvp?.note}</p>
+                    </Show>
                   </td>
                   <td class="py-5 px-2 text-right">
-                    <div class="inline-flex p-1 bg-black/30 rounded-xl border border-white/10">
-                      <button
-                        onClick={() => props.onPlay(player().id)}
-                        class={`px-5 py-2 rounded-lg text-sm font-bold transition-all ${player().playing === true
-                            ? "bg-cyan-500 text-white shadow-lg"
-                            : "text-slate-500 hover:text-slate-300"
-                          }`}
-                      >
-                        Yes
-                      </button>
+                    <div class="inline-flex items-center gap-2">
                       <button
-                        onClick={() => props.onNotPlay(player().id)}
-                        class={`px-5 py-2 rounded-lg text-sm font-bold transition-all ${player().playing === false
-                            ? "bg-rose-500/80 text-white shadow-lg"
-                            : "text-slate-500 hover:text-slate-300"
-                          }`}
+                        onClick={() => editNote(player().id, player().rsvp)}
+                        class="p-2 text-slate-500 hover:text-slate-300 transition-colors cursor-pointer"
+                        title="Add a note"
                       >
-                        No
+                        <i class="ph ph-note-pencil" />
                       </button>
+                      <div class="inline-flex p-1 bg-black/30 rounded-xl border border-white/10">
+                        {CHOICES.map((c) => (
+                          <button
+                            onClick={() => props.onRsvp(player().id, c.status)}
+                            class={`px-4 py-2 rounded-lg text-sm font-bold transition-all ${player().rsvp?.status === c.status
+                                ? c.active
+                                : "text-slate-500 hover:text-slate-300"
+                              }`}
+                          >
+                            {c.label}
+                          </button>
+                        ))}
+                      </div>
                     </div>
                   </td>
                 </tr>
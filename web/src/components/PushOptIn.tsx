@@ -0,0 +1,130 @@
+import { createSignal, For, Show } from "solid-js";
+import type { PlayerID } from "../types";
+import * as api from "../api";
+
+interface Props {
+  teamKey: string;
+  roster: Record<PlayerID, string>;
+}
+
+// What this browser subscribed for the team, so it can be turned off again.
+interface Saved {
+  playerId: PlayerID;
+  endpoint: string;
+}
+
+const supported = "serviceWorker" in navigator && "PushManager" in window && "Notification" in window;
+
+function storageKey(teamKey: string): string {
+  return `nextgame:push:${teamKey}`;
+}
+
+// The VAPID key comes base64url-encoded; subscribe() wants the raw bytes.
+function keyBytes(key: string): Uint8Array {
+  const b64 = key.replace(/-/g, "+").replace(/_/g, "/");
+  const raw = atob(b64 + "=".repeat((4 - (b64.length % 4)) % 4));
+  return Uint8Array.from(raw, (c) => c.charCodeAt(0));
+}
+
+export default function PushOptIn(props: Props) {
+  const load = (): Saved | null => JSON.parse(localStorage.getItem(storageKey(props.teamKey)) ?? "null");
+  const [saved, setSaved] = createSignal<Saved | null>(load());
+  const [playerId, setPlayerId] = createSignal<PlayerID>("");
+  const [busy, setBusy] = createSignal(false);
+  const [error, setError] = createSignal<string | null>(null);
+
+  const players = () => Object.entries(props.roster).sort(([, a], [, b]) => a.localeCompare(b));
+
+  const enable = async () => {
+    const pid = playerId();
+    if (!pid) return;
+    setBusy(true);
+    setError(null);
+    try {
+      if ((await Notification.requestPermission()) !== "granted") {
+        throw new Error("Notifications are blocked for this site");
+      }
+      const reg = await navigator.serviceWorker.register("/sw.js");
+      await navigator.serviceWorker.ready;
+      const { public_key } = await api.pushPublicKey();
+      const sub = await reg.pushManager.subscribe({ userVisibleOnly: true, applicationServerKey: keyBytes(public_key) });
+      await api.addPushSubscription(props.teamKey, pid, sub.toJSON());
+      const next = { playerId: pid, endpoint: sub.endpoint };
+      localStorage.setItem(storageKey(props.teamKey), JSON.stringify(next));
+      setSaved(next);
+    } catch (e: any) {
+      setError(e.message || "Couldn't turn on notifications");
+    }
+    setBusy(false);
+  };
+
+  const disable = async () => {
+    const s = saved();
+    if (!s) return;
+    setBusy(true);
+    setError(null);
+    try {
+      // The browser's subscription is shared with other teams, so only this team forgets it.
+      await api.removePushSubscription(props.teamKey, s.playerId, s.endpoint);
+      localStorage.removeItem(storageKey(props.teamKey));
+      setSaved(null);
+    } catch (e: any) {
+      setError(e.message || "Couldn't turn off notifications");
+    }
+    setBusy(false);
+  };
+
+  return (
+    <Show when={supported}>
+      <div class="glass-card rounded-[2rem] p-8 space-y-4">
+        <h2 class="text-2xl font-bold flex items-center gap-3">
+          <i class="ph ph-bell-ringing text-[var(--accent-cyan)]" />
+          Notifications
+        </h2>
+        <Show
+          when={saved()}
+          fallback={
+            <>
+              <p class="text-sm text-slate-400">Get a notification on this device when a game is set up, called off, or about to start.</p>
+              <div class="flex gap-2">
+                <select
+                  value={playerId()}
+                  onChange={(e) => setPlayerId(e.currentTarget.value)}
+                  class="flex-1 bg-white/5 border border-white/10 rounded-xl px-3 py-2 text-sm"
+                >
+                  <option value="">Who are you?</option>
+                  <For each={players()}>{([pid, name]) => <option value={pid}>{name}</option>}</For>
+                </select>
+                <button
+                  onClick={enable}
+                  disabled={busy() || !playerId()}
+                  class="px-4 py-2 bg-[var(--accent-cyan)]/15 text-[var(--accent-cyan)] hover:bg-[var(--accent-cyan)]/25 rounded-xl text-sm font-bold transition-all cursor-pointer disabled:opacity-50"
+                >
+                  Turn on
+                </button>
+              </div>
+            </>
+          }
+        >
+          {(s) => (
+            <div class="flex items-center justify-between gap-3">
+              <p class="text-sm text-slate-300">
+                On for <span class="font-bold text-white">{props.roster[s().playerId] ?? "a former player"}</span> on this device.
+              </p>
+              <button
+                onClick={disable}
+                disabled={busy()}
+                class="px-4 py-2 bg-white/5 hover:bg-white/10 rounded-xl text-sm font-bold transition-all cursor-pointer disabled:opacity-50"
+              >
+                Turn off
+              </button>
+            </div>
+          )}
+        </Show>
+        <Show when={error()}>
+          <p class="text-sm text-[var(--accent-danger)]">{error()}</p>
+        </Show>
+      </div>
+    </Show>
+  );
+}
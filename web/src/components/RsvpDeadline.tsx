@@ -0,0 +1,30 @@
+import { Show, createSignal, onCleanup } from "solid-js";
+
+interface Props {
+  deadline: string;
+}
+
+function remaining(ms: number): string {
+  const minutes = Math.floor(ms / 60000);
+  const days = Math.floor(minutes / 1440);
+  const hours = Math.floor((minutes % 1440) / 60);
+  if (days > 0) return `${days}d ${hours}h`;
+  return `${hours}h ${minutes % 60}m`;
+}
+
+export default function RsvpDeadline(props: Props) {
+  const [now, setNow] = createSignal(Date.now());
+  const timer = setInterval(() => setNow(Date.now()), 30000);
+  onCleanup(() => clearInterval(timer));
+
+  const left = () => new Date(props.deadline).getTime() - now();
+
+  return (
+    <div class="glass-card px-5 py-3 rounded-2xl flex items-center gap-3 mb-6 text-sm">
+      <i class="ph ph-hourglass-medium text-lg text-amber-400" />
+      <Show when={left() > 0} fallback={<span class="text-slate-300">RSVPs are closed. Ask an admin to make changes.</span>}>
+        <span class="text-slate-300">RSVPs close in <span class="font-bold text-white">{remaining(left())}</span></span>
+      </Show>
+    </div>
+  );
+}
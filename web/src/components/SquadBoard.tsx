@@ -1,16 +1,18 @@
 import { For, Show, createSignal, createMemo, onCleanup } from "solid-js";
-import type { PlayerID, SquadID } from "../types";
+import { isPlaying } from "../types";
+import type { Guest, PlayerID, Rsvp, SquadID } from "../types";
 
 interface Props {
   roster: Record<PlayerID, string>;
-  gamePlayers: Record<PlayerID, boolean | null>;
-  guests: string[];
+  gamePlayers: Record<PlayerID, Rsvp | null>;
+  guests: Guest[];
   squads: Record<SquadID, string>;
   assignments: Record<string, SquadID>;
   onDrop: (squadId: string, playerId: string) => void;
   onAddSquad: (name: string) => void;
   onDeleteSquad: (squadId: string) => void;
   onRandomAssign: (assignments: Record<string, string>) => void;
+  onBalance: () => void;
 }
 
 const TEAM_COLORS = [
@@ -49,16 +51,16 @@ export default function SquadBoard(props: Props) {
   // All assignable people: playing players + guests
   const allPeople = createMemo(() => {
     const players = Object.entries(props.gamePlayers)
-      .filter(([, playing]) => playing === true)
+      .filter(([, rsvp]) => isPlaying(rsvp))
       .map(([pid]) => ({
         id: pid,
         name: props.roster[pid] ?? "Unknown",
         isGuest: false,
       }));
 
-    const guests = props.guests.map((name, idx) => ({
-      id: `guest:${idx}`,
-      name,
+    const guests = props.guests.map((guest) => ({
+      id: `guest:${guest.id}`,
+      name: guest.name,
       isGuest: true,
     }));
 
@@ -149,13 +151,22 @@ export default function SquadBoard(props: Props) {
       <div class="mb-8 flex items-center justify-between">
         <p class="text-slate-400">Drag players into their respective squads.</p>
         <Show when={allPeople().length > 0 && squadEntries().length > 0}>
-          <button
-            onClick={handleRandomAssign}
-            class="flex items-center gap-2 px-4 py-2 bg-[var(--accent-cyan)]/15 text-[var(--accent-cyan)] hover:bg-[var(--accent-cyan)]/25 rounded-xl text-sm font-bold transition-all cursor-pointer"
-          >
-            <i class="ph ph-shuffle" />
-            Randomly Assign
-          </button>
+          <div class="flex items-center gap-2">
+            <button
+              onClick={() => props.onBalance()}
+              class="flex items-center gap-2 px-4 py-2 bg-[var(--accent-cyan)]/15 text-[var(--accent-cyan)] hover:bg-[var(--accent-cyan)]/25 rounded-xl text-sm font-bold transition-all cursor-pointer"
+            >
+              <i class="ph ph-scales" />
+              Balance
+            </button>
+            <button
+              onClick={handleRandomAssign}
+              class="flex items-center gap-2 px-4 py-2 bg-[var(--accent-cyan)]/15 text-[var(--accent-cyan)] hover:bg-[var(--accent-cyan)]/25 rounded-xl text-sm font-bold transition-all cursor-pointer"
+            >
+              <i class="ph ph-shuffle" />
+              Randomly Assign
+            </button>
+          </div>
         </Show>
       </div>
 
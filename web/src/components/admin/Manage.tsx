@@ -27,6 +27,18 @@ export default function AdminControls(props: Props) {
     } catch { }
   };
 
+  const exportTeam = async () => {
+    try {
+      const archive = await api.adminExportTeam(props.teamKey, props.teamSecret);
+      const url = URL.createObjectURL(new Blob([JSON.stringify(archive)], { type: "application/json" }));
+      const a = document.createElement("a");
+      a.href = url;
+      a.download = `nextgame-${props.teamKey}.json`;
+      a.click();
+      URL.revokeObjectURL(url);
+    } catch { }
+  };
+
   return (
     <div class="glass-card p-8 rounded-[2.5rem] space-y-6">
       <div class="flex items-center gap-3 border-b border-white/10 pb-4">
@@ -74,6 +86,14 @@ export default function AdminControls(props: Props) {
           <span class="font-bold text-white text-sm">Reset</span>
         </button>
       </div>
+
+      <button
+        onClick={exportTeam}
+        class="text-sm text-slate-400 hover:text-slate-200 transition-colors"
+      >
+        <i class="ph ph-download-simple mr-2" />
+        Download a backup of the team and all its games
+      </button>
     </div>
   );
 }
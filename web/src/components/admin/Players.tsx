@@ -1,4 +1,4 @@
-import { createSignal, For, createMemo } from "solid-js";
+import { createSignal, For, Show, createMemo } from "solid-js";
 import type { TeamPageResponse } from "../../types";
 import * as api from "../../api";
 
@@ -30,6 +30,52 @@ export default function AdminPlayers(props: Props) {
     setLoading(false);
   };
 
+  const isInactive = (playerId: string) => props.data.inactive_players.includes(playerId);
+
+  const renamePlayer = async (playerId: string, current: string) => {
+    const name = window.prompt("Rename player", current)?.trim();
+    if (!name || name === current) return;
+    try {
+      await api.adminUpdatePlayer(props.teamKey, props.teamSecret, playerId, { name });
+      props.onRefresh();
+    } catch { }
+  };
+
+  const toggleActive = async (playerId: string) => {
+    try {
+      await api.adminUpdatePlayer(props.teamKey, props.teamSecret, playerId, { active: isInactive(playerId) });
+      props.onRefresh();
+    } catch { }
+  };
+
+  const [editing, setEditing] = createSignal<string | null>(null);
+
+  const saveProfile = async (playerId: string, form: HTMLFormElement) => {
+    const field = (name: string) => (form.elements.namedItem(name) as HTMLInputElement).value.trim();
+    const shirt = field("shirt_number");
+    try {
+      await api.adminUpdatePlayer(props.teamKey, props.teamSecret, playerId, {
+        position: field("position"),
+        shirt_number: shirt ? Number(shirt) : null,
+        phone: field("phone"),
+        email: field("email"),
+        notes: field("notes"),
+      });
+      setEditing(null);
+      props.onRefresh();
+    } catch { }
+  };
+
+  const mergePlayer = async (playerId: string, into: string) => {
+    if (!into) return;
+    if (!window.confirm(`Merge ${props.data.players[playerId]} into ${props.data.players[into]}? This can't be undone.`)) return;
+    try {
+      await api.adminMergePlayer(props.teamKey, props.teamSecret, playerId, into);
+      setEditing(null);
+      props.onRefresh();
+    } catch { }
+  };
+
   const deletePlayer = async (playerId: string) => {
     try {
       await api.adminDeletePlayer(props.teamKey, props.teamSecret, playerId);
@@ -75,16 +121,38 @@ export default function AdminPlayers(props: Props) {
           <tbody class="divide-y divide-white/5">
             <For each={sortedPlayers()}>
               {([pid, name]) => (
+                <>
                 <tr class="hover:bg-white/5 transition-colors">
                   <td class="px-6 py-4">
                     <div class="flex items-center gap-3">
                       <div class="w-8 h-8 rounded-lg bg-white/10 flex items-center justify-center text-[10px] font-bold text-[var(--accent-cyan)]">
                         {name.charAt(0)}
                       </div>
-                      <span class="font-medium">{name}</span>
+                      <span class={`font-medium ${isInactive(pid) ? "text-slate-500 line-through" : ""}`}>{name}</span>
                     </div>
                   </td>
-                  <td class="px-6 py-4 text-right">
+                  <td class="px-6 py-4 text-right space-x-2">
+                    <button
+                      onClick={() => setEditing(editing() === pid ? null : pid)}
+                      class="p-2 bg-white/5 text-slate-300 hover:bg-white/10 rounded-lg transition-colors cursor-pointer"
+                      title="Profile"
+                    >
+                      <i class="ph ph-identification-card" />
+                    </button>
+                    <button
+                      onClick={() => renamePlayer(pid, name)}
+                      class="p-2 bg-white/5 text-slate-300 hover:bg-white/10 rounded-lg transition-colors cursor-pointer"
+                      title="Rename"
+                    >
+                      <i class="ph ph-pencil-simple" />
+                    </button>
+                    <button
+                      onClick={() => toggleActive(pid)}
+                      class="p-2 bg-white/5 text-slate-300 hover:bg-white/10 rounded-lg transition-colors cursor-pointer"
+                      title={isInactive(pid) ? "Reactivate" : "Deactivate"}
+                    >
+                      <i class={`ph ${isInactive(pid) ? "ph-user-check" : "ph-user-minus"}`} />
+                    </button>
                     <button
                       onClick={() => deletePlayer(pid)}
                       class="p-2 bg-rose-500/15 text-rose-300 hover:bg-rose-500/30 hover:text-rose-200 rounded-lg transition-colors cursor-pointer"
@@ -93,6 +161,36 @@ export default function AdminPlayers(props: Props) {
                     </button>
                   </td>
                 </tr>
+                <Show when={editing() === pid}>
+                  <tr>
+                    <td colSpan={2} class="px-6 py-4 bg-white/5">
+                      <form
+                        onSubmit={(e) => { e.preventDefault(); saveProfile(pid, e.currentTarget); }}
+                        class="grid grid-cols-2 gap-3"
+                      >
+                        <input name="position" value={props.data.player_details?.[pid]?.position ?? ""} placeholder="Position" class="glass-input rounded-xl px-4 py-2 text-white placeholder-slate-400" />
+                        <input name="shirt_number" type="number" min="0" max="99" value={props.data.player_details?.[pid]?.shirt_number ?? ""} placeholder="Shirt number" class="glass-input rounded-xl px-4 py-2 text-white placeholder-slate-400" />
+                        <input name="phone" type="tel" value={props.data.player_details?.[pid]?.phone ?? ""} placeholder="Phone (admins only)" class="glass-input rounded-xl px-4 py-2 text-white placeholder-slate-400" />
+                        <input name="email" type="email" value={props.data.player_details?.[pid]?.email ?? ""} placeholder="Email (admins only)" class="glass-input rounded-xl px-4 py-2 text-white placeholder-slate-400" />
+                        <input name="notes" value={props.data.player_details?.[pid]?.notes ?? ""} placeholder="Notes (admins only)" class="col-span-2 glass-input rounded-xl px-4 py-2 text-white placeholder-slate-400" />
+                        <button type="submit" class="col-span-2 py-2 bg-[var(--accent-turquoise)] hover:brightness-110 text-black font-bold rounded-xl transition-all">
+                          Save
+                        </button>
+                      </form>
+                      <select
+                        onChange={(e) => mergePlayer(pid, e.currentTarget.value)}
+                        class="mt-3 w-full glass-input rounded-xl px-4 py-2 text-slate-300 cursor-pointer"
+                        title="Fold this duplicate into another player"
+                      >
+                        <option value="">Merge into…</option>
+                        <For each={sortedPlayers().filter(([other]) => other !== pid)}>
+                          {([other, otherName]) => <option value={other}>{otherName}</option>}
+                        </For>
+                      </select>
+                    </td>
+                  </tr>
+                </Show>
+                </>
               )}
             </For>
           </tbody>
@@ -0,0 +1,93 @@
+import { createSignal, For, Show } from "solid-js";
+import type { RosterImportResponse } from "../../types";
+import * as api from "../../api";
+
+interface Props {
+  teamKey: string;
+  teamSecret: string;
+  onRefresh: () => void;
+}
+
+export default function RosterCsv(props: Props) {
+  const [loading, setLoading] = createSignal(false);
+  const [csv, setCsv] = createSignal("");
+  const [result, setResult] = createSignal<RosterImportResponse | null>(null);
+  const [error, setError] = createSignal<string | null>(null);
+
+  const loadFile = async (e: Event) => {
+    const file = (e.currentTarget as HTMLInputElement).files?.[0];
+    if (!file) return;
+    setCsv(await file.text());
+    await run(true);
+  };
+
+  const run = async (dryRun: boolean) => {
+    if (!csv().trim()) return;
+    setLoading(true);
+    setError(null);
+    try {
+      const r = await api.adminImportRoster(props.teamKey, props.teamSecret, csv(), dryRun);
+      setResult(r);
+      if (r.imported) {
+        setCsv("");
+        props.onRefresh();
+      }
+    } catch (e: any) {
+      setResult(null);
+      setError(e.message || "Failed to read CSV");
+    }
+    setLoading(false);
+  };
+
+  return (
+    <div class="glass-card p-8 rounded-[2.5rem] space-y-6">
+      <div class="flex items-center justify-between border-b border-white/10 pb-4">
+        <div class="flex items-center gap-3">
+          <i class="ph ph-file-csv text-2xl text-[var(--accent-turquoise)]" />
+          <h2 class="text-2xl font-bold">Import / Export</h2>
+        </div>
+        <a
+          href={api.adminRosterExportUrl(props.teamKey, props.teamSecret)}
+          class="px-4 py-2 bg-white/5 hover:bg-white/10 text-slate-300 rounded-xl transition-colors text-sm font-semibold"
+        >
+          <i class="ph ph-download-simple mr-2" />
+          Export CSV
+        </a>
+      </div>
+
+      <p class="text-sm text-slate-400">
+        Columns: name, position, shirt_number, phone, email, notes, active. Only name is required.
+      </p>
+      <input type="file" accept=".csv,text/csv" onChange={loadFile} class="text-sm text-slate-400" />
+
+      <Show when={result()}>
+        {(r) => (
+          <div class="space-y-2 text-sm">
+            <p class="text-slate-200">
+              {r().imported ? "Added" : "Will add"} {r().added.length} player{r().added.length === 1 ? "" : "s"}
+              <Show when={r().duplicates.length}>, skipping {r().duplicates.length} duplicate{r().duplicates.length === 1 ? "" : "s"}</Show>
+            </p>
+            <For each={r().duplicates}>
+              {(d) => <p class="text-amber-300">Line {d.line}: {d.name} is {d.player_id ? "already on the roster" : "listed twice"}</p>}
+            </For>
+            <For each={r().errors}>
+              {(e) => <p class="text-rose-300">Line {e.line}: {e.message}</p>}
+            </For>
+            <Show when={!r().imported && r().added.length && !r().errors.length}>
+              <button
+                onClick={() => run(false)}
+                disabled={loading()}
+                class="px-6 py-2 bg-[var(--accent-turquoise)] hover:brightness-110 text-black font-bold rounded-xl transition-all disabled:opacity-50"
+              >
+                Import
+              </button>
+            </Show>
+          </div>
+        )}
+      </Show>
+      <Show when={error()}>
+        <p class="text-sm text-[var(--accent-danger)]">{error()}</p>
+      </Show>
+    </div>
+  );
+}
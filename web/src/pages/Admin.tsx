@@ -6,6 +6,7 @@ import AdminSchedule from "../components/admin/Schedule";
 import AdminPlayers from "../components/admin/Players";
 import AdminManage from "../components/admin/Manage";
 import DefaultSquads from "../components/admin/DefaultSquads";
+import RosterCsv from "../components/admin/RosterCsv";
 
 export default function Admin() {
   const params = useParams<{ key: string; secret: string }>();
@@ -128,6 +129,11 @@ export default function Admin() {
                 data={d()}
                 onRefresh={refresh}
               />
+              <RosterCsv
+                teamKey={params.key}
+                teamSecret={params.secret}
+                onRefresh={refresh}
+              />
               <DefaultSquads
                 teamKey={params.key}
                 teamSecret={params.secret}
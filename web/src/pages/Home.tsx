@@ -39,6 +39,26 @@ export default function Home() {
     setLoading(false);
   };
 
+  const onRestore = async (e: Event) => {
+    const file = (e.currentTarget as HTMLInputElement).files?.[0];
+    if (!file) return;
+    setLoading(true);
+    setError(null);
+    try {
+      const archive = JSON.parse(await file.text());
+      const resp = await api.importTeam(archive);
+      const origin = window.location.origin;
+      setResult({
+        name: archive.team?.name ?? "Imported team",
+        teamLink: `${origin}/team/${resp.team_key}`,
+        adminLink: `${origin}/admin/${resp.team_key}/${resp.team_secret}`,
+      });
+    } catch (e: any) {
+      setError(e.message || "Failed to restore team");
+    }
+    setLoading(false);
+  };
+
   return (
     <div class="flex items-center justify-center min-h-screen px-6">
       <div class="glass-card rounded-[2.5rem] p-10 max-w-lg w-full">
@@ -70,6 +90,10 @@ export default function Home() {
             >
               {loading() ? "Creating..." : "Create Team"}
             </button>
+            <label class="block text-center text-sm text-slate-500 hover:text-slate-300 cursor-pointer transition-colors">
+              <input type="file" accept=".json,application/json" onChange={onRestore} class="hidden" />
+              or restore a team from a backup
+            </label>
           </form>
         }>
           {(r) => (
@@ -1,6 +1,7 @@
-import { createSignal, createMemo, onMount, Show } from "solid-js";
+import { createSignal, createMemo, onCleanup, onMount, Show } from "solid-js";
 import { useParams } from "@solidjs/router";
-import type { TeamPageResponse, Comment } from "../types";
+import { isPlaying } from "../types";
+import type { TeamPageResponse, Comment, Game, Guest, SquadOp, Rsvp, RsvpStatus } from "../types";
 import * as api from "../api";
 import Header from "../components/Header";
 import PlayerRoster from "../components/PlayerRoster";
@@ -10,6 +11,8 @@ import Comments from "../components/Comments";
 import GameOff from "../components/GameOff";
 import NewGameForm from "../components/NewGameForm";
 import Description from "../components/Description";
+import RsvpDeadline from "../components/RsvpDeadline";
+import PushOptIn from "../components/PushOptIn";
 
 export default function Team() {
   const params = useParams<{ key: string }>();
@@ -18,46 +21,71 @@ export default function Team() {
   const [activeTab, setActiveTab] = createSignal<"registration" | "squads">("registration");
 
   // Reactive game state lifted into signals
-  const [gamePlayers, setGamePlayers] = createSignal<Record<string, boolean | null>>({});
-  const [guests, setGuests] = createSignal<string[]>([]);
+  const [gamePlayers, setGamePlayers] = createSignal<Record<string, Rsvp | null>>({});
+  const [guests, setGuests] = createSignal<Guest[]>([]);
   const [comments, setComments] = createSignal<Comment[]>([]);
   const [squads, setSquads] = createSignal<Record<string, string>>({});
   const [squadAssignments, setSquadAssignments] = createSignal<Record<string, string>>({});
 
+  const applyGame = (game: Game) => {
+    setGamePlayers(game.players);
+    setGuests(game.guests);
+    setComments(game.comments);
+    setSquads(game.squads);
+    setSquadAssignments(game.squad_assignments);
+  };
+
+  let stopWatching: (() => void) | undefined;
+  let squadBoard: api.SquadBoardConnection | undefined;
+
+  const connectSquadBoard = () => {
+    squadBoard?.close();
+    squadBoard = api.connectSquadBoard(params.key, (event) => {
+      if (event.type === "state") {
+        setSquads(event.squads);
+        setSquadAssignments(event.assignments);
+      }
+    });
+  };
+
+  onCleanup(() => {
+    stopWatching?.();
+    squadBoard?.close();
+  });
+
   onMount(async () => {
     try {
       const d = await api.getTeam(params.key);
       setData(d);
       document.title = `nextgame / ${d.team_name}`;
-      if (d.game) {
-        setGamePlayers(d.game.players);
-        setGuests(d.game.guests);
-        setComments(d.game.comments);
-        setSquads(d.game.squads);
-        setSquadAssignments(d.game.squad_assignments);
-      }
+      if (d.game) applyGame(d.game);
     } catch (e: any) {
       setError(e.message || "Failed to load team");
+      return;
     }
+
+    // Keep the page live as others sign up, comment or shuffle squads.
+    stopWatching = api.watchGame(params.key, (game) => {
+      setData((prev) => (prev ? { ...prev, game } : prev));
+      if (game) applyGame(game);
+    });
+
+    if (data()?.game) connectSquadBoard();
   });
 
   const playingCount = createMemo(() =>
-    Object.values(gamePlayers()).filter((v) => v === true).length
+    Object.values(gamePlayers()).filter(isPlaying).length
   );
 
   const guestCount = createMemo(() => guests().length);
 
-  const handlePlay = async (playerId: string) => {
+  const handleRsvp = async (playerId: string, status: RsvpStatus, note?: string) => {
     try {
-      await api.playerPlay(params.key, playerId);
-      setGamePlayers((prev) => ({ ...prev, [playerId]: true }));
-    } catch { }
-  };
-
-  const handleNotPlay = async (playerId: string) => {
-    try {
-      await api.playerNotPlay(params.key, playerId);
-      setGamePlayers((prev) => ({ ...prev, [playerId]: false }));
+      await api.setRsvp(params.key, playerId, status, note);
+      setGamePlayers((prev) => ({
+        ...prev,
+        [playerId]: { status, note: note === undefined ? prev[playerId]?.note : note || undefined },
+      }));
     } catch { }
   };
 
@@ -68,17 +96,17 @@ export default function Team() {
     } catch { }
   };
 
-  const handleAddGuest = async (name: string) => {
+  const handleAddGuest = async (name: string, invitedBy?: string) => {
     try {
-      const newGuests = await api.addGuest(params.key, name);
+      const newGuests = await api.addGuest(params.key, name, invitedBy);
       setGuests(newGuests);
     } catch { }
   };
 
-  const handleDeleteGuest = async (idx: number) => {
+  const handleDeleteGuest = async (id: string) => {
     try {
-      await api.deleteGuest(params.key, idx);
-      setGuests((prev) => prev.filter((_, i) => i !== idx));
+      await api.deleteGuest(params.key, id);
+      setGuests((prev) => prev.filter((g) => g.id !== id));
     } catch { }
   };
 
@@ -87,6 +115,8 @@ export default function Team() {
     // Remove assignment if dropping to unassigned (empty squadId)
     if (!squadId) delete newAssignments[playerId];
     setSquadAssignments(newAssignments);
+    const op: SquadOp = squadId ? { op: "assign", player: playerId, squad: squadId } : { op: "unassign", player: playerId };
+    if (squadBoard?.send(op)) return;
     try {
       await api.saveSquads(params.key, squads(), newAssignments);
     } catch { }
@@ -96,6 +126,7 @@ export default function Team() {
     const id = crypto.randomUUID().slice(0, 8);
     const newSquads = { ...squads(), [id]: name };
     setSquads(newSquads);
+    if (squadBoard?.send({ op: "add_squad", squad: id, name })) return;
     try {
       await api.saveSquads(params.key, newSquads, squadAssignments());
     } catch { }
@@ -111,6 +142,7 @@ export default function Team() {
     }
     setSquads(newSquads);
     setSquadAssignments(newAssignments);
+    if (squadBoard?.send({ op: "remove_squad", squad: squadId })) return;
     try {
       await api.saveSquads(params.key, newSquads, newAssignments);
     } catch { }
@@ -123,9 +155,18 @@ export default function Team() {
     } catch { }
   };
 
+  const handleBalance = async () => {
+    try {
+      const result = await api.autoSquads(params.key);
+      setSquads(result.squads);
+      setSquadAssignments(result.assignments);
+    } catch { }
+  };
+
   const handleGameCreated = (d: TeamPageResponse) => {
     setData(d);
     if (d.game) {
+      connectSquadBoard();
       setGamePlayers(d.game.players);
       setGuests(d.game.guests);
       setComments(d.game.comments);
@@ -166,6 +207,10 @@ export default function Team() {
                   <GameOff />
                 </Show>
 
+                <Show when={game() && !game()!.is_game_off && d().rsvp_deadline}>
+                  {(deadline) => <RsvpDeadline deadline={deadline()} />}
+                </Show>
+
                 <Show when={game()} fallback={
                   <NewGameForm teamKey={params.key} onCreated={handleGameCreated} />
                 }>
@@ -203,12 +248,14 @@ export default function Team() {
                               <Show when={activeTab() === "registration"}>
                                 <PlayerRoster
                                   roster={d().players}
+                                  profiles={d().profiles}
                                   gamePlayers={gamePlayers()}
-                                  onPlay={handlePlay}
-                                  onNotPlay={handleNotPlay}
+                                  onRsvp={handleRsvp}
                                 />
                                 <GuestForm
                                   guests={guests()}
+                                  roster={d().players}
+                                  inactive={d().inactive_players}
                                   onAdd={handleAddGuest}
                                   onDelete={handleDeleteGuest}
                                 />
@@ -224,6 +271,7 @@ export default function Team() {
                                   onAddSquad={handleAddSquad}
                                   onDeleteSquad={handleDeleteSquad}
                                   onRandomAssign={handleRandomAssign}
+                                  onBalance={handleBalance}
                                 />
                               </Show>
                             </div>
@@ -239,6 +287,7 @@ export default function Team() {
                             comments={comments()}
                             onAdd={handleAddComment}
                           />
+                          <PushOptIn teamKey={params.key} roster={d().players} />
                         </aside>
                       </main>
                     </>
@@ -1,22 +1,81 @@
-// Mirrors shared/src/lib.rs types
+// Mirrors api/src/types.rs. api/openapi.json (also served at /api/openapi.json) describes every endpoint.
 
 export type PlayerID = string;
 export type SquadID = string;
 
 // A comment is either a legacy plain string or an object with optional author.
-export type Comment = string | { text: string; author?: string };
+export interface Comment {
+  text: string;
+  author?: string;
+  // Written before comments had authors.
+  legacy?: boolean;
+}
 
 export interface Game {
   description: string;
-  players: Record<PlayerID, boolean | null>;
-  guests: string[];
+  // null until the player answers.
+  players: Record<PlayerID, Rsvp | null>;
+  guests: Guest[];
   comments: Comment[];
   date: string | null;
   squads: Record<SquadID, string>;
   squad_assignments: Record<PlayerID, SquadID>;
   is_game_off: boolean;
+  squad_scores?: Record<SquadID, number>;
+  player_stats?: Record<string, PlayerStats>;
+  // Votes per candidate. Candidates are squad board ids, so guests can win too.
+  mvp_tally?: Record<string, number>;
+  // Set once voting closes.
+  mvp?: string[] | null;
+  // Who actually turned up, from the admin's check-in.
+  attendance?: Record<string, boolean>;
+}
+
+export interface Guest {
+  id: string;
+  name: string;
+  invited_by: PlayerID | null;
+}
+
+export type RsvpStatus = "in" | "out" | "maybe" | "late";
+
+export interface Rsvp {
+  status: RsvpStatus;
+  note?: string;
+}
+
+// Whether they're coming, even if late.
+export function isPlaying(rsvp: Rsvp | null | undefined): boolean {
+  return rsvp?.status === "in" || rsvp?.status === "late";
+}
+
+export interface RsvpCounts {
+  in: number;
+  late: number;
+  maybe: number;
+  out: number;
+  no_answer: number;
+  guests: number;
+  playing: number;
 }
 
+export interface PlayerStats {
+  goals: number;
+  assists: number;
+}
+
+// A single squad board change, sent over the squad board WebSocket.
+export type SquadOp =
+  | { op: "assign"; player: string; squad: SquadID }
+  | { op: "unassign"; player: string }
+  | { op: "add_squad"; squad: SquadID; name: string }
+  | { op: "rename_squad"; squad: SquadID; name: string }
+  | { op: "remove_squad"; squad: SquadID };
+
+export type SquadBoardEvent =
+  | { type: "state"; op: SquadOp | null; squads: Record<SquadID, string>; assignments: Record<string, SquadID> }
+  | { type: "error"; message: string };
+
 export interface TeamPageResponse {
   team_name: string;
   team_key: string;
@@ -25,10 +84,111 @@ export interface TeamPageResponse {
   weekly_schedule: number | null;
   default_squads: Record<SquadID, string>;
   players: Record<PlayerID, string>;
+  // Still listed in players so old games show their names, but left out of new games.
+  inactive_players: PlayerID[];
+  // Positions and shirt numbers, for players who have either.
+  profiles: Record<PlayerID, PublicProfile>;
   game: Game | null;
+  rsvp_counts: RsvpCounts | null;
+  rsvp_deadline_hours: number | null;
+  // When RSVPs for the current game lock. After this only admins can change them.
+  rsvp_deadline: string | null;
+  guest_limit: number | null;
+  results_open: boolean;
+  mvp_voting_hours: number | null;
+  mvp_voting_opens: string | null;
+  mvp_voting_closes: string | null;
+  // Only present in admin responses.
+  ratings?: Record<PlayerID, number>;
+  // Only present in admin responses.
+  join_requests?: JoinRequest[];
+  // Only present in admin responses.
+  player_details?: Record<PlayerID, Player>;
+}
+
+export interface PublicProfile {
+  position: string | null;
+  shirt_number: number | null;
+}
+
+export interface Player extends PublicProfile {
+  name: string;
+  active: boolean;
+  phone: string | null;
+  email: string | null;
+  notes: string | null;
+  notifications: Notifications;
+}
+
+// Which emails a player gets at their address.
+export interface Notifications {
+  reminders: boolean;
+  game_off: boolean;
+  rsvp_changes: boolean;
+}
+
+export interface JoinRequest {
+  id: string;
+  name: string;
+  requested_at: string;
 }
 
 export interface NewTeamResponse {
   team_key: string;
   team_secret: string;
 }
+
+export interface AutoSquadsResponse {
+  seed: number;
+  squads: Record<SquadID, string>;
+  assignments: Record<string, SquadID>;
+}
+
+export interface LeaderboardEntry {
+  player_id: PlayerID;
+  name: string;
+  played: number;
+  goals: number;
+  assists: number;
+  wins: number;
+  draws: number;
+  losses: number;
+}
+
+export interface LeaderboardResponse {
+  season: number | null;
+  entries: LeaderboardEntry[];
+}
+
+export interface NoShowEntry {
+  player_id: PlayerID;
+  name: string;
+  said_playing: number;
+  no_shows: number;
+  rate: number;
+}
+
+export interface PromoteGuestResponse {
+  player_id: PlayerID;
+  // How many games the guest's history was carried over from.
+  games: number;
+}
+
+export interface RosterImportResponse {
+  // False for dry runs and imports with errors.
+  imported: boolean;
+  added: Player[];
+  // Rows skipped because the name is already on the roster (player_id set) or earlier in the file.
+  duplicates: { line: number; name: string; player_id: PlayerID | null }[];
+  errors: { line: number; message: string }[];
+}
+
+// A stored document exactly as it is, for fixing one the server can't read.
+export interface RawDocument {
+  kind: "team" | "game";
+  key: string;
+  // null if the document is missing.
+  document: string | null;
+  // Why the document can't be read, if it can't.
+  error: string | null;
+}